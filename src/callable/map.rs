@@ -1,6 +1,7 @@
 use crate::{
     data::list::List,
-    types::{BaseType, FunctionType, Type},
+    diagnostic::{Diagnostic, DiagnosticKind},
+    types::{FunctionType, Type},
     value::Value,
 };
 
@@ -9,39 +10,49 @@ use super::Callable;
 #[derive(Debug)]
 pub struct Map {}
 
+fn map_error() -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticKind::Runtime,
+        String::from("map expects a list and a function"),
+    )
+}
+
 impl Callable for Map {
-    fn call(&mut self, arguments: Vec<Value>) -> Result<Value, String> {
-        if let Some(Value::List(list)) = arguments.get(0) {
-            if let Some(Value::Function(callable)) = arguments.get(1) {
-                return Ok(Value::List(List {
-                    vector: list
-                        .vector
-                        .iter()
-                        .map(|elem| callable.borrow().clone().call([elem.clone()].to_vec()))
-                        .collect::<Result<Vec<Value>, String>>()?,
-                }));
-            }
+    fn call(&mut self, arguments: Vec<Value>) -> Result<Value, Diagnostic> {
+        if let Some(Value::Function(callable)) = arguments.get(1) {
+            let Some(elements) = arguments.first().and_then(Value::elements) else {
+                return Err(map_error());
+            };
+
+            return Ok(Value::List(List {
+                vector: elements
+                    .map(|elem| callable.borrow().clone().call([elem].to_vec()))
+                    .collect::<Result<Vec<Value>, Diagnostic>>()?,
+            }));
         }
 
-        Err(format!("Dumbass"))
+        Err(map_error())
     }
 
-    fn get_type(&mut self) -> Result<FunctionType, String> {
+    fn get_type(&mut self) -> Result<FunctionType, Diagnostic> {
+        // <T, U>(xs: T[], f: (T) => U): U[]
+        let element = Type::Variable(String::from("T"));
+        let mapped = Type::Variable(String::from("U"));
         Ok(FunctionType::Literal(
             [
-                Type::List(Box::from(Type::BaseType(BaseType::Any))),
+                Type::List(Box::from(element.clone())),
                 Type::Function(Box::from(FunctionType::Literal(
-                    [Type::BaseType(BaseType::Any)].to_vec(),
-                    Type::BaseType(BaseType::Any),
+                    [element].to_vec(),
+                    mapped.clone(),
                 ))),
             ]
             .to_vec(),
-            Type::List(Box::from(Type::BaseType(BaseType::Any))),
+            Type::List(Box::from(mapped)),
         ))
     }
 
     fn clone(&self) -> Box<dyn Callable> {
-        return Box::from(Map {});
+        Box::from(Map {})
     }
 
     fn signature(&self) -> String {
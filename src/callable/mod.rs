@@ -1,11 +1,11 @@
 pub mod map;
 pub mod print;
 
-use crate::{types::FunctionType, value::Value};
+use crate::{diagnostic::Diagnostic, types::FunctionType, value::Value};
 
 pub trait Callable: std::fmt::Debug {
     fn signature(&self) -> String;
-    fn call(&mut self, arguments: Vec<Value>) -> Result<Value, String>;
-    fn get_type(&mut self) -> Result<FunctionType, String>;
+    fn call(&mut self, arguments: Vec<Value>) -> Result<Value, Diagnostic>;
+    fn get_type(&mut self) -> Result<FunctionType, Diagnostic>;
     fn clone(&self) -> Box<dyn Callable>;
 }
@@ -1,4 +1,5 @@
 use crate::{
+    diagnostic::Diagnostic,
     types::{BaseType, FunctionType, Type},
     value::Value,
 };
@@ -9,13 +10,17 @@ use super::Callable;
 pub struct Print {}
 
 impl Callable for Print {
-    fn call(&mut self, arguments: Vec<Value>) -> Result<Value, String> {
+    fn call(&mut self, arguments: Vec<Value>) -> Result<Value, Diagnostic> {
+        let arguments = arguments
+            .into_iter()
+            .map(Value::expect_value)
+            .collect::<Result<Vec<Value>, Diagnostic>>()?;
         let s: String = arguments.iter().map(|v| v.to_string()).collect();
         println!("{}", s);
         Ok(Value::String(s))
     }
 
-    fn get_type(&mut self) -> Result<FunctionType, String> {
+    fn get_type(&mut self) -> Result<FunctionType, Diagnostic> {
         Ok(FunctionType::ArrayArgs(
             Type::BaseType(BaseType::Any),
             Type::BaseType(BaseType::String),
@@ -23,7 +28,7 @@ impl Callable for Print {
     }
 
     fn clone(&self) -> Box<dyn Callable> {
-        return Box::from(Print {});
+        Box::from(Print {})
     }
 
     fn signature(&self) -> String {
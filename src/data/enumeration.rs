@@ -0,0 +1,38 @@
+use crate::{diagnostic::Diagnostic, value::Value};
+
+use super::tuple::elements_equal;
+
+/// A value of an enum, `Shape::Rect(1, 2)` has the values `1` and `2`
+#[derive(Clone)]
+pub struct Variant {
+    pub enum_name: String,
+    pub variant: String,
+    pub values: Vec<Value>,
+}
+
+impl Variant {
+    pub fn equals(&self, other: &Variant) -> Result<bool, Diagnostic> {
+        Ok(self.enum_name == other.enum_name
+            && self.variant == other.variant
+            && elements_equal(&self.values, &other.values)?)
+    }
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.values.is_empty() {
+            true => write!(f, "{}::{}", self.enum_name, self.variant),
+            false => write!(
+                f,
+                "{}::{}({})",
+                self.enum_name,
+                self.variant,
+                self.values
+                    .iter()
+                    .map(|value| value.to_log_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
@@ -1,13 +1,112 @@
-use crate::value::Value;
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    value::Value,
+};
 
 #[derive(Clone)]
 pub struct List {
     pub vector: Vec<Value>,
 }
 
-impl List {
-    pub fn to_string(&self) -> String {
+/// Turns a possibly negative index into a position, negative indices count
+/// back from the end of the list (`-1` is the last element)
+pub fn resolve_index(length: usize, index: i64) -> Option<usize> {
+    resolve_position(length as i128, index).map(|position| position as usize)
+}
+
+/// `resolve_index` for sequences that can be longer than a `usize`, like ranges
+pub fn resolve_position(length: i128, index: i64) -> Option<i128> {
+    let position = match index < 0 {
+        true => length + index as i128,
+        false => index as i128,
+    };
+
+    (0..length).contains(&position).then_some(position)
+}
+
+/// Turns the bounds of a slice into a range of positions, bounds may be
+/// negative and `end` is exclusive
+pub fn resolve_slice(
+    length: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<(usize, usize), Diagnostic> {
+    resolve_bounds(length as i128, start, end).map(|(start, end)| (start as usize, end as usize))
+}
+
+/// `resolve_slice` for sequences that can be longer than a `usize`
+pub fn resolve_bounds(
+    length: i128,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<(i128, i128), Diagnostic> {
+    let resolve_bound = |bound: i64| {
+        let position = match bound < 0 {
+            true => length + bound as i128,
+            false => bound as i128,
+        };
+
+        (0..=length).contains(&position).then_some(position)
+    };
+
+    let start_position = start.map_or(Some(0), resolve_bound);
+    let end_position = end.map_or(Some(length), resolve_bound);
+
+    match (start_position, end_position) {
+        (Some(start_position), Some(end_position)) if start_position <= end_position => {
+            Ok((start_position, end_position))
+        }
+        _ => Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            format!(
+                "Slice {}..{} is out of range for length {}",
+                start.map_or(String::new(), |start| start.to_string()),
+                end.map_or(String::new(), |end| end.to_string()),
+                length
+            ),
+        )),
+    }
+}
+
+fn out_of_range(index: i64, length: usize) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticKind::Runtime,
         format!(
+            "Index {} is out of range for list of length {}",
+            index, length
+        ),
+    )
+}
+
+impl List {
+    pub fn get(&self, index: i64) -> Result<Value, Diagnostic> {
+        resolve_index(self.vector.len(), index)
+            .map(|position| self.vector[position].clone())
+            .ok_or(out_of_range(index, self.vector.len()))
+    }
+
+    pub fn set(&mut self, index: i64, value: Value) -> Result<(), Diagnostic> {
+        let Some(position) = resolve_index(self.vector.len(), index) else {
+            return Err(out_of_range(index, self.vector.len()));
+        };
+
+        self.vector[position] = value;
+        Ok(())
+    }
+
+    pub fn slice(&self, start: Option<i64>, end: Option<i64>) -> Result<List, Diagnostic> {
+        let (start, end) = resolve_slice(self.vector.len(), start, end)?;
+
+        Ok(List {
+            vector: self.vector[start..end].to_vec(),
+        })
+    }
+}
+
+impl std::fmt::Display for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "[{}]",
             self.vector
                 .iter()
@@ -17,3 +116,33 @@ impl List {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::value::Value;
+
+    use super::List;
+
+    fn list() -> List {
+        List {
+            vector: vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)],
+        }
+    }
+
+    #[test]
+    fn indexes_from_either_end() {
+        assert_eq!(list().get(0).unwrap().to_string(), "1");
+        assert_eq!(list().get(-1).unwrap().to_string(), "3");
+        assert!(list().get(3).is_err());
+        assert!(list().get(-4).is_err());
+    }
+
+    #[test]
+    fn slices_with_open_and_negative_bounds() {
+        assert_eq!(list().slice(Some(1), None).unwrap().to_string(), "[2,3]");
+        assert_eq!(list().slice(None, Some(-1)).unwrap().to_string(), "[1,2]");
+        assert_eq!(list().slice(Some(3), None).unwrap().to_string(), "[]");
+        assert!(list().slice(Some(2), Some(1)).is_err());
+        assert!(list().slice(Some(0), Some(4)).is_err());
+    }
+}
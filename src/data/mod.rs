@@ -1 +1,5 @@
+pub mod enumeration;
 pub mod list;
+pub mod range;
+pub mod structure;
+pub mod tuple;
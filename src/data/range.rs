@@ -0,0 +1,173 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    value::Value,
+};
+
+use super::list::{resolve_bounds, resolve_position, List};
+
+/// A lazy sequence of integers, `start..end by step` (or `start..=end by step`),
+/// elements are only computed when they are read
+#[derive(Clone, Debug)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub inclusive: bool,
+}
+
+impl Range {
+    pub fn new(start: i64, end: i64, step: i64, inclusive: bool) -> Result<Range, Diagnostic> {
+        if step == 0 {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                String::from("Range step cannot be 0"),
+            ));
+        }
+
+        Ok(Range {
+            start,
+            end,
+            step,
+            inclusive,
+        })
+    }
+
+    /// How many elements there are, an i128 as `i64::MIN..=i64::MAX` has
+    /// more than fit in an i64 or a usize
+    pub fn length(&self) -> i128 {
+        let (start, end, step) = (self.start as i128, self.end as i128, self.step as i128);
+        let distance = match step > 0 {
+            true => end - start,
+            false => start - end,
+        };
+        let step = step.abs();
+
+        match self.inclusive {
+            true if distance >= 0 => distance / step + 1,
+            false if distance > 0 => (distance + step - 1) / step,
+            _ => 0,
+        }
+    }
+
+    /// The element at `position`, which must be less than the length
+    fn nth(&self, position: i128) -> i64 {
+        (self.start as i128 + self.step as i128 * position) as i64
+    }
+
+    pub fn get(&self, index: i64) -> Result<Value, Diagnostic> {
+        match resolve_position(self.length(), index) {
+            Some(position) => Ok(Value::Integer(self.nth(position))),
+            None => Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!(
+                    "Index {} is out of range for range of length {}",
+                    index,
+                    self.length()
+                ),
+            )),
+        }
+    }
+
+    pub fn slice(&self, start: Option<i64>, end: Option<i64>) -> Result<Range, Diagnostic> {
+        let (start, end) = resolve_bounds(self.length(), start, end)?;
+
+        Ok(match start < end {
+            true => Range {
+                start: self.nth(start),
+                end: self.nth(end - 1),
+                step: self.step,
+                inclusive: true,
+            },
+            false => Range {
+                start: self.start,
+                end: self.start,
+                step: self.step,
+                inclusive: false,
+            },
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Value> + '_ {
+        (0..self.length()).map(|position| Value::Integer(self.nth(position)))
+    }
+
+    pub fn to_list(&self) -> List {
+        List {
+            vector: self.iter().collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match self.inclusive {
+            true => "..=",
+            false => "..",
+        };
+
+        match self.step {
+            1 => write!(f, "{}{}{}", self.start, operator, self.end),
+            step => write!(f, "{}{}{} by {}", self.start, operator, self.end, step),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Range;
+
+    fn elements(range: &Range) -> String {
+        range.to_list().to_string()
+    }
+
+    #[test]
+    fn computes_length_and_elements() {
+        assert_eq!(
+            elements(&Range::new(0, 5, 1, false).unwrap()),
+            "[0,1,2,3,4]"
+        );
+        assert_eq!(
+            elements(&Range::new(0, 5, 1, true).unwrap()),
+            "[0,1,2,3,4,5]"
+        );
+        assert_eq!(elements(&Range::new(0, 10, 3, false).unwrap()), "[0,3,6,9]");
+        assert_eq!(elements(&Range::new(5, 0, -2, true).unwrap()), "[5,3,1]");
+        assert_eq!(Range::new(5, 0, 1, false).unwrap().length(), 0);
+        assert_eq!(
+            Range::new(0, i64::MAX, 1, false).unwrap().length(),
+            i64::MAX as i128
+        );
+        assert_eq!(
+            Range::new(i64::MIN, i64::MAX, 1, true).unwrap().length(),
+            1 << 64
+        );
+        assert!(Range::new(0, 1, 0, false).is_err());
+    }
+
+    #[test]
+    fn indexes_and_slices_without_materializing() {
+        let range = Range::new(0, 1_000_000_000_000, 2, false).unwrap();
+        assert_eq!(range.get(-1).unwrap().to_string(), "999999999998");
+        assert_eq!(elements(&range.slice(Some(2), Some(5)).unwrap()), "[4,6,8]");
+        assert_eq!(range.slice(Some(3), Some(3)).unwrap().length(), 0);
+    }
+
+    #[test]
+    fn indexes_and_slices_ranges_longer_than_i64_max() {
+        let range = Range::new(0, i64::MAX, 1, true).unwrap();
+        assert_eq!(range.get(-1).unwrap().to_string(), i64::MAX.to_string());
+        assert_eq!(
+            range.get(i64::MAX).unwrap().to_string(),
+            i64::MAX.to_string()
+        );
+        assert_eq!(range.get(i64::MIN).unwrap().to_string(), "0");
+        assert_eq!(
+            elements(&range.slice(Some(-2), None).unwrap()),
+            format!("[{},{}]", i64::MAX - 1, i64::MAX)
+        );
+        assert_eq!(
+            elements(&range.slice(None, Some(i64::MIN + 2)).unwrap()),
+            "[0,1]"
+        );
+    }
+}
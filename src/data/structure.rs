@@ -0,0 +1,57 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    environment::Variable,
+    value::Value,
+};
+
+/// An instance of a struct, fields keep the order they were declared in
+#[derive(Clone)]
+pub struct Struct {
+    pub name: String,
+    pub fields: Vec<(String, Variable<Value>)>,
+}
+
+impl Struct {
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, variable)| variable.value.clone())
+    }
+
+    /// Replaces the value of a field, like `Environment::set` only mutable
+    /// fields can be replaced
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), Diagnostic> {
+        let Some((_, variable)) = self.fields.iter_mut().find(|(field, _)| field == name) else {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!("{} has no field named `{}`", self.name, name),
+            ));
+        };
+
+        if !variable.mutable {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Immutable,
+                format!("Cannot mutate const field ({}.{})", self.name, name),
+            ));
+        }
+
+        variable.value = value;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Struct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {{ {} }}",
+            self.name,
+            self.fields
+                .iter()
+                .map(|(name, variable)| format!("{}: {}", name, variable.value.to_log_string()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    value::Value,
+};
+
+use super::list::resolve_index;
+
+/// Compares values position by position, `null` is equal to `null`
+pub fn elements_equal(left: &[Value], right: &[Value]) -> Result<bool, Diagnostic> {
+    if left.len() != right.len() {
+        return Ok(false);
+    }
+
+    for (left, right) in left.iter().zip(right.iter()) {
+        let equal = match (left, right) {
+            (Value::Null, Value::Null) => true,
+            _ => matches!(left.clone().equals(right.clone())?, Value::Boolean(true)),
+        };
+
+        if !equal {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// A fixed length list, its elements can be read but never replaced
+#[derive(Clone)]
+pub struct Tuple {
+    pub elements: Vec<Value>,
+}
+
+impl Tuple {
+    pub fn get(&self, index: i64) -> Result<Value, Diagnostic> {
+        resolve_index(self.elements.len(), index)
+            .map(|position| self.elements[position].clone())
+            .ok_or(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!(
+                    "Index {} is out of range for tuple of length {}",
+                    index,
+                    self.elements.len()
+                ),
+            ))
+    }
+
+    pub fn equals(&self, other: &Tuple) -> Result<bool, Diagnostic> {
+        elements_equal(&self.elements, &other.elements)
+    }
+}
+
+impl std::fmt::Display for Tuple {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let elements = self
+            .elements
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+
+        // `(1,)` keeps a single element tuple distinguishable from `(1)`
+        match self.elements.len() {
+            1 => write!(f, "({},)", elements),
+            _ => write!(f, "({})", elements),
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagnosticKind {
+    Scan,
+    Syntax,
+    UndefinedVariable,
+    UndefinedType,
+    Redeclaration,
+    Immutable,
+    TypeMismatch,
+    NotCallable,
+    ArgumentCount,
+    Runtime,
+    Internal,
+}
+
+impl DiagnosticKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticKind::Scan => "E0001",
+            DiagnosticKind::Syntax => "E0002",
+            DiagnosticKind::UndefinedVariable => "E0100",
+            DiagnosticKind::Redeclaration => "E0101",
+            DiagnosticKind::Immutable => "E0102",
+            DiagnosticKind::UndefinedType => "E0103",
+            DiagnosticKind::TypeMismatch => "E0200",
+            DiagnosticKind::NotCallable => "E0201",
+            DiagnosticKind::ArgumentCount => "E0202",
+            DiagnosticKind::Runtime => "E0300",
+            DiagnosticKind::Internal => "E0900",
+        }
+    }
+}
+
+/// A secondary span pointing at something related to the diagnostic
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Labels and notes of a diagnostic, kept behind a box since most
+/// diagnostics have neither
+#[derive(Debug, Clone, Default)]
+pub struct Details {
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+/// An error (or warning) produced by any stage, from scanning to interpreting.
+///
+/// Errors raised below the expression level (e.g. in `Environment` or `Value`)
+/// are created without a span, the expression that triggered them fills it in
+/// with `or_span`.
+///
+/// Every stage returns `Result<_, Diagnostic>`, so it is kept small enough to
+/// move around cheaply.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Span>,
+    pub details: Option<Box<Details>>,
+}
+
+impl Diagnostic {
+    pub fn error(kind: DiagnosticKind, message: String) -> Diagnostic {
+        Diagnostic {
+            kind,
+            severity: Severity::Error,
+            message,
+            span: None,
+            details: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        self.span = Some(span);
+        self
+    }
+
+    /// Sets the primary span only if a more precise one was not already given
+    pub fn or_span(mut self, span: Span) -> Diagnostic {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: String) -> Diagnostic {
+        self.details_mut().labels.push(Label { span, message });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.details_mut().notes.push(note);
+        self
+    }
+
+    fn details_mut(&mut self) -> &mut Details {
+        self.details.get_or_insert_with(Box::default)
+    }
+
+    pub fn labels(&self) -> &[Label] {
+        self.details.as_ref().map_or(&[], |details| &details.labels)
+    }
+
+    pub fn notes(&self) -> &[String] {
+        self.details.as_ref().map_or(&[], |details| &details.notes)
+    }
+
+    /// Renders the diagnostic the same way rustc does, with an excerpt of the
+    /// offending source lines and carets underneath the spans
+    pub fn render(&self, source: &str, filename: &str) -> String {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut out = format!(
+            "{}[{}]: {}\n",
+            self.severity,
+            self.kind.code(),
+            self.message
+        );
+
+        let mut markers: Vec<(Span, char, String)> = Vec::new();
+        if let Some(span) = self.span {
+            markers.push((span, '^', String::new()));
+        }
+        for label in self.labels().iter() {
+            markers.push((label.span, '-', label.message.clone()));
+        }
+
+        let gutter = markers
+            .iter()
+            .map(|(span, _, _)| span.start.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(gutter);
+
+        if let Some(span) = self.span.or(markers.first().map(|(span, _, _)| *span)) {
+            out += &format!(
+                "{}--> {}:{}:{}\n",
+                pad, filename, span.start.line, span.start.column
+            );
+        }
+
+        if !markers.is_empty() {
+            out += &format!("{} |\n", pad);
+        }
+
+        let mut previous_line = None;
+        for (span, marker, message) in markers.iter() {
+            let line_number = span.start.line;
+            let Some(line) = lines.get(line_number - 1) else {
+                continue;
+            };
+
+            if previous_line != Some(line_number) {
+                out += &format!("{:>width$} | {}\n", line_number, line, width = gutter);
+            }
+            previous_line = Some(line_number);
+
+            let line_length = line.chars().count() + 1;
+            let end_column = match span.end.line == line_number {
+                true => span.end.column.min(line_length),
+                false => line_length,
+            };
+            let width = end_column.saturating_sub(span.start.column).max(1);
+
+            out += &format!(
+                "{} | {}{} {}\n",
+                pad,
+                " ".repeat(span.start.column - 1),
+                marker.to_string().repeat(width),
+                message
+            )
+            .replace(" \n", "\n");
+        }
+
+        if !markers.is_empty() && !self.notes().is_empty() {
+            out += &format!("{} |\n", pad);
+        }
+
+        for note in self.notes().iter() {
+            out += &format!("{} = note: {}\n", pad, note);
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::span::{Position, Span};
+
+    use super::{Diagnostic, DiagnosticKind};
+
+    fn span(line: usize, start: usize, end: usize) -> Span {
+        Span::new(
+            Position {
+                offset: 0,
+                line,
+                column: start,
+            },
+            Position {
+                offset: 0,
+                line,
+                column: end,
+            },
+        )
+    }
+
+    #[test]
+    fn renders_source_excerpt() {
+        let source = "let x = 1;\nx = \"a\";";
+        let rendered = Diagnostic::error(DiagnosticKind::Immutable, String::from("not mutable"))
+            .with_span(span(2, 1, 8))
+            .with_label(span(1, 5, 6), String::from("declared here"))
+            .with_note(String::from("add `mutable`"))
+            .render(source, "test.aa");
+
+        assert_eq!(
+            rendered,
+            "error[E0102]: not mutable
+ --> test.aa:2:1
+  |
+2 | x = \"a\";
+  | ^^^^^^^
+1 | let x = 1;
+  |     - declared here
+  |
+  = note: add `mutable`
+"
+        );
+    }
+}
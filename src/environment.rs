@@ -1,4 +1,13 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    types::{Type, TypeDefinition},
+};
 
 #[derive(Debug, Clone)]
 pub struct Variable<T> {
@@ -6,8 +15,29 @@ pub struct Variable<T> {
     pub value: T,
 }
 
+/// `impl Interface for Type { ... }`, the methods are values when interpreting
+/// and types when type checking
+#[derive(Debug, Clone)]
+pub struct Implementation<T> {
+    pub interface: String,
+    pub for_type: Type,
+    pub methods: Vec<(String, T)>,
+}
+
 pub struct Environment<T> {
     pub variables: HashMap<String, Variable<T>>,
+    /// Types declared in this scope, kept apart from variables so a struct and
+    /// a variable can share a name
+    pub types: HashMap<String, TypeDefinition>,
+    pub implementations: Vec<Implementation<T>>,
+    /// What conditions have shown about the variables visible here, e.g.
+    /// inside `if x != null { ... }` the type of `x` without `null`
+    pub narrowed: HashMap<String, T>,
+    /// Variables declared here that a function body assigns to. A call can
+    /// run that assignment at any point, so conditions never narrow them
+    pub assigned_by_functions: HashSet<String>,
+    /// Set on the outermost scope of a function body
+    pub function_body: bool,
     pub parent: Option<Rc<RefCell<Environment<T>>>>,
 }
 
@@ -15,6 +45,11 @@ impl<T: std::clone::Clone + std::fmt::Debug> Environment<T> {
     pub fn new(parent: Option<&Rc<RefCell<Environment<T>>>>) -> Rc<RefCell<Environment<T>>> {
         Rc::new(RefCell::new(Environment {
             variables: HashMap::new(),
+            types: HashMap::new(),
+            implementations: Vec::new(),
+            narrowed: HashMap::new(),
+            assigned_by_functions: HashSet::new(),
+            function_body: false,
             parent: match parent {
                 None => None,
                 Some(rc) => Some(Rc::clone(rc)),
@@ -25,30 +60,43 @@ impl<T: std::clone::Clone + std::fmt::Debug> Environment<T> {
     pub fn pop(&self) -> Option<Rc<RefCell<Environment<T>>>> {
         match &self.parent {
             None => None,
-            Some(rc) => Some(Rc::clone(&rc)),
+            Some(rc) => Some(Rc::clone(rc)),
         }
     }
 
-    pub fn create(&mut self, key: String, value: Variable<T>) -> Result<Variable<T>, String> {
+    pub fn create(&mut self, key: String, value: Variable<T>) -> Result<Variable<T>, Diagnostic> {
         if self.variables.contains_key(&key) {
-            return Err(String::from("Cannot create variable that already exists"));
+            return Err(Diagnostic::error(
+                DiagnosticKind::Redeclaration,
+                format!("Cannot create variable ({}) that already exists", key),
+            ));
         }
 
+        // a new variable isn't what an earlier condition tested
+        self.narrowed.remove(&key);
         self.variables.insert(key.clone(), value.clone());
 
         Ok(value)
     }
 
-    pub fn set(&mut self, key: String, value: T) -> Result<Variable<T>, String> {
+    /// Whether `key` was created in this scope rather than one of its parents
+    pub fn declares(&self, key: &str) -> bool {
+        self.variables.contains_key(key)
+    }
+
+    pub fn set(&mut self, key: String, value: T) -> Result<Variable<T>, Diagnostic> {
         // Hack - should be done at compile time not runtime
         match self.get_with_depth(key.clone(), 0) {
-            None => Err(format!(
-                "Cannot set a variable ({}) that does not exist",
-                key.clone()
+            None => Err(Diagnostic::error(
+                DiagnosticKind::UndefinedVariable,
+                format!("Cannot set a variable ({}) that does not exist", key),
             )),
             Some((variable, 0)) => {
                 if !variable.mutable {
-                    return Err(String::from("Cannot mutate const variable"));
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::Immutable,
+                        format!("Cannot mutate const variable ({})", key),
+                    ));
                 }
 
                 let new_variable = Variable {
@@ -63,7 +111,12 @@ impl<T: std::clone::Clone + std::fmt::Debug> Environment<T> {
         }
     }
 
-    fn set_at_depth(&mut self, key: String, value: T, depth: u16) -> Result<Variable<T>, String> {
+    fn set_at_depth(
+        &mut self,
+        key: String,
+        value: T,
+        depth: u16,
+    ) -> Result<Variable<T>, Diagnostic> {
         let mut i = 1;
         let mut env = self.pop();
         while i < depth {
@@ -72,7 +125,10 @@ impl<T: std::clone::Clone + std::fmt::Debug> Environment<T> {
         }
 
         match env {
-            None => Err(String::from("Cannot set in a parent that does not exist")),
+            None => Err(Diagnostic::error(
+                DiagnosticKind::Internal,
+                String::from("Cannot set in a parent that does not exist"),
+            )),
             Some(e) => e.borrow_mut().set(key, value),
         }
     }
@@ -87,16 +143,150 @@ impl<T: std::clone::Clone + std::fmt::Debug> Environment<T> {
         }
     }
 
+    /// The variable as it was declared, without what conditions showed about it
+    pub fn declared(&self, key: &str) -> Option<Variable<T>> {
+        self.get_with_depth(key.to_owned(), 0)
+            .map(|(variable, _)| variable)
+    }
+
     pub fn get(&self, key: String) -> Option<Variable<T>> {
-        match self.variables.get(&key).cloned() {
+        let variable = match self.variables.get(&key).cloned() {
+            None => match self.pop() {
+                Some(env) => env.borrow().get(key.clone()),
+                None => None,
+            },
+            some => some,
+        };
+
+        // narrowing changes what the value is known to be, not whether it can be set
+        match self.narrowed.get(&key) {
+            Some(value) if !self.assigned_by_function(&key) => variable.map(|variable| Variable {
+                mutable: variable.mutable,
+                value: value.clone(),
+            }),
+            _ => variable,
+        }
+    }
+
+    /// Gives `key` a narrower value for the rest of this scope, see
+    /// `Environment::narrowed`
+    pub fn narrow(&mut self, key: String, value: T) {
+        self.narrowed.insert(key, value);
+    }
+
+    /// Removes what is known about `key` in every scope up to the one that
+    /// declares it, after it's assigned a new value
+    pub fn forget_narrowing(&mut self, key: &str) {
+        self.forget_narrowing_from(key, false)
+    }
+
+    fn forget_narrowing_from(&mut self, key: &str, in_function: bool) {
+        self.narrowed.remove(key);
+        if self.variables.contains_key(key) {
+            if in_function {
+                self.assigned_by_functions.insert(key.to_owned());
+            }
+            return;
+        }
+
+        if let Some(env) = self.pop() {
+            env.borrow_mut()
+                .forget_narrowing_from(key, in_function || self.function_body);
+        }
+    }
+
+    /// Whether a function body assigns to the variable `key`, see
+    /// `Environment::assigned_by_functions`
+    pub fn assigned_by_function(&self, key: &str) -> bool {
+        if self.variables.contains_key(key) {
+            return self.assigned_by_functions.contains(key);
+        }
+
+        match self.pop() {
+            Some(env) => env.borrow().assigned_by_function(key),
+            None => false,
+        }
+    }
+
+    /// The variables something is known about here or in a parent scope,
+    /// see `Environment::narrowed`
+    pub fn narrowed_keys(&self) -> Vec<String> {
+        let mut keys = match self.pop() {
+            Some(env) => env.borrow().narrowed_keys(),
+            None => Vec::new(),
+        };
+        keys.extend(
+            self.narrowed
+                .keys()
+                .filter(|key| !self.assigned_by_function(key))
+                .cloned(),
+        );
+        keys
+    }
+
+    pub fn create_type(
+        &mut self,
+        name: String,
+        definition: TypeDefinition,
+    ) -> Result<(), Diagnostic> {
+        if self.types.contains_key(&name) {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Redeclaration,
+                format!("Cannot declare type ({}) that already exists", name),
+            ));
+        }
+
+        self.types.insert(name, definition);
+
+        Ok(())
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<TypeDefinition> {
+        match self.types.get(name).cloned() {
             None => match self.pop() {
-                Some(env) => env.borrow().get(key),
+                Some(env) => env.borrow().get_type(name),
                 None => None,
             },
             some => some,
         }
     }
 
+    pub fn create_implementation(&mut self, implementation: Implementation<T>) {
+        self.implementations.push(implementation);
+    }
+
+    /// The method called `name` from the innermost `impl` whose type satisfies
+    /// `implemented_for`, only looking at impls of `interface` when given
+    pub fn get_method(
+        &self,
+        name: &str,
+        interface: Option<&str>,
+        implemented_for: &dyn Fn(&Type) -> bool,
+    ) -> Option<T> {
+        let method = self
+            .implementations
+            .iter()
+            .rev()
+            .filter(|implementation| {
+                interface.is_none_or(|interface| implementation.interface == interface)
+                    && implemented_for(&implementation.for_type)
+            })
+            .find_map(|implementation| {
+                implementation
+                    .methods
+                    .iter()
+                    .find(|(method, _)| method == name)
+                    .map(|(_, method)| method.clone())
+            });
+
+        match method {
+            None => self
+                .pop()
+                .and_then(|env| env.borrow().get_method(name, interface, implemented_for)),
+            some => some,
+        }
+    }
+
     pub fn print(&self) -> String {
         format!(
             "{{\n\t\"variables\": {:#?},\n\t\"parent\": {}\n}}",
@@ -111,6 +301,8 @@ impl<T: std::clone::Clone + std::fmt::Debug> Environment<T> {
 
 #[cfg(test)]
 mod test {
+    use std::{cell::RefCell, rc::Rc};
+
     use crate::{environment::Variable, value::Value};
 
     use super::Environment;
@@ -158,4 +350,41 @@ mod test {
 
         println!("{}", other_new_env.borrow().print())
     }
+
+    #[test]
+    fn narrowing_lasts_until_assignment() {
+        let variable = |value: i64| Variable {
+            mutable: true,
+            value: Value::Integer(value),
+        };
+        let value = |env: &Rc<RefCell<Environment<Value>>>| {
+            env.borrow().get("x".to_owned()).unwrap().value.to_string()
+        };
+
+        let env = Environment::new(None);
+        env.borrow_mut()
+            .create("x".to_owned(), variable(1))
+            .unwrap();
+
+        let branch = Environment::new(Some(&env));
+        branch
+            .borrow_mut()
+            .narrow("x".to_owned(), Value::Integer(2));
+        let block = Environment::new(Some(&branch));
+
+        assert_eq!(value(&block), "2");
+        assert!(block.borrow().get("x".to_owned()).unwrap().mutable);
+        assert_eq!(value(&env), "1");
+
+        // a variable declared inside the branch isn't the narrowed one
+        let shadowing = Environment::new(Some(&branch));
+        shadowing
+            .borrow_mut()
+            .create("x".to_owned(), variable(3))
+            .unwrap();
+        assert_eq!(value(&shadowing), "3");
+
+        block.borrow_mut().forget_narrowing("x");
+        assert_eq!(value(&block), "1");
+    }
 }
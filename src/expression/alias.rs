@@ -0,0 +1,179 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{AliasType, BaseType, Type, TypeDefinition},
+    value::Value,
+};
+
+use super::{doc_to_string, Expression, Interpreter};
+
+/// `type Id = number | string`, names a type in the current scope. Generic
+/// aliases take type arguments like enums, `type Pair<T> = (T, T)`
+pub struct TypeAlias {
+    pub name: String,
+    pub doc: Option<String>,
+    pub parameters: Vec<String>,
+    pub type_: Type,
+    pub span: Span,
+}
+
+/// The members of a union, without expanding the aliases among them
+fn union_members(type_: &Type) -> Vec<&Type> {
+    match type_ {
+        Type::Or(left, right) => {
+            let mut members = union_members(left);
+            members.extend(union_members(right));
+            members
+        }
+        _ => vec![type_],
+    }
+}
+
+impl TypeAlias {
+    /// Creates the alias before resolving what it stands for, so uses of its
+    /// name inside the definition refer back to it. Aliases declared later in
+    /// the block already exist from `hoist`, without a definition
+    fn create<T: Clone + std::fmt::Debug>(
+        &self,
+        interpreter: &mut Interpreter<T>,
+    ) -> Result<AliasType, Diagnostic> {
+        let hoisted = interpreter
+            .environment
+            .borrow()
+            .types
+            .get(&self.name)
+            .cloned();
+        if let Some(TypeDefinition::Alias(alias)) = hoisted {
+            if !alias.is_defined() {
+                return Ok(alias);
+            }
+        }
+
+        let alias = AliasType::new(self.name.clone(), self.parameters.clone());
+        interpreter
+            .create_type(self.name.clone(), TypeDefinition::Alias(alias.clone()))
+            .map_err(|err| err.or_span(self.span))?;
+        Ok(alias)
+    }
+
+    /// The aliases leading from this one back to itself without a list,
+    /// tuple, function or generic in between, e.g. `B = A = B` for
+    /// `type A = B; type B = A;`
+    fn cycle<T: Clone + std::fmt::Debug>(
+        &self,
+        interpreter: &Interpreter<T>,
+    ) -> Option<Vec<String>> {
+        let mut visited: Vec<String> = Vec::new();
+        let mut pending = vec![(self.type_.clone(), vec![self.name.clone()])];
+
+        while let Some((type_, path)) = pending.pop() {
+            for member in union_members(&type_) {
+                let alias = match member {
+                    Type::Alias(alias, _) => alias.clone(),
+                    Type::Named(name, _) => match interpreter.environment.borrow().get_type(name) {
+                        Some(TypeDefinition::Alias(alias)) => alias,
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+
+                let mut path = path.clone();
+                path.push(alias.name.clone());
+                if alias.name == self.name {
+                    return Some(path);
+                }
+
+                if visited.contains(&alias.name) {
+                    continue;
+                }
+                visited.push(alias.name.clone());
+
+                let definition = alias.definition.borrow().clone();
+                if let Some(definition) = definition {
+                    pending.push((definition, path));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn declare<T: Clone + std::fmt::Debug>(
+        &self,
+        interpreter: &mut Interpreter<T>,
+    ) -> Result<(), Diagnostic> {
+        let alias = self.create(interpreter)?;
+
+        // `type Loop = integer | Loop` would never get to a type, a recursive
+        // use has to be inside a list, tuple, function or generic
+        if let Some(cycle) = self.cycle(interpreter) {
+            let mut diagnostic = Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("Type alias {} is defined as itself", self.name),
+            )
+            .with_span(self.span);
+            if cycle.len() > 2 {
+                diagnostic = diagnostic.with_note(format!(
+                    "through the other aliases it stands for, `{}`",
+                    cycle.join(" = ")
+                ));
+            }
+
+            return Err(diagnostic.with_note(format!(
+                "recursive uses of an alias have to be nested, e.g. `type {} = integer | {}[]`",
+                self.name, self.name
+            )));
+        }
+
+        let definition = interpreter.resolve(&self.type_);
+        *alias.definition.borrow_mut() = Some(definition);
+        Ok(())
+    }
+
+    /// Creates the alias without a definition, see `TypeAlias::create`
+    fn hoist_alias<T: Clone + std::fmt::Debug>(
+        &self,
+        interpreter: &mut Interpreter<T>,
+    ) -> Result<(), Diagnostic> {
+        let alias = AliasType::new(self.name.clone(), self.parameters.clone());
+        interpreter
+            .create_type(self.name.clone(), TypeDefinition::Alias(alias))
+            .map_err(|err| err.or_span(self.span))
+    }
+}
+
+impl Expression for TypeAlias {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        self.declare(type_interpreter)?;
+
+        Ok(Type::BaseType(BaseType::Null))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        self.declare(interpreter)?;
+
+        Ok(Value::Null)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn hoist(&self, interpreter: &mut Interpreter<Value>) -> Result<(), Diagnostic> {
+        self.hoist_alias(interpreter)
+    }
+
+    fn hoist_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<(), Diagnostic> {
+        self.hoist_alias(type_interpreter)
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"TypeAlias\", \"name\": \"{}\", \"doc\": {}, \"parameters\": {:?}, \"aliased\": \"{:?}\" }}",
+            self.name,
+            doc_to_string(&self.doc),
+            self.parameters,
+            self.type_
+        )
+    }
+}
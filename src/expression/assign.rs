@@ -1,53 +1,295 @@
-use crate::{types::Type, value::Value};
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
 
 use super::{Expression, Interpreter};
 
+/// A step into the assigned variable, e.g. the `[0]` in `xs[0] = 1` or the
+/// `.y` in `point.y = 1`
+pub enum Accessor {
+    Index(Box<dyn Expression>),
+    Field(String),
+}
+
+/// An accessor once its index has been evaluated
+enum PathStep {
+    Index(i64),
+    Field(String),
+}
+
+impl std::fmt::Display for Accessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Accessor::Index(index) => write!(
+                f,
+                "{{ \"type\": \"Index\", \"index\": {} }}",
+                index.to_string()
+            ),
+            Accessor::Field(name) => {
+                write!(f, "{{ \"type\": \"Field\", \"name\": \"{}\" }}", name)
+            }
+        }
+    }
+}
+
 pub struct Assign {
     pub key: String,
+    pub accessors: Vec<Accessor>,
     pub value: Box<dyn Expression>,
+    pub span: Span,
+}
+
+/// Replaces the element at the end of `path` inside `target`, rebuilding every
+/// list and struct along the way
+fn assign_path(target: Value, path: &[PathStep], value: Value) -> Result<Value, Diagnostic> {
+    let Some((step, rest)) = path.split_first() else {
+        return Ok(value);
+    };
+
+    let index = match step {
+        PathStep::Index(index) => index,
+        PathStep::Field(name) => {
+            let Value::Struct(mut structure) = target else {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!("Cannot assign into {} by field", target.to_log_string()),
+                ));
+            };
+
+            let Some(field) = structure.get(name) else {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!("{} has no field named `{}`", structure.name, name),
+                ));
+            };
+
+            structure.set(name, assign_path(field, rest, value)?)?;
+            return Ok(Value::Struct(structure));
+        }
+    };
+
+    let mut list = match target {
+        Value::List(list) => list,
+        // ranges are never materialised, copying `0..1_000_000_000` to change
+        // one element would allocate all of it
+        Value::Range(range) => {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!("Cannot assign into the range {} by index", range),
+            )
+            .with_note(String::from(
+                "ranges are read only, `map` it into a list to change its elements",
+            )))
+        }
+        target => {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!("Cannot assign into {} by index", target.to_log_string()),
+            ))
+        }
+    };
+
+    let element = assign_path(list.get(*index)?, rest, value)?;
+    list.set(*index, element)?;
+    Ok(Value::List(list))
 }
 
 impl Expression for Assign {
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
-        let assigned_type = self.value.check_type(type_interpreter)?;
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let assigned_type = type_interpreter.check_operand(self.value.as_ref())?;
+        // a new value can be anything the variable was declared as, what a
+        // condition showed about the old one no longer holds
+        if self.accessors.is_empty() {
+            type_interpreter.forget_narrowing(&self.key);
+        }
+
         let variable = match type_interpreter.get(self.key.clone()) {
             None => {
-                return Err(format!(
-                    "Assigning variable that does not exist {}",
-                    self.key,
-                ))
+                return Err(Diagnostic::error(
+                    DiagnosticKind::UndefinedVariable,
+                    format!("Assigning variable that does not exist {}", self.key),
+                )
+                .with_span(self.span))
             }
             Some(value) => value,
         };
 
         if !variable.mutable {
-            return Err(format!("Variable \"{}\" is not mutable", self.key));
+            return Err(Diagnostic::error(
+                DiagnosticKind::Immutable,
+                format!("Variable \"{}\" is not mutable", self.key),
+            )
+            .with_span(self.span)
+            .with_note(String::from(
+                "variables are const by default, declare it with `let x: mutable = ...`",
+            )));
         }
 
-        if !assigned_type.is_sub_type_of(&variable.value) {
-            return Err(format!(
-                "Actual type assigned to \"{}\" was {:#?} which doesn't subtype {:#?}",
-                self.key, assigned_type, variable.value
-            ));
+        let mut target_type = variable.value;
+        for accessor in self.accessors.iter() {
+            match accessor {
+                Accessor::Index(index) => {
+                    let index_type = type_interpreter.check_operand(index.as_ref())?;
+                    if !index_type.is_sub_type_of(&Type::BaseType(BaseType::Integer)) {
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!(
+                                "Cannot index with a non integer value of type {}",
+                                index_type
+                            ),
+                        )
+                        .with_span(index.span()));
+                    }
+
+                    target_type = match target_type {
+                        Type::List(element_type) => *element_type,
+                        Type::Tuple(_) => {
+                            return Err(Diagnostic::error(
+                                DiagnosticKind::Immutable,
+                                format!(
+                                    "Cannot assign into a value of type {}, tuples are immutable",
+                                    target_type
+                                ),
+                            )
+                            .with_span(self.span))
+                        }
+                        target_type => {
+                            return Err(Diagnostic::error(
+                                DiagnosticKind::TypeMismatch,
+                                format!(
+                                    "Cannot assign by index into a value of type {}",
+                                    target_type
+                                ),
+                            )
+                            .with_span(self.span))
+                        }
+                    };
+                }
+                Accessor::Field(name) => {
+                    let Type::Named(struct_name, _) = &target_type else {
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!(
+                                "Cannot assign field `{}` of a value of type {}",
+                                name, target_type
+                            ),
+                        )
+                        .with_span(self.span));
+                    };
+
+                    let struct_type = type_interpreter
+                        .get_struct(struct_name)
+                        .map_err(|err| err.or_span(self.span))?;
+                    let Some(field) = struct_type.field(name) else {
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!("Struct {} has no field named `{}`", struct_name, name),
+                        )
+                        .with_span(self.span));
+                    };
+
+                    if !field.mutable {
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::Immutable,
+                            format!("Field `{}` of {} is not mutable", name, struct_name),
+                        )
+                        .with_span(self.span)
+                        .with_note(format!(
+                            "fields are const by default, declare it with `mutable {}: ...`",
+                            name
+                        )));
+                    }
+
+                    target_type = field.type_.clone();
+                }
+            }
         }
 
-        if let Some(_) = assigned_type.get_return_type() {
-            return Err(format!("Cannot assign variable to return value"));
+        if !assigned_type.is_sub_type_of(&target_type) {
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "Actual type assigned to \"{}\" was {} which doesn't subtype {}",
+                    self.key, assigned_type, target_type
+                ),
+            )
+            .with_span(self.value.span()));
         }
 
         Ok(assigned_type)
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let mut path = Vec::new();
+        for accessor in self.accessors.iter() {
+            match accessor {
+                Accessor::Index(index) => match index.interpret(interpreter)? {
+                    Value::Integer(index) => path.push(PathStep::Index(index)),
+                    signal if signal.is_signal() => return Ok(signal),
+                    _ => {
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::Runtime,
+                            String::from("Index did not resolve to an integer"),
+                        )
+                        .with_span(index.span()))
+                    }
+                },
+                Accessor::Field(name) => path.push(PathStep::Field(name.clone())),
+            }
+        }
+
         let actual_value = self.value.interpret(interpreter)?;
-        interpreter.set(self.key.clone(), actual_value.clone())?;
+        if actual_value.is_signal() {
+            return Ok(actual_value);
+        }
+
+        let root = match path.is_empty() {
+            true => actual_value.clone(),
+            false => {
+                let Some(current) = interpreter.get(self.key.clone()) else {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::UndefinedVariable,
+                        format!("Assigning variable that does not exist {}", self.key),
+                    )
+                    .with_span(self.span));
+                };
+                assign_path(current.value, &path, actual_value.clone())
+                    .map_err(|err| err.or_span(self.span))?
+            }
+        };
+
+        interpreter
+            .set(self.key.clone(), root)
+            .map_err(|err| err.or_span(self.span))?;
         Ok(actual_value)
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        names.push(self.key.clone());
+        for accessor in self.accessors.iter() {
+            if let Accessor::Index(index) = accessor {
+                index.assigned(names);
+            }
+        }
+        self.value.assigned(names);
+    }
+
     fn to_string(&self) -> String {
         format!(
-            "{{ \"type\": \"Assign\", \"key\": \"{}\", \"value\": {} }}",
+            "{{ \"type\": \"Assign\", \"key\": \"{}\", \"accessors\": [{}], \"value\": {} }}",
             self.key,
+            self.accessors
+                .iter()
+                .map(|accessor| accessor.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
             self.value.to_string()
         )
     }
@@ -1,18 +1,51 @@
 use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
     tokeniser::Operator,
     types::{BaseType, Type},
     value::Value,
 };
 
-use super::{Expression, Interpreter};
+use super::{
+    interface::{call_method, check_method_call},
+    Expression, Interpreter,
+};
 
 pub struct Binary {
     pub left: Box<dyn Expression>,
     pub operator: Operator,
     pub right: Box<dyn Expression>,
+    pub span: Span,
+}
+
+/// The interface and method an operator can be implemented with, e.g. `a + b`
+/// calls `a.add(b)` when `a` implements `Add`
+fn overload(operator: &Operator) -> Option<(&'static str, &'static str)> {
+    match operator {
+        Operator::Plus => Some(("Add", "add")),
+        Operator::Minus => Some(("Subtract", "subtract")),
+        Operator::Star => Some(("Multiply", "multiply")),
+        Operator::Slash => Some(("Divide", "divide")),
+        _ => None,
+    }
 }
 
 fn typeof_add(left: Type, right: Type) -> Type {
+    // a side without values, e.g. a recursive call whose type isn't known
+    // yet, is whatever the other side is
+    match (&left, &right) {
+        (Type::BaseType(BaseType::Never), Type::BaseType(BaseType::Never)) => return left,
+        (Type::BaseType(BaseType::Never), _) => return typeof_add(right.clone(), right),
+        (_, Type::BaseType(BaseType::Never)) => return typeof_add(left.clone(), left),
+        _ => (),
+    }
+
+    if left.is_sub_type_of(&Type::BaseType(BaseType::String))
+        || right.is_sub_type_of(&Type::BaseType(BaseType::String))
+    {
+        return Type::BaseType(BaseType::String);
+    }
+
     if let Type::BaseType(BaseType::Any) = left {
         return Type::Or(
             Box::from(Type::BaseType(BaseType::String)),
@@ -32,10 +65,21 @@ fn typeof_add(left: Type, right: Type) -> Type {
     if left.is_sub_type_of(&Type::BaseType(BaseType::Number))
         && right.is_sub_type_of(&Type::BaseType(BaseType::Number))
     {
-        return Type::BaseType(BaseType::Number);
+        return typeof_arithmetic(&left, &right);
     }
 
-    return Type::BaseType(BaseType::String);
+    Type::BaseType(BaseType::String)
+}
+
+/// Integers only stay integers when both sides are integers
+fn typeof_arithmetic(left: &Type, right: &Type) -> Type {
+    if left.is_sub_type_of(&Type::BaseType(BaseType::Integer))
+        && right.is_sub_type_of(&Type::BaseType(BaseType::Integer))
+    {
+        return Type::BaseType(BaseType::Integer);
+    }
+
+    Type::BaseType(BaseType::Number)
 }
 
 fn assert_type_for(
@@ -44,41 +88,100 @@ fn assert_type_for(
     left: Type,
     right: Type,
     return_type: Type,
-) -> Result<Type, String> {
+) -> Result<Type, Diagnostic> {
     if left.is_sub_type_of(&type_) && right.is_sub_type_of(&type_) {
         return Ok(return_type);
     }
 
-    return Err(format!("Cannot not {} non-{:?} values", symbol, type_));
+    Err(Diagnostic::error(
+        DiagnosticKind::TypeMismatch,
+        format!(
+            "Cannot {} non-{} values, got {} and {}",
+            symbol, type_, left, right
+        ),
+    ))
 }
 
 impl Expression for Binary {
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
-        let left = self.left.check_type(type_interpreter)?;
-        let right = self.right.check_type(type_interpreter)?;
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let left = type_interpreter.check_operand(self.left.as_ref())?;
+        // the right of `&` only runs when the left is true, and of `|` when it's false
+        let right = match self.operator {
+            Operator::And | Operator::Or => {
+                let narrowings = self
+                    .left
+                    .narrowings(type_interpreter, self.operator == Operator::And);
+                type_interpreter.with_narrowings(&narrowings, |type_interpreter| {
+                    type_interpreter.check_operand(self.right.as_ref())
+                })?
+            }
+            _ => type_interpreter.check_operand(self.right.as_ref())?,
+        };
 
-        match self.operator {
+        if let Some((interface, method)) = overload(&self.operator) {
+            let overloaded = check_method_call(
+                type_interpreter,
+                &left,
+                method,
+                Some(interface),
+                std::slice::from_ref(&right),
+            )
+            .map_err(|err| err.or_span(self.span))?;
+
+            if let Some(result) = overloaded {
+                return Ok(result);
+            }
+        }
+
+        // unannotated parameters take the type the operator works on
+        let operand_type = match self.operator {
+            Operator::Plus
+                if left.is_sub_type_of(&Type::BaseType(BaseType::String))
+                    || right.is_sub_type_of(&Type::BaseType(BaseType::String)) =>
+            {
+                None
+            }
+            Operator::Plus
+            | Operator::Minus
+            | Operator::Star
+            | Operator::Slash
+            | Operator::GreaterThan
+            | Operator::GreaterThanOrEqual
+            | Operator::LesserThan
+            | Operator::LesserThanOrEqual => Some(Type::BaseType(BaseType::Number)),
+            Operator::And | Operator::Or => Some(Type::BaseType(BaseType::Boolean)),
+            Operator::Equal | Operator::NotEqual | Operator::Not => None,
+        };
+        let (left, right) = match operand_type {
+            Some(operand_type) => (
+                type_interpreter.infer(&left, &operand_type),
+                type_interpreter.infer(&right, &operand_type),
+            ),
+            None => (left, right),
+        };
+
+        let result = match self.operator {
             Operator::Plus => Ok(typeof_add(left, right)),
             Operator::Minus => assert_type_for(
                 "subtract",
                 Type::BaseType(BaseType::Number),
-                left,
-                right,
-                Type::BaseType(BaseType::Number),
+                left.clone(),
+                right.clone(),
+                typeof_arithmetic(&left, &right),
             ),
             Operator::Star => assert_type_for(
                 "multiply",
                 Type::BaseType(BaseType::Number),
-                left,
-                right,
-                Type::BaseType(BaseType::Number),
+                left.clone(),
+                right.clone(),
+                typeof_arithmetic(&left, &right),
             ),
             Operator::Slash => assert_type_for(
                 "divide",
                 Type::BaseType(BaseType::Number),
-                left,
-                right,
-                Type::BaseType(BaseType::Number),
+                left.clone(),
+                right.clone(),
+                typeof_arithmetic(&left, &right),
             ),
             Operator::Equal => Ok(Type::BaseType(BaseType::Boolean)),
             Operator::NotEqual => Ok(Type::BaseType(BaseType::Boolean)),
@@ -124,40 +227,80 @@ impl Expression for Binary {
                 right,
                 Type::BaseType(BaseType::Boolean),
             ),
-            Operator::Not => Err("Cannot use ! (not) in a binary expression".to_owned()),
-        }
+            Operator::Not => Err(Diagnostic::error(
+                DiagnosticKind::Syntax,
+                "Cannot use ! (not) in a binary expression".to_owned(),
+            )),
+        };
+
+        result.map_err(|err| err.or_span(self.span))
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
         let left = self.left.interpret(interpreter)?;
+        if left.is_signal() {
+            return Ok(left);
+        }
 
         if let Value::Boolean(boolean) = left {
-            if self.operator == Operator::And && boolean == false {
+            if self.operator == Operator::And && !boolean {
                 return Ok(Value::Boolean(false));
             }
 
-            if self.operator == Operator::Or && boolean == true {
+            if self.operator == Operator::Or && boolean {
                 return Ok(Value::Boolean(true));
             }
         }
 
         let right = self.right.interpret(interpreter)?;
+        if right.is_signal() {
+            return Ok(right);
+        }
 
-        match self.operator {
-            Operator::Plus => left.add(right),
-            Operator::Minus => left.sub(right),
-            Operator::Star => left.mul(right),
-            Operator::Slash => left.div(right),
+        if let Some((interface, method)) = overload(&self.operator) {
+            let overloaded = call_method(
+                interpreter,
+                left.clone(),
+                method,
+                Some(interface),
+                vec![right.clone()],
+            )
+            .map_err(|err| err.or_span(self.span))?;
+
+            if let Some(result) = overloaded {
+                return Ok(result);
+            }
+        }
+
+        let result = match self.operator {
+            Operator::Plus => left + right,
+            Operator::Minus => left - right,
+            Operator::Star => left * right,
+            Operator::Slash => left / right,
             Operator::Equal => left.equals(right),
-            Operator::NotEqual => left.equals(right)?.not(),
+            Operator::NotEqual => !left.equals(right)?,
             Operator::GreaterThan => left.greater(right),
-            Operator::GreaterThanOrEqual => left.lesser(right)?.not(),
+            Operator::GreaterThanOrEqual => !left.lesser(right)?,
             Operator::LesserThan => left.lesser(right),
-            Operator::LesserThanOrEqual => left.greater(right)?.not(),
+            Operator::LesserThanOrEqual => !left.greater(right)?,
             Operator::And => left.and(right),
             Operator::Or => left.or(right),
-            Operator::Not => Err("Cannot use ! (not) in a binary expression".to_owned()),
-        }
+            Operator::Not => Err(Diagnostic::error(
+                DiagnosticKind::Syntax,
+                "Cannot use ! (not) in a binary expression".to_owned(),
+            )),
+        };
+
+        result.map_err(|err| err.or_span(self.span))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.left.assigned(names);
+        self.right.assigned(names);
     }
 
     fn to_string(&self) -> String {
@@ -168,4 +311,46 @@ impl Expression for Binary {
             self.right.to_string()
         )
     }
+
+    /// `x == null` and `x != null` narrow `x`, `&` and `|` narrow by both sides
+    /// when they show both are true or both are false
+    fn narrowings(
+        &self,
+        type_interpreter: &mut Interpreter<Type>,
+        truthy: bool,
+    ) -> Vec<(String, Type)> {
+        match self.operator {
+            Operator::Equal | Operator::NotEqual => {
+                let name = match (self.left.variable_name(), self.right.variable_name()) {
+                    (Some(name), _) if self.right.is_null_literal() => name,
+                    (_, Some(name)) if self.left.is_null_literal() => name,
+                    _ => return Vec::new(),
+                };
+
+                // `x == null` being true or `x != null` being false
+                let is_null = truthy == (self.operator == Operator::Equal);
+                type_interpreter.narrowing(name, &Type::BaseType(BaseType::Null), is_null)
+            }
+            Operator::And | Operator::Or if truthy == (self.operator == Operator::And) => {
+                let left = self.left.narrowings(type_interpreter, truthy);
+                // the right side is tested knowing what the left showed
+                let right = type_interpreter
+                    .with_narrowings(&left, |type_interpreter| {
+                        Ok(self.right.narrowings(type_interpreter, truthy))
+                    })
+                    .unwrap_or_default();
+
+                // but what the left showed no longer holds for a variable the
+                // right side assigns
+                let mut assigned = Vec::new();
+                self.right.assigned(&mut assigned);
+
+                left.into_iter()
+                    .filter(|(name, _)| !assigned.contains(name))
+                    .chain(right)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
 }
@@ -1,4 +1,6 @@
 use crate::{
+    diagnostic::Diagnostic,
+    span::Span,
     types::{BaseType, Type},
     value::Value,
 };
@@ -7,11 +9,15 @@ use super::{Expression, Interpreter};
 
 pub struct Body {
     pub body: Vec<Box<dyn Expression>>,
+    pub span: Span,
 }
 
 impl Expression for Body {
-    fn check_type(&self, type_checker: &mut Interpreter<Type>) -> Result<Type, String> {
+    fn check_type(&self, type_checker: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
         type_checker.push_environment();
+        for expression in self.body.iter() {
+            expression.hoist_type(type_checker)?;
+        }
 
         let mut return_types: Vec<Type> = Vec::new();
 
@@ -32,7 +38,7 @@ impl Expression for Body {
 
         let mut block_return_type: Option<Type> = last_type;
         for return_type in return_types {
-            if let None = block_return_type.clone() {
+            if block_return_type.clone().is_none() {
                 block_return_type = Some(return_type);
                 continue;
             }
@@ -53,10 +59,13 @@ impl Expression for Body {
         })
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
         let mut last_result = Value::Null;
 
         interpreter.push_environment();
+        for expression in self.body.iter() {
+            expression.hoist(interpreter)?;
+        }
         for expression in self.body.iter() {
             last_result = expression.interpret(interpreter)?;
 
@@ -64,10 +73,26 @@ impl Expression for Body {
                 interpreter.pop_environment()?;
                 return Ok(*return_value);
             }
+
+            // loop signals pass through blocks until they reach their loop
+            if let Value::Break(..) | Value::Continue(_) = last_result {
+                interpreter.pop_environment()?;
+                return Ok(last_result);
+            }
         }
         interpreter.pop_environment()?;
 
-        return Ok(last_result);
+        Ok(last_result)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        for expression in self.body.iter() {
+            expression.assigned(names);
+        }
     }
 
     fn to_string(&self) -> String {
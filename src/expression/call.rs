@@ -1,41 +1,115 @@
-use crate::{types::Type, value::Value};
+use std::ops::ControlFlow;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{FunctionType, Type},
+    value::Value,
+};
 
 use super::{Expression, Interpreter};
 
 pub struct Call {
     pub target: Box<dyn Expression>,
     pub arguments: Vec<Box<dyn Expression>>,
+    pub span: Span,
 }
 
 impl Expression for Call {
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
-        let target = self.target.check_type(type_interpreter)?;
-        let argument_types = self
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let target = type_interpreter.check_operand(self.target.as_ref())?;
+        let mut argument_types = self
             .arguments
             .iter()
-            .map(|arg| arg.check_type(type_interpreter))
-            .collect::<Result<Vec<Type>, String>>()?;
+            .map(|arg| type_interpreter.check_operand(arg.as_ref()))
+            .collect::<Result<Vec<Type>, Diagnostic>>()?;
+
+        let called = |err: Diagnostic| {
+            err.or_span(self.span)
+                .with_label(self.target.span(), String::from("function called here"))
+        };
+
+        // calling an unannotated parameter makes it a function of the
+        // arguments, returning whatever the rest of the body decides
+        let target = match target {
+            Type::Variable(name) if Type::is_inferred(&name) => {
+                let function_type = Type::Function(Box::from(FunctionType::Literal(
+                    argument_types.clone(),
+                    Type::Variable(format!("{}()", name)),
+                )));
+                type_interpreter
+                    .inferred
+                    .insert(name, function_type.clone());
+                function_type
+            }
+            target => target,
+        };
 
         if let Type::Function(function_type) = target {
-            return function_type.apply(argument_types);
+            // and arguments that are unannotated parameters take the types
+            // the function expects
+            let expected_type = match function_type.as_ref() {
+                FunctionType::WithBody(function_instance) => function_instance
+                    .borrow()
+                    .clone()
+                    .get_type()
+                    .map_err(called)?,
+                function_type => function_type.clone(),
+            };
+            if let FunctionType::Literal(expected_argument_types, _) = &expected_type {
+                for (argument_type, expected_argument_type) in argument_types
+                    .iter_mut()
+                    .zip(expected_argument_types.iter())
+                {
+                    *argument_type = type_interpreter.infer(argument_type, expected_argument_type);
+                }
+            }
+
+            return expected_type.apply(argument_types).map_err(called);
         }
 
-        print!("{:#?}", target);
-        Err(String::from("Cannot call non function"))
+        Err(Diagnostic::error(
+            DiagnosticKind::NotCallable,
+            format!("Cannot call non function of type {}", target),
+        )
+        .with_span(self.target.span()))
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
-        if let Value::Function(callee) = self.target.interpret(interpreter)? {
-            let mut arguments = Vec::new();
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let target = self.target.interpret(interpreter)?;
+        if target.is_signal() {
+            return Ok(target);
+        }
+
+        if let Value::Function(callee) = target {
+            let arguments = match interpreter.operands(&self.arguments)? {
+                ControlFlow::Continue(arguments) => arguments,
+                ControlFlow::Break(signal) => return Ok(signal),
+            };
 
-            for argument in self.arguments.iter() {
-                let value = argument.interpret(interpreter)?;
-                arguments.push(value);
-            }
+            return callee
+                .borrow()
+                .clone()
+                .call(arguments)
+                .map_err(|err| err.or_span(self.span));
+        }
+
+        Err(Diagnostic::error(
+            DiagnosticKind::NotCallable,
+            format!("Cannot call non function value {}", target.to_log_string()),
+        )
+        .with_span(self.target.span()))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
 
-            return callee.borrow().clone().call(arguments);
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.target.assigned(names);
+        for argument in self.arguments.iter() {
+            argument.assigned(names);
         }
-        panic!("Expression(Call).interpret - not implemented!")
     }
 
     fn to_string(&self) -> String {
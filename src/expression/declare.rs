@@ -1,64 +1,200 @@
 use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
     environment::Variable,
+    pattern::Pattern,
+    span::Span,
     types::{BaseType, Type},
     value::Value,
 };
 
-use super::{Expression, Interpreter};
+use super::{doc_to_string, Expression, Interpreter};
 
 pub struct Declare {
-    pub key: String,
+    pub pattern: Pattern,
+    pub doc: Option<String>,
     pub assigned_type: Option<Variable<Type>>,
+    /// Set for `let name = (...) => ...`, which is created before the rest of
+    /// its block runs
+    pub hoisted: bool,
     pub value: Box<dyn Expression>,
+    pub span: Span,
+}
+
+impl Declare {
+    /// The declared names, for error messages
+    fn name(&self) -> String {
+        self.pattern.names().join(", ")
+    }
+
+    /// The name of a hoisted function already created in the current scope
+    fn hoisted_name<T: Clone + std::fmt::Debug>(
+        &self,
+        interpreter: &Interpreter<T>,
+    ) -> Option<String> {
+        match &self.pattern {
+            Pattern::Binding(name) if self.hoisted && interpreter.declares(name) => {
+                Some(name.clone())
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Expression for Declare {
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        if let Some(name) = self.hoisted_name(type_interpreter) {
+            return Ok(type_interpreter.get(name).unwrap().value);
+        }
+
         let mut actual_type = Variable {
             mutable: false,
-            value: self.value.check_type(type_interpreter)?,
+            value: type_interpreter.check_operand(self.value.as_ref())?,
         };
 
-        if let Some(assigned_type) = self.assigned_type.as_ref() {
+        let assigned_type = self.assigned_type.as_ref().map(|assigned_type| Variable {
+            mutable: assigned_type.mutable,
+            value: type_interpreter.resolve(&assigned_type.value),
+        });
+
+        if let Some(assigned_type) = assigned_type {
             if assigned_type
                 .value
                 .is_sub_type_of(&Type::BaseType(BaseType::Infer))
             {
                 actual_type.mutable = assigned_type.mutable;
+
+                if let (true, Type::List(element_type)) = (actual_type.mutable, &actual_type.value)
+                {
+                    if let Type::BaseType(BaseType::Never) = **element_type {
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!("Cannot infer the element type of \"{}\"", self.name()),
+                        )
+                        .with_span(self.value.span())
+                        .with_note(format!(
+                            "annotate the list type, e.g. `let {}: mutable number[] = []`",
+                            self.name()
+                        )));
+                    }
+                }
             } else {
+                actual_type.value =
+                    type_interpreter.infer(&actual_type.value, &assigned_type.value);
                 if !actual_type.value.is_sub_type_of(&assigned_type.value) {
-                    return Err(format!(
-                        "Actual type assigned to \"{}\" was {:#?} which doesn't subtype {:#?}",
-                        self.key, actual_type, assigned_type
-                    ));
-                }
-
-                if let Some(_) = actual_type.value.get_return_type() {
-                    return Err(format!("Cannot assign variable to return value"));
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::TypeMismatch,
+                        format!(
+                            "Actual type assigned to \"{}\" was {} which doesn't subtype {}",
+                            self.name(),
+                            actual_type.value,
+                            assigned_type.value
+                        ),
+                    )
+                    .with_span(self.value.span()));
                 }
 
-                actual_type = assigned_type.clone()
+                actual_type = assigned_type
             }
         }
 
-        type_interpreter.create(self.key.clone(), actual_type.clone())?;
+        let bindings = self
+            .pattern
+            .bind_types(&actual_type.value, self.span, type_interpreter)?;
+        for (name, value) in bindings {
+            type_interpreter
+                .create(
+                    name,
+                    Variable {
+                        mutable: actual_type.mutable,
+                        value,
+                    },
+                )
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
         Ok(actual_type.value)
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
-        let actual_value = Variable {
-            mutable: self.assigned_type.as_ref().is_some_and(|t| t.mutable),
-            value: self.value.interpret(interpreter)?,
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        if let Some(name) = self.hoisted_name(interpreter) {
+            return Ok(interpreter.get(name).unwrap().value);
+        }
+
+        let mutable = self.assigned_type.as_ref().is_some_and(|t| t.mutable);
+        let actual_value = self.value.interpret(interpreter)?;
+        if actual_value.is_signal() {
+            return Ok(actual_value);
+        }
+
+        let Some(bindings) = self.pattern.matches(&actual_value, interpreter)? else {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!(
+                    "{} does not match the pattern of \"{}\"",
+                    actual_value.to_log_string(),
+                    self.name()
+                ),
+            )
+            .with_span(self.span));
         };
 
-        interpreter.create(self.key.clone(), actual_value.clone())?;
-        Ok(actual_value.value)
+        for (name, value) in bindings {
+            interpreter
+                .create(name, Variable { mutable, value })
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
+        Ok(actual_value)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn hoist(&self, interpreter: &mut Interpreter<Value>) -> Result<(), Diagnostic> {
+        if let (true, Pattern::Binding(name)) = (self.hoisted, &self.pattern) {
+            let value = self.value.interpret(interpreter)?;
+            interpreter
+                .create(
+                    name.clone(),
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
+        Ok(())
+    }
+
+    fn hoist_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<(), Diagnostic> {
+        if let (true, Pattern::Binding(name)) = (self.hoisted, &self.pattern) {
+            let value = self.value.check_type(type_interpreter)?;
+            type_interpreter
+                .create(
+                    name.clone(),
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
+        Ok(())
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.value.assigned(names);
     }
 
     fn to_string(&self) -> String {
         format!(
-            "{{ \"type\": \"Declare\", \"key\": \"{}\", \"value\": {}, \"type\": {:#?} }}",
-            self.key,
+            "{{ \"type\": \"Declare\", \"pattern\": {}, \"doc\": {}, \"hoisted\": {}, \"value\": {}, \"type\": {:#?} }}",
+            self.pattern,
+            doc_to_string(&self.doc),
+            self.hoisted,
             self.value.to_string(),
             self.assigned_type
         )
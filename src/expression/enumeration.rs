@@ -0,0 +1,194 @@
+use std::ops::ControlFlow;
+
+use std::collections::HashMap;
+
+use crate::{
+    data::enumeration::Variant,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, EnumType, Type, TypeDefinition},
+    value::Value,
+};
+
+use super::{doc_to_string, Expression, Interpreter};
+
+/// `enum Shape { Circle(number), Rect(number, number), Empty }`, declares a
+/// type in the current scope
+pub struct EnumDeclaration {
+    pub enum_type: EnumType,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl Expression for EnumDeclaration {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let mut enum_type = self.enum_type.clone();
+        for variant in enum_type.variants.iter_mut() {
+            variant.fields = variant
+                .fields
+                .iter()
+                .map(|field| type_interpreter.resolve(field))
+                .collect();
+        }
+
+        type_interpreter
+            .create_type(self.enum_type.name.clone(), TypeDefinition::Enum(enum_type))
+            .map_err(|err| err.or_span(self.span))?;
+
+        Ok(Type::BaseType(BaseType::Null))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        interpreter
+            .create_type(
+                self.enum_type.name.clone(),
+                TypeDefinition::Enum(self.enum_type.clone()),
+            )
+            .map_err(|err| err.or_span(self.span))?;
+
+        Ok(Value::Null)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"EnumDeclaration\", \"name\": \"{}\", \"doc\": {}, \"parameters\": {:?}, \"variants\": [{}] }}",
+            self.enum_type.name,
+            doc_to_string(&self.doc),
+            self.enum_type.parameters,
+            self.enum_type
+                .variants
+                .iter()
+                .map(|variant| format!(
+                    "{{ \"name\": \"{}\", \"fields\": \"{:?}\" }}",
+                    variant.name, variant.fields
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+/// `Shape::Rect(1, 2)` or `Shape::Empty`, creates a value of an enum
+pub struct EnumConstructor {
+    pub enum_name: String,
+    pub variant: String,
+    pub arguments: Vec<Box<dyn Expression>>,
+    pub span: Span,
+}
+
+impl Expression for EnumConstructor {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let enum_type = type_interpreter
+            .get_enum(&self.enum_name)
+            .map_err(|err| err.or_span(self.span))?;
+
+        let Some(variant) = enum_type.variant(&self.variant) else {
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "Enum {} has no variant named `{}`",
+                    self.enum_name, self.variant
+                ),
+            )
+            .with_span(self.span));
+        };
+
+        if variant.fields.len() != self.arguments.len() {
+            return Err(Diagnostic::error(
+                DiagnosticKind::ArgumentCount,
+                format!(
+                    "{}::{} holds {} values but {} were supplied",
+                    self.enum_name,
+                    self.variant,
+                    variant.fields.len(),
+                    self.arguments.len()
+                ),
+            )
+            .with_span(self.span));
+        }
+
+        let argument_types = self
+            .arguments
+            .iter()
+            .map(|argument| type_interpreter.check_operand(argument.as_ref()))
+            .collect::<Result<Vec<Type>, Diagnostic>>()?;
+
+        // parameters that no value decides, e.g. the `T` of `Optional::None`, are `never`
+        let mut inferred = HashMap::new();
+        for (field, argument_type) in variant.fields.iter().zip(argument_types.iter()) {
+            field.infer_parameters(argument_type, &mut inferred);
+        }
+        for parameter in enum_type.parameters.iter() {
+            inferred
+                .entry(parameter.clone())
+                .or_insert(Type::BaseType(BaseType::Never));
+        }
+
+        for (i, (field, argument_type)) in variant.fields.iter().zip(argument_types).enumerate() {
+            let field_type = field.substitute(&inferred);
+            if !argument_type.is_sub_type_of(&field_type) {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!(
+                        "Value {} of {}::{} was {} which doesn't subtype {}",
+                        i + 1,
+                        self.enum_name,
+                        self.variant,
+                        argument_type,
+                        field_type
+                    ),
+                )
+                .with_span(self.arguments[i].span()));
+            }
+        }
+
+        Ok(Type::Named(
+            self.enum_name.clone(),
+            enum_type
+                .parameters
+                .iter()
+                .map(|parameter| inferred[parameter].clone())
+                .collect(),
+        ))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let values = match interpreter.operands(&self.arguments)? {
+            ControlFlow::Continue(values) => values,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+
+        Ok(Value::Enum(Variant {
+            enum_name: self.enum_name.clone(),
+            variant: self.variant.clone(),
+            values,
+        }))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        for argument in self.arguments.iter() {
+            argument.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"EnumConstructor\", \"enum\": \"{}\", \"variant\": \"{}\", \"arguments\": [{}] }}",
+            self.enum_name,
+            self.variant,
+            self.arguments
+                .iter()
+                .map(|argument| argument.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    environment::Variable,
+    pattern::Pattern,
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{
+    jump::{loop_control, LoopControl},
+    Expression, Interpreter,
+};
+
+pub struct For {
+    pub label: Option<String>,
+    pub pattern: Pattern,
+    pub iterable: Box<dyn Expression>,
+    pub body: Box<dyn Expression>,
+    pub span: Span,
+}
+
+impl For {
+    /// Runs the body once with `element` bound to the loop variable
+    fn iteration(
+        &self,
+        element: Value,
+        interpreter: &mut Interpreter<Value>,
+    ) -> Result<Value, Diagnostic> {
+        let Some(bindings) = self.pattern.matches(&element, interpreter)? else {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!(
+                    "{} does not match the pattern of the `for` loop",
+                    element.to_log_string()
+                ),
+            )
+            .with_span(self.span));
+        };
+
+        interpreter.push_environment();
+        for (name, value) in bindings {
+            interpreter
+                .create(
+                    name,
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
+        let result = self.body.interpret(interpreter);
+        interpreter.pop_environment()?;
+
+        result
+    }
+}
+
+impl Expression for For {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let iterable_type = type_interpreter.check_operand(self.iterable.as_ref())?;
+        let element_type = match type_interpreter.known(iterable_type, self.iterable.span())? {
+            Type::List(element_type) => *element_type,
+            Type::BaseType(BaseType::String) => Type::BaseType(BaseType::String),
+            iterable_type => {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!("Cannot iterate over a value of type {}", iterable_type),
+                )
+                .with_span(self.iterable.span()))
+            }
+        };
+
+        let bindings = self
+            .pattern
+            .bind_types(&element_type, self.span, type_interpreter)?;
+
+        type_interpreter.push_environment();
+        for (name, value) in bindings {
+            type_interpreter
+                .create(
+                    name,
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
+        type_interpreter.push_loop(self.label.clone(), false);
+        let typeof_body = type_interpreter
+            .check_loop_body(|type_interpreter| self.body.check_type(type_interpreter));
+        type_interpreter.pop_loop();
+        type_interpreter.pop_environment()?;
+
+        if let Some(return_type) = typeof_body?.get_return_type() {
+            return Ok(Type::Or(
+                Box::from(Type::BaseType(BaseType::Null)),
+                Box::from(Type::Return(Box::from(return_type))),
+            ));
+        }
+
+        Ok(Type::BaseType(BaseType::Null))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let iterable = self.iterable.interpret(interpreter)?;
+        if iterable.is_signal() {
+            return Ok(iterable);
+        }
+        let Some(elements) = iterable.elements() else {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                format!("Cannot iterate over {}", iterable.to_log_string()),
+            )
+            .with_span(self.iterable.span()));
+        };
+
+        for element in elements {
+            match loop_control(&self.label, self.iteration(element, interpreter)?) {
+                LoopControl::Next => continue,
+                LoopControl::Exit(_) => break,
+                LoopControl::Propagate(signal) => return Ok(signal),
+            }
+        }
+
+        Ok(Value::Null)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.iterable.assigned(names);
+        self.body.assigned(names);
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"For\", \"label\": {}, \"pattern\": {}, \"iterable\": {}, \"body\": {} }}",
+            match &self.label {
+                Some(label) => format!("\"{}\"", label),
+                None => "null".to_owned(),
+            },
+            self.pattern,
+            self.iterable.to_string(),
+            self.body.to_string()
+        )
+    }
+}
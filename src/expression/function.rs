@@ -2,6 +2,10 @@ use std::{cell::RefCell, fmt::Debug, rc::Rc};
 
 use crate::{
     callable::Callable,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    environment::Variable,
+    pattern::Pattern,
+    span::Span,
     types::{BaseType, FunctionType, Type},
     value::Value,
 };
@@ -10,14 +14,16 @@ use super::{Expression, Interpreter};
 
 #[derive(Clone)]
 pub struct FunctionArgument {
-    pub name: String,
+    pub pattern: Pattern,
     pub type_annotation: Type,
+    pub span: Span,
 }
 
 pub struct Function {
     pub arguments: Vec<FunctionArgument>,
     pub return_type: Type,
     pub body: Rc<Box<dyn Expression>>,
+    pub span: Span,
 }
 
 impl Debug for Function {
@@ -32,47 +38,79 @@ impl Clone for Function {
             arguments: self.arguments.clone(),
             return_type: self.return_type.clone(),
             body: Rc::clone(&self.body),
+            span: self.span,
         }
     }
 }
 
 impl Expression for Function {
-    fn check_type(&self, type_checker: &mut Interpreter<Type>) -> Result<Type, String> {
+    fn check_type(&self, type_checker: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
         Ok(Type::Function(Box::from(FunctionType::WithBody(Rc::from(
             RefCell::from(FunctionInstance {
                 arguments: self.arguments.clone(),
                 return_type: self.return_type.clone(),
-                actual_type: Rc::from(RefCell::from(None)),
+                check_state: Rc::from(RefCell::from(CheckState::Unchecked)),
                 body: Rc::clone(&self.body),
                 interpreter: type_checker.clone(),
             }),
         )))))
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
         Ok(Value::Function(Rc::new(RefCell::new(FunctionInstance {
             arguments: self.arguments.clone(), //self.arguments.iter().map(|arg| arg.name.clone()).collect(),
             return_type: self.return_type.clone(),
-            actual_type: Rc::from(RefCell::from(None)),
+            check_state: Rc::from(RefCell::from(CheckState::Unchecked)),
             body: Rc::clone(&self.body),
             interpreter: interpreter.clone(),
         }))))
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.body.assigned(names);
+    }
+
     fn to_string(&self) -> String {
         format!(
-            "{{ \"type\": \"Function\", \"argument_names\": {:#?}, \"argument_types\": {:#?}, \"body\": {:#?} }}",
-            self.arguments.iter().map(|arg| arg.name.clone()).collect::<Vec<String>>(),
+            "{{ \"type\": \"Function\", \"argument_patterns\": [{}], \"argument_types\": {:#?}, \"body\": {:#?} }}",
+            self.arguments.iter().map(|arg| arg.pattern.to_string()).collect::<Vec<String>>().join(", "),
             self.arguments.iter().map(|arg| arg.type_annotation.clone()).collect::<Vec<Type>>(),
             self.body.to_string()
         )
     }
 }
 
+/// How far checking the body of a function has got, shared by its clones
+#[derive(Clone)]
+pub enum CheckState {
+    Unchecked,
+    /// The body is being checked, recursive calls see the type found so far
+    Checking(FunctionType),
+    Checked(FunctionType),
+}
+
+/// A function whose body is being checked, see `Interpreter::recursion`
+pub struct RecursionFrame {
+    check_state: Rc<RefCell<CheckState>>,
+    /// Whether the body called the function again
+    recursive: bool,
+    /// Whether the body used the unfinished type of a function further out,
+    /// in which case its own type isn't final either
+    provisional: bool,
+}
+
+/// How many times the body of a recursive function is checked to find its
+/// return type before asking for an annotation
+const RECURSION_PASSES: usize = 8;
+
 pub struct FunctionInstance<T> {
     pub arguments: Vec<FunctionArgument>,
     pub return_type: Type,
-    pub actual_type: Rc<RefCell<Option<FunctionType>>>,
+    pub check_state: Rc<RefCell<CheckState>>,
 
     pub body: Rc<Box<dyn Expression>>,
     pub interpreter: Interpreter<T>,
@@ -84,69 +122,189 @@ impl<T: Debug> Debug for FunctionInstance<T> {
     }
 }
 
-impl Callable for FunctionInstance<Type> {
-    fn signature(&self) -> String {
-        String::from("Function")
+impl FunctionInstance<Type> {
+    /// Without an annotation the return type of a recursive function starts
+    /// out as `never` and grows until checking the body again doesn't change it
+    fn check_recursively(&mut self) -> Result<FunctionType, Diagnostic> {
+        let inferred = matches!(self.return_type, Type::BaseType(BaseType::Infer));
+        let mut return_type = match inferred {
+            true => Type::BaseType(BaseType::Never),
+            false => self.interpreter.resolve(&self.return_type),
+        };
+
+        for _ in 0..RECURSION_PASSES {
+            let (argument_types, body_type) = self.check_body(&return_type)?;
+
+            let recursive = self
+                .interpreter
+                .recursion
+                .borrow()
+                .last()
+                .is_some_and(|frame| frame.recursive);
+            if !inferred || !recursive || body_type.is_sub_type_of(&return_type) {
+                // an annotated or recursive function keeps the type calls have seen
+                let return_type = match inferred && !recursive {
+                    true => body_type,
+                    false => return_type,
+                };
+
+                return Ok(FunctionType::Literal(argument_types, return_type));
+            }
+
+            return_type = return_type.union(body_type);
+        }
+
+        Err(Diagnostic::error(
+            DiagnosticKind::TypeMismatch,
+            String::from("Cannot infer the return type of this recursive function"),
+        )
+        .with_span(self.body.span())
+        .with_note(String::from(
+            "annotate the return type, e.g. `(n: integer): integer => ...`",
+        )))
     }
 
-    fn get_type(&mut self) -> Result<FunctionType, String> {
-        if let Some(function_type) = self.actual_type.borrow().clone() {
-            return Ok(function_type);
+    /// Checks the body once with recursive calls returning `return_type`,
+    /// giving the argument types and the type of the body
+    fn check_body(&mut self, return_type: &Type) -> Result<(Vec<Type>, Type), Diagnostic> {
+        // a parameter shadowing an unannotated one outside starts out unknown again
+        for argument in self.arguments.iter() {
+            if let Type::Variable(name) = &argument.type_annotation {
+                self.interpreter.inferred.remove(name);
+            }
         }
 
         let argument_types: Vec<Type> = self
             .arguments
             .iter()
-            .map(|arg| arg.type_annotation.clone())
+            .map(|arg| self.interpreter.resolve(&arg.type_annotation))
             .collect();
 
-        *self.actual_type.borrow_mut() = Some(FunctionType::Literal(
+        *self.check_state.borrow_mut() = CheckState::Checking(FunctionType::Literal(
             argument_types.clone(),
-            self.return_type.clone(),
+            return_type.clone(),
         ));
 
         self.interpreter.push_environment();
-        for function_argument in self.arguments.iter() {
-            self.interpreter.create(
-                function_argument.name.clone(),
-                crate::environment::Variable {
-                    mutable: false,
-                    value: function_argument.type_annotation.clone(),
-                },
+        self.interpreter.environment.borrow_mut().function_body = true;
+        // the function can be called after a mutable variable it captures is
+        // assigned again, so a condition around its declaration proves nothing
+        self.interpreter.forget_mutable_narrowings();
+        for (function_argument, argument_type) in self.arguments.iter().zip(argument_types.iter()) {
+            let bindings = function_argument.pattern.bind_types(
+                argument_type,
+                function_argument.span,
+                &mut self.interpreter,
             )?;
+
+            for (name, value) in bindings {
+                self.interpreter.create(
+                    name,
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )?;
+            }
         }
 
-        let return_type = self.body.check_type(&mut self.interpreter)?;
+        let body_type = self.body.check_type(&mut self.interpreter)?;
         self.interpreter.pop_environment()?;
 
-        if let Type::BaseType(BaseType::Infer) = self.return_type {
-            self.return_type = return_type;
-        } else if !return_type.is_sub_type_of(&self.return_type) {
-            return Err(format!(
-                "Actual return type ({:#?}) does not match the return type of the body ({:#?})",
-                return_type, self.return_type
-            ));
+        // an annotated return type can decide unannotated parameters, `(x): number => x`
+        let body_type = match self.return_type {
+            Type::BaseType(BaseType::Infer) => self.interpreter.resolve(&body_type),
+            _ => {
+                let body_type = self.interpreter.infer(&body_type, return_type);
+                if !body_type.is_sub_type_of(return_type) {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::TypeMismatch,
+                        format!(
+                            "Actual return type ({}) does not match the return type of the body ({})",
+                            body_type, return_type
+                        ),
+                    )
+                    .with_span(self.body.span()));
+                }
+
+                body_type
+            }
+        };
+
+        // whatever the body didn't decide stays generic, e.g. `(x) => x`
+        let argument_types = argument_types
+            .iter()
+            .map(|argument_type| self.interpreter.resolve(argument_type))
+            .collect();
+
+        Ok((argument_types, body_type))
+    }
+}
+
+impl Callable for FunctionInstance<Type> {
+    fn signature(&self) -> String {
+        String::from("Function")
+    }
+
+    /// Checks the body, calls to functions that are already being checked
+    /// (recursion) see the type found for them so far
+    fn get_type(&mut self) -> Result<FunctionType, Diagnostic> {
+        let check_state = self.check_state.borrow().clone();
+        match check_state {
+            CheckState::Checked(function_type) => return Ok(function_type),
+            CheckState::Checking(function_type) => {
+                let mut recursion = self.interpreter.recursion.borrow_mut();
+                if let Some(position) = recursion
+                    .iter()
+                    .position(|frame| Rc::ptr_eq(&frame.check_state, &self.check_state))
+                {
+                    recursion[position].recursive = true;
+                    for frame in recursion[position + 1..].iter_mut() {
+                        frame.provisional = true;
+                    }
+                }
+
+                return Ok(function_type);
+            }
+            CheckState::Unchecked => (),
         }
 
-        *self.actual_type.borrow_mut() = Some(FunctionType::Literal(
-            argument_types,
-            self.return_type.clone(),
-        ));
+        self.interpreter
+            .recursion
+            .borrow_mut()
+            .push(RecursionFrame {
+                check_state: Rc::clone(&self.check_state),
+                recursive: false,
+                provisional: false,
+            });
+        let function_type = self.check_recursively();
+        let frame = self
+            .interpreter
+            .recursion
+            .borrow_mut()
+            .pop()
+            .expect("recursion frame pushed above");
+
+        // a type depending on an unfinished one is worked out again next time
+        *self.check_state.borrow_mut() = match &function_type {
+            Ok(function_type) if !frame.provisional => CheckState::Checked(function_type.clone()),
+            _ => CheckState::Unchecked,
+        };
 
-        self.get_type()
+        function_type
     }
 
     fn clone(&self) -> Box<dyn Callable> {
-        return Box::new(FunctionInstance {
+        Box::new(FunctionInstance {
             arguments: self.arguments.clone(),
             return_type: self.return_type.clone(),
-            actual_type: Rc::clone(&self.actual_type),
+            check_state: Rc::clone(&self.check_state),
             body: Rc::clone(&self.body),
             interpreter: self.interpreter.clone(),
-        });
+        })
     }
 
-    fn call(&mut self, _: Vec<crate::value::Value>) -> Result<Value, String> {
+    fn call(&mut self, _: Vec<crate::value::Value>) -> Result<Value, Diagnostic> {
         panic!("Cannot compute value in type environment")
     }
 }
@@ -156,39 +314,73 @@ impl Callable for FunctionInstance<Value> {
         String::from("Function")
     }
 
-    fn get_type(&mut self) -> Result<FunctionType, String> {
+    fn get_type(&mut self) -> Result<FunctionType, Diagnostic> {
         panic!("Cannot get type of function instance");
     }
 
     fn clone(&self) -> Box<dyn Callable> {
-        return Box::new(FunctionInstance {
+        Box::new(FunctionInstance {
             arguments: self.arguments.clone(),
             return_type: self.return_type.clone(),
-            actual_type: self.actual_type.clone(),
+            check_state: Rc::clone(&self.check_state),
             body: Rc::clone(&self.body),
             interpreter: self.interpreter.clone(),
-        });
+        })
     }
 
-    fn call(&mut self, arguments: Vec<crate::value::Value>) -> Result<Value, String> {
-        if self.arguments.len() != arguments.len() {
-            return Err(format!("Arguments for function mismatch"));
+    /// Runs the body, or with fewer arguments than parameters gives a function
+    /// taking the remaining ones
+    fn call(&mut self, arguments: Vec<crate::value::Value>) -> Result<Value, Diagnostic> {
+        if arguments.len() > self.arguments.len()
+            || (arguments.is_empty() && !self.arguments.is_empty())
+        {
+            return Err(Diagnostic::error(
+                DiagnosticKind::ArgumentCount,
+                format!(
+                    "Function expects {} arguments but {} were supplied",
+                    self.arguments.len(),
+                    arguments.len()
+                ),
+            ));
         }
 
-        self.interpreter.push_environment();
-        for (index, argument) in self.arguments.iter().enumerate() {
-            self.interpreter.create(
-                argument.name.to_string(),
-                crate::environment::Variable {
-                    mutable: false,
-                    value: arguments[index].clone(),
-                },
-            )?;
+        // the bound arguments live in their own scope, which partial
+        // applications keep hold of
+        let mut interpreter = self.interpreter.clone();
+        interpreter.push_environment();
+        for (argument, value) in self.arguments.iter().zip(arguments.iter()) {
+            let Some(bindings) = argument.pattern.matches(value, &mut interpreter)? else {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!(
+                        "Argument {} does not match the pattern of the parameter",
+                        value.to_log_string()
+                    ),
+                )
+                .with_label(argument.span, String::from("parameter declared here")));
+            };
+
+            for (name, value) in bindings {
+                interpreter.create(
+                    name,
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )?;
+            }
         }
 
-        let result = self.body.interpret(&mut self.interpreter);
-        self.interpreter.pop_environment()?;
+        if arguments.len() < self.arguments.len() {
+            return Ok(Value::Function(Rc::new(RefCell::new(FunctionInstance {
+                arguments: self.arguments[arguments.len()..].to_vec(),
+                return_type: self.return_type.clone(),
+                check_state: Rc::from(RefCell::from(CheckState::Unchecked)),
+                body: Rc::clone(&self.body),
+                interpreter,
+            }))));
+        }
 
-        result
+        self.body.interpret(&mut interpreter)
     }
 }
@@ -1,4 +1,6 @@
 use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
     types::{BaseType, Type},
     value::Value,
 };
@@ -9,25 +11,64 @@ pub struct If {
     pub condition: Box<dyn Expression>,
     pub body: Box<dyn Expression>,
     pub else_body: Option<Box<dyn Expression>>,
+    pub span: Span,
+}
+
+/// Whether a branch of type `type_` always leaves the block with `return`
+fn always_returns(type_: &Type) -> bool {
+    type_
+        .members()
+        .iter()
+        .all(|member| matches!(member, Type::Return(_)))
 }
 
 impl Expression for If {
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
-        let typeof_condition = self.condition.check_type(type_interpreter)?;
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let typeof_condition = type_interpreter.check_operand(self.condition.as_ref())?;
+        let typeof_condition =
+            type_interpreter.infer(&typeof_condition, &Type::BaseType(BaseType::Boolean));
 
         if !typeof_condition.is_sub_type_of(&Type::BaseType(BaseType::Boolean)) {
-            return Err(String::from("Tried to use an expression that evaluated to non boolean as the condition in an `if` expression"));
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("Tried to use an expression that evaluated to non boolean ({}) as the condition in an `if` expression", typeof_condition),
+            )
+            .with_span(self.condition.span()));
         }
 
-        let typeof_body = self.body.check_type(type_interpreter)?;
+        // each branch knows what the condition showed to get there
+        let when_true = self.condition.narrowings(type_interpreter, true);
+        let when_false = self.condition.narrowings(type_interpreter, false);
+
+        let typeof_body = type_interpreter.with_narrowings(&when_true, |type_interpreter| {
+            self.body.check_type(type_interpreter)
+        })?;
         let typeof_else_body = match &self.else_body {
             None => Type::BaseType(BaseType::Null),
-            Some(body) => body.check_type(type_interpreter)?,
+            Some(body) => type_interpreter.with_narrowings(&when_false, |type_interpreter| {
+                body.check_type(type_interpreter)
+            })?,
+        };
+
+        // after `if x == null return 0;` the rest of the block only runs when
+        // the branch that returns didn't
+        let after = match (
+            always_returns(&typeof_body),
+            always_returns(&typeof_else_body),
+        ) {
+            (true, false) => when_false,
+            (false, true) => when_true,
+            _ => Vec::new(),
         };
+        for (name, type_) in after {
+            type_interpreter.narrow(name, type_);
+        }
+
+        if typeof_body.is_sub_type_of(&typeof_else_body) {
+            return Ok(typeof_else_body);
+        }
 
-        if typeof_body.is_sub_type_of(&typeof_else_body)
-            || typeof_else_body.is_sub_type_of(&typeof_body)
-        {
+        if typeof_else_body.is_sub_type_of(&typeof_body) {
             return Ok(typeof_body);
         }
 
@@ -37,8 +78,11 @@ impl Expression for If {
         ))
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
         let condition = self.condition.interpret(interpreter)?;
+        if condition.is_signal() {
+            return Ok(condition);
+        }
 
         if let Value::Boolean(boolean) = condition {
             if boolean {
@@ -51,7 +95,23 @@ impl Expression for If {
             }
         }
 
-        Err(format!("Condition for `if` did not resolve to a boolean"))
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Condition for `if` did not resolve to a boolean".to_string(),
+        )
+        .with_span(self.condition.span()))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.condition.assigned(names);
+        self.body.assigned(names);
+        if let Some(else_body) = &self.else_body {
+            else_body.assigned(names);
+        }
     }
 
     fn to_string(&self) -> String {
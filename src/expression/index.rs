@@ -0,0 +1,113 @@
+use crate::{
+    data::list::resolve_index,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{Expression, Interpreter};
+
+pub struct Index {
+    pub target: Box<dyn Expression>,
+    pub index: Box<dyn Expression>,
+    pub span: Span,
+}
+
+impl Expression for Index {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let target = type_interpreter.check_operand(self.target.as_ref())?;
+        let target = type_interpreter.known(target, self.target.span())?;
+        let index = type_interpreter.check_operand(self.index.as_ref())?;
+        let index = type_interpreter.infer(&index, &Type::BaseType(BaseType::Integer));
+
+        if !index.is_sub_type_of(&Type::BaseType(BaseType::Integer)) {
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("Cannot index with a non integer value of type {}", index),
+            )
+            .with_span(self.index.span()));
+        }
+
+        match target {
+            Type::List(element_type) => Ok(*element_type),
+            // the position is only known at runtime, `tuple.0` keeps the exact type
+            Type::Tuple(element_types) => Ok(element_types
+                .into_iter()
+                .reduce(Type::union)
+                .unwrap_or(Type::BaseType(BaseType::Never))),
+            Type::BaseType(BaseType::String) => Ok(Type::BaseType(BaseType::String)),
+            target => Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("Cannot index into a value of type {}", target),
+            )
+            .with_span(self.target.span())),
+        }
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let target = self.target.interpret(interpreter)?;
+        if target.is_signal() {
+            return Ok(target);
+        }
+
+        let index = self.index.interpret(interpreter)?;
+        if index.is_signal() {
+            return Ok(index);
+        }
+
+        let Value::Integer(index) = index else {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                String::from("Index did not resolve to an integer"),
+            )
+            .with_span(self.index.span()));
+        };
+
+        let element = match &target {
+            Value::List(list) => list.get(index),
+            Value::Range(range) => range.get(index),
+            Value::Tuple(tuple) => tuple.get(index),
+            Value::String(string) => {
+                let chars: Vec<char> = string.chars().collect();
+                match resolve_index(chars.len(), index) {
+                    Some(position) => Ok(Value::String(chars[position].to_string())),
+                    None => Err(Diagnostic::error(
+                        DiagnosticKind::Runtime,
+                        format!(
+                            "Index {} is out of range for string of length {}",
+                            index,
+                            chars.len()
+                        ),
+                    )),
+                }
+            }
+            _ => {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!("Cannot index into {}", target.to_log_string()),
+                )
+                .with_span(self.target.span()))
+            }
+        };
+
+        element.map_err(|err| err.or_span(self.span))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.target.assigned(names);
+        self.index.assigned(names);
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"Index\", \"target\": {}, \"index\": {} }}",
+            self.target.to_string(),
+            self.index.to_string()
+        )
+    }
+}
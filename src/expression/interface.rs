@@ -0,0 +1,291 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    environment::Implementation,
+    span::Span,
+    types::{BaseType, FunctionType, InterfaceMethod, InterfaceType, Type, TypeDefinition},
+    value::Value,
+};
+
+use super::{doc_to_string, Expression, Interpreter};
+
+/// `interface Add { add: (Self, Self) => Self }`, declares a type in the
+/// current scope that every type implementing it subtypes
+pub struct InterfaceDeclaration {
+    pub name: String,
+    pub doc: Option<String>,
+    pub methods: Vec<InterfaceMethod>,
+    pub span: Span,
+}
+
+impl InterfaceDeclaration {
+    /// A new interface without implementors, each interpreter gets its own
+    fn interface_type(&self, methods: Vec<InterfaceMethod>) -> InterfaceType {
+        InterfaceType {
+            name: self.name.clone(),
+            methods,
+            implementors: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+}
+
+impl Expression for InterfaceDeclaration {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| InterfaceMethod {
+                name: method.name.clone(),
+                type_: type_interpreter.resolve(&method.type_),
+            })
+            .collect();
+
+        type_interpreter
+            .create_type(
+                self.name.clone(),
+                TypeDefinition::Interface(self.interface_type(methods)),
+            )
+            .map_err(|err| err.or_span(self.span))?;
+
+        Ok(Type::BaseType(BaseType::Null))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        interpreter
+            .create_type(
+                self.name.clone(),
+                TypeDefinition::Interface(self.interface_type(self.methods.clone())),
+            )
+            .map_err(|err| err.or_span(self.span))?;
+
+        Ok(Value::Null)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"InterfaceDeclaration\", \"name\": \"{}\", \"doc\": {}, \"methods\": [{}] }}",
+            self.name,
+            doc_to_string(&self.doc),
+            self.methods
+                .iter()
+                .map(|method| format!(
+                    "{{ \"name\": \"{}\", \"type\": \"{:?}\" }}",
+                    method.name, method.type_
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+/// `impl Add for Point { add: (a: Point, b: Point): Point => ... }`, makes the
+/// methods callable on values of the type
+pub struct Implement {
+    pub interface: String,
+    pub doc: Option<String>,
+    pub for_type: Type,
+    pub methods: Vec<(String, Box<dyn Expression>)>,
+    pub span: Span,
+}
+
+impl Expression for Implement {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let interface = type_interpreter
+            .get_interface(&self.interface)
+            .map_err(|err| err.or_span(self.span))?;
+        let for_type = type_interpreter.resolve(&self.for_type);
+
+        if interface.implementors.borrow().iter().any(|implementor| {
+            implementor.is_sub_type_of(&for_type) && for_type.is_sub_type_of(implementor)
+        }) {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Redeclaration,
+                format!("{} is already implemented for {}", self.interface, for_type),
+            )
+            .with_span(self.span));
+        }
+
+        for (name, value) in self.methods.iter() {
+            if interface.method(name).is_none() {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!(
+                        "Interface {} has no method named `{}`",
+                        self.interface, name
+                    ),
+                )
+                .with_span(value.span()));
+            }
+        }
+
+        if let Some(missing) = interface
+            .methods
+            .iter()
+            .find(|method| !self.methods.iter().any(|(name, _)| *name == method.name))
+        {
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "Implementation of {} for {} is missing the method `{}`",
+                    self.interface, for_type, missing.name
+                ),
+            )
+            .with_span(self.span)
+            .with_note(format!(
+                "`{}` should be a {}",
+                missing.name,
+                interface.method_type(missing, &for_type)
+            )));
+        }
+
+        let mut methods = Vec::new();
+        for (name, value) in self.methods.iter() {
+            methods.push((name.clone(), value.check_type(type_interpreter)?));
+        }
+
+        type_interpreter.create_implementation(Implementation {
+            interface: self.interface.clone(),
+            for_type: for_type.clone(),
+            methods: methods.clone(),
+        });
+        interface.implementors.borrow_mut().push(for_type.clone());
+
+        // bodies are checked once the impl exists so methods can call each other
+        for ((name, method_type), (_, value)) in methods.into_iter().zip(self.methods.iter()) {
+            let method_type = match method_type {
+                Type::Function(function_type) => match *function_type {
+                    FunctionType::WithBody(function_instance) => {
+                        let function_type = function_instance
+                            .borrow()
+                            .clone()
+                            .get_type()
+                            .map_err(|err| err.or_span(value.span()))?;
+                        Type::Function(Box::from(function_type))
+                    }
+                    function_type => Type::Function(Box::from(function_type)),
+                },
+                method_type => method_type,
+            };
+
+            let expected = interface.method_type(interface.method(&name).unwrap(), &for_type);
+            if !method_type.is_sub_type_of(&expected) {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!(
+                        "Method `{}` of {} for {} was {} which doesn't subtype {}",
+                        name, self.interface, for_type, method_type, expected
+                    ),
+                )
+                .with_span(value.span()));
+            }
+        }
+
+        Ok(Type::BaseType(BaseType::Null))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let interface = interpreter
+            .get_interface(&self.interface)
+            .map_err(|err| err.or_span(self.span))?;
+        let for_type = interpreter.resolve(&self.for_type);
+
+        let mut methods = Vec::new();
+        for (name, value) in self.methods.iter() {
+            methods.push((name.clone(), value.interpret(interpreter)?));
+        }
+
+        interpreter.create_implementation(Implementation {
+            interface: self.interface.clone(),
+            for_type: for_type.clone(),
+            methods,
+        });
+        interface.implementors.borrow_mut().push(for_type);
+
+        Ok(Value::Null)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"Implement\", \"interface\": \"{}\", \"doc\": {}, \"for\": \"{:?}\", \"methods\": [{}] }}",
+            self.interface,
+            doc_to_string(&self.doc),
+            self.for_type,
+            self.methods
+                .iter()
+                .map(|(name, value)| format!(
+                    "{{ \"name\": \"{}\", \"value\": {} }}",
+                    name,
+                    value.to_string()
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+/// The type returned by calling `method` on a value of `receiver`, which is
+/// passed as the first argument. `None` when some member of `receiver` has no
+/// such method, optionally only looking at implementations of `interface`
+pub fn check_method_call(
+    type_interpreter: &mut Interpreter<Type>,
+    receiver: &Type,
+    method: &str,
+    interface: Option<&str>,
+    argument_types: &[Type],
+) -> Result<Option<Type>, Diagnostic> {
+    let mut return_types = Vec::new();
+    for member in receiver.members() {
+        let method_type = match &member {
+            Type::Interface(receiver_interface) => match interface {
+                Some(interface) if interface != receiver_interface.name => None,
+                _ => receiver_interface
+                    .method(method)
+                    .map(|method| receiver_interface.method_type(method, &member)),
+            },
+            Type::BaseType(BaseType::Any) => None,
+            _ => type_interpreter.get_method(method, interface, &|for_type| {
+                member.is_sub_type_of(for_type)
+            }),
+        };
+
+        let Some(Type::Function(function_type)) = method_type else {
+            return Ok(None);
+        };
+
+        let mut arguments = vec![member.clone()];
+        arguments.extend_from_slice(argument_types);
+        return_types.push(function_type.apply(arguments)?);
+    }
+
+    Ok(return_types.into_iter().reduce(Type::union))
+}
+
+/// Calls the `method` implemented for `receiver` with it as the first
+/// argument, `None` when there is no such method
+pub fn call_method(
+    interpreter: &Interpreter<Value>,
+    receiver: Value,
+    method: &str,
+    interface: Option<&str>,
+    arguments: Vec<Value>,
+) -> Result<Option<Value>, Diagnostic> {
+    let Some(Value::Function(callee)) = interpreter.get_method(method, interface, &|for_type| {
+        receiver.is_instance_of(for_type)
+    }) else {
+        return Ok(None);
+    };
+
+    let mut all_arguments = vec![receiver];
+    all_arguments.extend(arguments);
+    let result = callee.borrow().clone().call(all_arguments)?;
+    Ok(Some(result))
+}
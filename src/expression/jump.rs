@@ -0,0 +1,167 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{Expression, Interpreter};
+
+/// `break` or `continue`, both leave the rest of the loop body unevaluated.
+/// Without a label they target the innermost loop
+pub struct Jump {
+    pub kind: JumpKind,
+    pub label: Option<String>,
+    /// Only `break` inside a `loop` can carry a value
+    pub value: Option<Box<dyn Expression>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JumpKind {
+    Break,
+    Continue,
+}
+
+impl JumpKind {
+    fn keyword(&self) -> &'static str {
+        match self {
+            JumpKind::Break => "break",
+            JumpKind::Continue => "continue",
+        }
+    }
+}
+
+/// A loop enclosing the expression being type checked
+pub struct LoopFrame {
+    pub label: Option<String>,
+    /// `while` and `for` always evaluate to null, only `loop` can be broken with a value
+    pub accepts_value: bool,
+    pub break_types: Vec<Type>,
+    /// Not a loop but an operand whose value is needed, jumps can't leave it
+    /// for the loops outside, see `Interpreter::check_operand`
+    pub operand: bool,
+}
+
+/// What a loop does after its body evaluated to a value
+pub enum LoopControl {
+    Next,
+    /// Stop looping, with the value given to `break` (null if there was none)
+    Exit(Value),
+    /// A signal for an outer loop or block, the loop stops and passes it on
+    Propagate(Value),
+}
+
+pub fn loop_control(label: &Option<String>, value: Value) -> LoopControl {
+    let targets = |target: &Option<String>| target.is_none() || target == label;
+
+    match value {
+        Value::Break(target, value) if targets(&target) => LoopControl::Exit(*value),
+        Value::Continue(target) if targets(&target) => LoopControl::Next,
+        Value::Break(..) | Value::Continue(_) | Value::Return(_) => LoopControl::Propagate(value),
+        _ => LoopControl::Next,
+    }
+}
+
+impl Expression for Jump {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let value_type = match &self.value {
+            Some(value) => Some(type_interpreter.check_operand(value.as_ref())?),
+            None => None,
+        };
+
+        let frame = match &self.label {
+            None => type_interpreter.loops.last_mut(),
+            Some(label) => type_interpreter
+                .loops
+                .iter_mut()
+                .rev()
+                .find(|frame| frame.operand || frame.label.as_ref() == Some(label)),
+        };
+
+        let Some(frame) = frame else {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Syntax,
+                match &self.label {
+                    None => format!("`{}` used outside of a loop", self.kind.keyword()),
+                    Some(label) => format!("Use of undeclared loop label '{}", label),
+                },
+            )
+            .with_span(self.span));
+        };
+
+        if frame.operand {
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("`{}` cannot be used as a value", self.kind.keyword()),
+            )
+            .with_span(self.span)
+            .with_note(format!(
+                "the expression around it would be left without a value, use `{}` as a statement, e.g. `if done {{ {}; }};`",
+                self.kind.keyword(),
+                self.kind.keyword()
+            )));
+        }
+
+        match (value_type, frame.accepts_value) {
+            (Some(_), false) => {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    String::from("`break` with a value can only be used inside `loop`"),
+                )
+                .with_span(self.span)
+                .with_note(String::from(
+                    "`while` and `for` loops always evaluate to null",
+                )))
+            }
+            (value_type, true) if self.kind == JumpKind::Break => frame
+                .break_types
+                .push(value_type.unwrap_or(Type::BaseType(BaseType::Null))),
+            _ => (),
+        }
+
+        Ok(Type::BaseType(BaseType::Never))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        Ok(match self.kind {
+            JumpKind::Break => {
+                let value = match &self.value {
+                    Some(value) => value.interpret(interpreter)?,
+                    None => Value::Null,
+                };
+                if value.is_signal() {
+                    return Ok(value);
+                }
+
+                Value::Break(self.label.clone(), Box::from(value))
+            }
+            JumpKind::Continue => Value::Continue(self.label.clone()),
+        })
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        if let Some(value) = &self.value {
+            value.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"{:?}\", \"label\": {}, \"value\": {} }}",
+            self.kind,
+            match &self.label {
+                Some(label) => format!("\"{}\"", label),
+                None => "null".to_owned(),
+            },
+            match &self.value {
+                Some(value) => value.to_string(),
+                None => "null".to_owned(),
+            }
+        )
+    }
+}
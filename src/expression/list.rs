@@ -1,45 +1,67 @@
-use crate::{data::list::List, types::Type, value::Value};
+use std::ops::ControlFlow;
+
+use crate::{
+    data::list::List,
+    diagnostic::Diagnostic,
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
 
 use super::{Expression, Interpreter};
 
 pub struct ListLiteral {
     pub elements: Vec<Box<dyn Expression>>,
+    pub span: Span,
 }
 
 impl Expression for ListLiteral {
     fn check_type(
         &self,
         type_interpreter: &mut Interpreter<Type>,
-    ) -> Result<crate::types::Type, String> {
-        self.elements
+    ) -> Result<crate::types::Type, Diagnostic> {
+        let element_type = self
+            .elements
             .iter()
-            .map(|expression| expression.check_type(type_interpreter))
-            .collect::<Result<Vec<Type>, String>>()?
+            .map(|expression| type_interpreter.check_operand(expression.as_ref()))
+            .collect::<Result<Vec<Type>, Diagnostic>>()?
             .iter()
             .cloned()
             .reduce(|acc, cur| {
                 if cur.is_sub_type_of(&acc) {
                     acc
+                } else if acc.is_sub_type_of(&cur) {
+                    cur
                 } else {
                     Type::Or(Box::from(acc.clone()), Box::from(cur.clone()))
                 }
             })
-            .map(Box::from)
-            .map(Type::List)
-            .ok_or(String::from("Cannot infer type of empty arrays (yet)"))
+            // `[]` has no elements to infer from, so it fits any list type
+            .unwrap_or(Type::BaseType(BaseType::Never));
+
+        Ok(Type::List(Box::from(element_type)))
     }
 
     fn interpret(
         &self,
         interpreter: &mut super::Interpreter<crate::value::Value>,
-    ) -> Result<crate::value::Value, String> {
-        Ok(Value::List(List {
-            vector: self
-                .elements
-                .iter()
-                .map(|expression| expression.interpret(interpreter))
-                .collect::<Result<Vec<_>, _>>()?,
-        }))
+    ) -> Result<crate::value::Value, Diagnostic> {
+        let vector = match interpreter.operands(&self.elements)? {
+            ControlFlow::Continue(elements) => elements,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+
+        Ok(Value::List(List { vector }))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        for element in self.elements.iter() {
+            element.assigned(names);
+        }
     }
 
     fn to_string(&self) -> String {
@@ -1,4 +1,6 @@
 use crate::{
+    diagnostic::Diagnostic,
+    span::Span,
     tokeniser,
     types::{BaseType, Type},
     value::Value,
@@ -6,33 +8,45 @@ use crate::{
 
 use super::{Expression, Interpreter};
 
+#[derive(Clone)]
 pub struct Literal {
     pub value: tokeniser::Literal,
+    pub span: Span,
 }
 
 impl Expression for Literal {
-    fn check_type(&self, _type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
-        return Ok(match self.value {
+    fn check_type(&self, _type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        Ok(match self.value {
             tokeniser::Literal::Null => Type::BaseType(BaseType::Null),
+            tokeniser::Literal::Integer(_) => Type::BaseType(BaseType::Integer),
             tokeniser::Literal::Number(_) => Type::BaseType(BaseType::Number),
             tokeniser::Literal::String(_) => Type::BaseType(BaseType::String),
             tokeniser::Literal::Boolean(_) => Type::BaseType(BaseType::Boolean),
-        });
+        })
     }
 
-    fn interpret(&self, _interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
-        return Ok(match self.value.clone() {
+    fn interpret(&self, _interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        Ok(match self.value.clone() {
             tokeniser::Literal::Null => Value::Null,
+            tokeniser::Literal::Integer(integer) => Value::Integer(integer),
             tokeniser::Literal::Number(number) => Value::Number(number),
             tokeniser::Literal::String(string) => Value::String(string),
             tokeniser::Literal::Boolean(boolean) => Value::Boolean(boolean),
-        });
+        })
+    }
+
+    fn span(&self) -> Span {
+        self.span
     }
 
     fn to_string(&self) -> String {
         format!(
             "{{ \"type\": \"Literal\", \"value\": {} }}",
-            self.value.to_owned().to_string()
+            self.value.to_owned()
         )
     }
+
+    fn is_null_literal(&self) -> bool {
+        self.value == tokeniser::Literal::Null
+    }
 }
@@ -0,0 +1,73 @@
+use crate::{
+    diagnostic::Diagnostic,
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{
+    jump::{loop_control, LoopControl},
+    Expression, Interpreter,
+};
+
+/// `loop body`, runs until it is broken out of and evaluates to the value
+/// given to `break`
+pub struct Loop {
+    pub label: Option<String>,
+    pub body: Box<dyn Expression>,
+    pub span: Span,
+}
+
+impl Expression for Loop {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        type_interpreter.push_loop(self.label.clone(), true);
+        let typeof_body = type_interpreter
+            .check_loop_body(|type_interpreter| self.body.check_type(type_interpreter));
+        let frame = type_interpreter.pop_loop();
+
+        // a loop that is never broken out of never produces a value
+        let mut loop_type = frame
+            .break_types
+            .into_iter()
+            .reduce(Type::union)
+            .unwrap_or(Type::BaseType(BaseType::Never));
+
+        if let Some(return_type) = typeof_body?.get_return_type() {
+            loop_type = Type::Or(
+                Box::from(loop_type),
+                Box::from(Type::Return(Box::from(return_type))),
+            );
+        }
+
+        Ok(loop_type)
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        loop {
+            match loop_control(&self.label, self.body.interpret(interpreter)?) {
+                LoopControl::Next => continue,
+                LoopControl::Exit(value) => return Ok(value),
+                LoopControl::Propagate(signal) => return Ok(signal),
+            }
+        }
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.body.assigned(names);
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"Loop\", \"label\": {}, \"body\": {} }}",
+            match &self.label {
+                Some(label) => format!("\"{}\"", label),
+                None => "null".to_owned(),
+            },
+            self.body.to_string()
+        )
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    environment::Variable,
+    pattern::Pattern,
+    span::Span,
+    tokeniser,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{Expression, Interpreter};
+
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Box<dyn Expression>>,
+    pub body: Box<dyn Expression>,
+    pub span: Span,
+}
+
+/// `match subject { pattern if guard => body, ... }`, evaluates the body of
+/// the first arm whose pattern matches and whose guard holds
+pub struct Match {
+    pub subject: Box<dyn Expression>,
+    pub arms: Vec<MatchArm>,
+    pub span: Span,
+}
+
+fn union(types: Vec<Type>) -> Option<Type> {
+    types.into_iter().reduce(Type::union)
+}
+
+impl MatchArm {
+    /// Checks the guard and body with the pattern's bindings in scope
+    fn check_type(
+        &self,
+        matched: &Type,
+        type_interpreter: &mut Interpreter<Type>,
+    ) -> Result<Type, Diagnostic> {
+        let bindings = self
+            .pattern
+            .bind_types(matched, self.span, type_interpreter)?;
+
+        type_interpreter.push_environment();
+        for (name, value) in bindings {
+            type_interpreter
+                .create(
+                    name,
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
+        let result = self.check_guard_and_body(type_interpreter);
+        type_interpreter.pop_environment()?;
+
+        result
+    }
+
+    fn check_guard_and_body(
+        &self,
+        type_interpreter: &mut Interpreter<Type>,
+    ) -> Result<Type, Diagnostic> {
+        if let Some(guard) = &self.guard {
+            let typeof_guard = type_interpreter.check_operand(guard.as_ref())?;
+            let typeof_guard =
+                type_interpreter.infer(&typeof_guard, &Type::BaseType(BaseType::Boolean));
+
+            if !typeof_guard.is_sub_type_of(&Type::BaseType(BaseType::Boolean)) {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!("Tried to use an expression that evaluated to non boolean ({}) as a `match` guard", typeof_guard),
+                )
+                .with_span(guard.span()));
+            }
+        }
+
+        self.body.check_type(type_interpreter)
+    }
+
+    /// Runs the arm if it matches, `None` means the next arm should be tried
+    fn interpret(
+        &self,
+        subject: &Value,
+        interpreter: &mut Interpreter<Value>,
+    ) -> Result<Option<Value>, Diagnostic> {
+        let Some(bindings) = self.pattern.matches(subject, interpreter)? else {
+            return Ok(None);
+        };
+
+        interpreter.push_environment();
+        for (name, value) in bindings {
+            interpreter
+                .create(
+                    name,
+                    Variable {
+                        mutable: false,
+                        value,
+                    },
+                )
+                .map_err(|err| err.or_span(self.span))?;
+        }
+
+        let result = self.interpret_guard_and_body(interpreter);
+        interpreter.pop_environment()?;
+
+        result
+    }
+
+    fn interpret_guard_and_body(
+        &self,
+        interpreter: &mut Interpreter<Value>,
+    ) -> Result<Option<Value>, Diagnostic> {
+        if let Some(guard) = &self.guard {
+            match guard.interpret(interpreter)? {
+                Value::Boolean(true) => (),
+                Value::Boolean(false) => return Ok(None),
+                signal if signal.is_signal() => return Ok(Some(signal)),
+                _ => {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::Runtime,
+                        "Guard for `match` arm did not resolve to a boolean".to_string(),
+                    )
+                    .with_span(guard.span()))
+                }
+            }
+        }
+
+        Ok(Some(self.body.interpret(interpreter)?))
+    }
+}
+
+impl Expression for Match {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let mut subject_type = type_interpreter.check_operand(self.subject.as_ref())?;
+
+        // members of the subject's type that no arm has fully matched yet
+        let mut remaining = subject_type.members();
+        let mut booleans_matched = Vec::new();
+        // list lengths matched exactly, and the length from which every list matches
+        let mut list_lengths_matched = Vec::new();
+        let mut list_lengths_from: Option<usize> = None;
+        // variants of each enum matched whatever values they hold
+        let mut variants_matched: HashMap<String, Vec<String>> = HashMap::new();
+        let mut arm_types = Vec::new();
+        // the arm after which nothing is left to match, e.g. a `_` arm
+        let mut exhausted_by: Option<Span> = None;
+
+        for arm in self.arms.iter() {
+            if let Some(exhausted_by) = exhausted_by {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    String::from("Unreachable match arm"),
+                )
+                .with_span(arm.span)
+                .with_label(
+                    exhausted_by,
+                    String::from("every value is already matched by this arm"),
+                ));
+            }
+
+            // later arms only see what earlier arms left unmatched
+            let matched = union(remaining.clone()).unwrap_or(subject_type.clone());
+            arm_types.push(arm.check_type(&matched, type_interpreter)?);
+
+            // an arm's pattern can decide the type of an unannotated subject
+            subject_type = type_interpreter.resolve(&subject_type);
+            remaining = remaining
+                .iter()
+                .flat_map(|member| type_interpreter.resolve(member).members())
+                .collect();
+
+            if arm.guard.is_some() {
+                continue;
+            }
+
+            let covered = match &arm.pattern {
+                Pattern::Literal(literal) => match literal.value {
+                    tokeniser::Literal::Boolean(boolean) => {
+                        booleans_matched.push(boolean);
+                        match booleans_matched.contains(&!boolean) {
+                            true => Some(Type::BaseType(BaseType::Boolean)),
+                            false => None,
+                        }
+                    }
+                    _ => arm.pattern.covers(),
+                },
+                Pattern::List { .. } => {
+                    match arm.pattern.list_length() {
+                        Some((length, true)) => {
+                            list_lengths_from = list_lengths_from.min(Some(length)).or(Some(length))
+                        }
+                        Some((length, false)) => list_lengths_matched.push(length),
+                        None => (),
+                    }
+
+                    match list_lengths_from {
+                        Some(from)
+                            if (0..from).all(|length| list_lengths_matched.contains(&length)) =>
+                        {
+                            arm.pattern
+                                .covers()
+                                .or(Some(Type::List(Box::from(Type::BaseType(BaseType::Any)))))
+                        }
+                        _ => None,
+                    }
+                }
+                Pattern::Enum { .. } => match arm.pattern.covers_variant() {
+                    Some((enum_name, variant)) => {
+                        let matched = variants_matched.entry(enum_name.to_owned()).or_default();
+                        matched.push(variant.to_owned());
+
+                        let enum_type = type_interpreter
+                            .get_enum(enum_name)
+                            .map_err(|err| err.or_span(arm.span))?;
+                        match enum_type
+                            .variants
+                            .iter()
+                            .all(|variant| matched.contains(&variant.name))
+                        {
+                            true => Some(Type::Named(enum_name.to_owned(), Vec::new())),
+                            false => None,
+                        }
+                    }
+                    None => None,
+                },
+                pattern => pattern.covers(),
+            };
+
+            if let Some(covered) = covered {
+                // patterns are written with names, e.g. aliases, that stand for other types
+                let covered = type_interpreter.resolve(&covered);
+                remaining.retain(|member| !member.is_sub_type_of(&covered));
+                if remaining.is_empty() {
+                    exhausted_by = Some(arm.span);
+                }
+            }
+        }
+
+        if let Some(missing) = union(remaining) {
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "Non-exhaustive match, values of type {} are not matched by any arm",
+                    missing
+                ),
+            )
+            .with_span(self.subject.span())
+            .with_note(String::from(
+                "add an arm for the missing type, or a `_ => ...` arm to match anything",
+            )));
+        }
+
+        Ok(union(arm_types).unwrap_or(Type::BaseType(BaseType::Never)))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let subject = self.subject.interpret(interpreter)?;
+        if subject.is_signal() {
+            return Ok(subject);
+        }
+
+        for arm in self.arms.iter() {
+            if let Some(result) = arm.interpret(&subject, interpreter)? {
+                return Ok(result);
+            }
+        }
+
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            format!("No `match` arm matched {}", subject.to_log_string()),
+        )
+        .with_span(self.subject.span()))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.subject.assigned(names);
+        for arm in self.arms.iter() {
+            if let Some(guard) = &arm.guard {
+                guard.assigned(names);
+            }
+            arm.body.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"Match\", \"subject\": {}, \"arms\": [{}] }}",
+            self.subject.to_string(),
+            self.arms
+                .iter()
+                .map(|arm| format!(
+                    "{{ \"pattern\": {}, \"guard\": {}, \"body\": {} }}",
+                    arm.pattern,
+                    match &arm.guard {
+                        Some(guard) => guard.to_string(),
+                        None => "null".to_owned(),
+                    },
+                    arm.body.to_string()
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
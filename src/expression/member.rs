@@ -0,0 +1,118 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{Expression, Interpreter};
+
+pub struct Member {
+    pub target: Box<dyn Expression>,
+    pub name: String,
+    pub span: Span,
+}
+
+impl Member {
+    fn no_member(&self, of: String) -> Diagnostic {
+        Diagnostic::error(
+            DiagnosticKind::TypeMismatch,
+            format!("{} has no member named `{}`", of, self.name),
+        )
+        .with_span(self.span)
+    }
+
+    /// The type of the member of an already checked target
+    pub fn member_type(
+        &self,
+        target: Type,
+        type_interpreter: &mut Interpreter<Type>,
+    ) -> Result<Type, Diagnostic> {
+        let target = type_interpreter.known(target, self.target.span())?;
+        match (&target, self.name.as_str()) {
+            (Type::List(_) | Type::Tuple(_), "length")
+            | (Type::BaseType(BaseType::String), "length") => Ok(Type::BaseType(BaseType::Integer)),
+            (Type::Tuple(element_types), name) => match name.parse::<usize>() {
+                Ok(position) if position < element_types.len() => {
+                    Ok(element_types[position].clone())
+                }
+                _ => Err(self.no_member(format!("Type {}", target))),
+            },
+            (Type::Named(name, _), field) => {
+                let struct_type = type_interpreter
+                    .get_struct(name)
+                    .map_err(|err| err.or_span(self.span))?;
+
+                match struct_type.field(field) {
+                    Some(field) => Ok(field.type_.clone()),
+                    None => Err(self.no_member(format!("Struct {}", name))),
+                }
+            }
+            _ => Err(self.no_member(format!("Type {}", target))),
+        }
+    }
+
+    /// The member of an already evaluated target
+    pub fn member_value(&self, target: Value) -> Result<Value, Diagnostic> {
+        match (&target, self.name.as_str()) {
+            (Value::List(list), "length") => Ok(Value::Integer(list.vector.len() as i64)),
+            (Value::Range(range), "length") => match i64::try_from(range.length()) {
+                Ok(length) => Ok(Value::Integer(length)),
+                Err(_) => Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!(
+                        "The length of {} ({}) is too large for an integer",
+                        range,
+                        range.length()
+                    ),
+                )
+                .with_span(self.span)),
+            },
+            (Value::Tuple(tuple), "length") => Ok(Value::Integer(tuple.elements.len() as i64)),
+            (Value::Tuple(tuple), name) => match name.parse::<usize>() {
+                Ok(position) if position < tuple.elements.len() => {
+                    Ok(tuple.elements[position].clone())
+                }
+                _ => Err(self.no_member(target.to_log_string())),
+            },
+            (Value::Struct(structure), name) => match structure.get(name) {
+                Some(value) => Ok(value),
+                None => Err(self.no_member(target.to_log_string())),
+            },
+            (Value::String(string), "length") => Ok(Value::Integer(string.chars().count() as i64)),
+            _ => Err(self.no_member(target.to_log_string())),
+        }
+    }
+}
+
+impl Expression for Member {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let target = type_interpreter.check_operand(self.target.as_ref())?;
+        self.member_type(target, type_interpreter)
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let target = self.target.interpret(interpreter)?;
+        if target.is_signal() {
+            return Ok(target);
+        }
+
+        self.member_value(target)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.target.assigned(names);
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"Member\", \"target\": {}, \"name\": \"{}\" }}",
+            self.target.to_string(),
+            self.name
+        )
+    }
+}
@@ -0,0 +1,120 @@
+use std::ops::ControlFlow;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::Type,
+    value::Value,
+};
+
+use super::{
+    interface::{call_method, check_method_call},
+    member::Member,
+    Expression, Interpreter,
+};
+
+/// `value.method(args)`, calls a method implemented for the value's type with
+/// the value as the first argument, or a function stored in a field of it
+pub struct MethodCall {
+    pub member: Member,
+    pub arguments: Vec<Box<dyn Expression>>,
+    pub span: Span,
+}
+
+impl Expression for MethodCall {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let receiver = type_interpreter.check_operand(self.member.target.as_ref())?;
+        let argument_types = self
+            .arguments
+            .iter()
+            .map(|arg| type_interpreter.check_operand(arg.as_ref()))
+            .collect::<Result<Vec<Type>, Diagnostic>>()?;
+
+        let called = |err: Diagnostic| {
+            err.or_span(self.span)
+                .with_label(self.member.span, String::from("method called here"))
+        };
+
+        if let Some(return_type) = check_method_call(
+            type_interpreter,
+            &receiver,
+            &self.member.name,
+            None,
+            &argument_types,
+        )
+        .map_err(called)?
+        {
+            return Ok(return_type);
+        }
+
+        match self.member.member_type(receiver, type_interpreter)? {
+            Type::Function(function_type) => function_type.apply(argument_types).map_err(called),
+            target => Err(Diagnostic::error(
+                DiagnosticKind::NotCallable,
+                format!("Cannot call non function of type {}", target),
+            )
+            .with_span(self.member.span)),
+        }
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let receiver = self.member.target.interpret(interpreter)?;
+        if receiver.is_signal() {
+            return Ok(receiver);
+        }
+
+        let arguments = match interpreter.operands(&self.arguments)? {
+            ControlFlow::Continue(arguments) => arguments,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+
+        if let Some(result) = call_method(
+            interpreter,
+            receiver.clone(),
+            &self.member.name,
+            None,
+            arguments.clone(),
+        )
+        .map_err(|err| err.or_span(self.span))?
+        {
+            return Ok(result);
+        }
+
+        match self.member.member_value(receiver)? {
+            Value::Function(callee) => callee
+                .borrow()
+                .clone()
+                .call(arguments)
+                .map_err(|err| err.or_span(self.span)),
+            target => Err(Diagnostic::error(
+                DiagnosticKind::NotCallable,
+                format!("Cannot call non function value {}", target.to_log_string()),
+            )
+            .with_span(self.member.span)),
+        }
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.member.assigned(names);
+        for argument in self.arguments.iter() {
+            argument.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"MethodCall\", \"target\": {}, \"method\": \"{}\", \"arguments\": [{}] }}",
+            self.member.target.to_string(),
+            self.member.name,
+            self.arguments
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
@@ -1,37 +1,76 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, ops::ControlFlow, rc::Rc};
 
 use crate::{
     callable::{map::Map, print::Print},
-    environment::{Environment, Variable},
-    types::{FunctionType, Type},
+    diagnostic::{Diagnostic, DiagnosticKind},
+    environment::{Environment, Implementation, Variable},
+    parser,
+    span::Span,
+    tokeniser,
+    types::{EnumType, FunctionType, InterfaceType, StructType, Type, TypeDefinition},
     value::Value,
 };
 
+use self::{function::RecursionFrame, jump::LoopFrame};
+
+pub mod alias;
 pub mod assign;
 pub mod binary;
 pub mod body;
 pub mod call;
 pub mod declare;
+pub mod enumeration;
+pub mod for_expression;
 pub mod function;
 pub mod if_expression;
+pub mod index;
+pub mod interface;
+pub mod jump;
 pub mod list;
 pub mod literal;
+pub mod loop_expression;
+pub mod match_expression;
+pub mod member;
+pub mod method_call;
+pub mod range;
 pub mod return_expression;
+pub mod slice;
+pub mod structure;
+pub mod tuple;
+pub mod type_test;
 pub mod unary;
 pub mod variable;
+pub mod while_expression;
 
 pub struct Interpreter<T> {
     pub environment: Rc<RefCell<Environment<T>>>,
+    /// The loops enclosing the expression being type checked, innermost last
+    pub loops: Vec<LoopFrame>,
+    /// The types worked out so far for unannotated parameters, see
+    /// `Interpreter::infer`
+    pub inferred: HashMap<String, Type>,
+    /// The functions whose bodies are being checked, outermost first, shared
+    /// by every clone so recursive calls can be recognised
+    pub recursion: Rc<RefCell<Vec<RecursionFrame>>>,
+}
+
+impl<T: std::clone::Clone + std::fmt::Debug> Default for Interpreter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: std::clone::Clone + std::fmt::Debug> Interpreter<T> {
     pub fn new() -> Interpreter<T> {
-        return Interpreter {
+        Interpreter {
             environment: Environment::new(None),
-        };
+            loops: Vec::new(),
+            inferred: HashMap::new(),
+            recursion: Rc::new(RefCell::new(Vec::new())),
+        }
     }
 
-    pub fn create(&self, key: String, value: Variable<T>) -> Result<Variable<T>, String> {
+    pub fn create(&self, key: String, value: Variable<T>) -> Result<Variable<T>, Diagnostic> {
         self.environment.borrow_mut().create(key, value)
     }
 
@@ -39,23 +78,194 @@ impl<T: std::clone::Clone + std::fmt::Debug> Interpreter<T> {
         self.environment.borrow().get(key)
     }
 
-    pub fn set(&self, key: String, value: T) -> Result<Variable<T>, String> {
+    /// Whether `key` was created in the innermost scope
+    pub fn declares(&self, key: &str) -> bool {
+        self.environment.borrow().declares(key)
+    }
+
+    pub fn set(&self, key: String, value: T) -> Result<Variable<T>, Diagnostic> {
         self.environment.borrow_mut().set(key, value)
     }
 
+    /// See `Environment::narrow`
+    pub fn narrow(&self, key: String, value: T) {
+        self.environment.borrow_mut().narrow(key, value)
+    }
+
+    /// See `Environment::forget_narrowing`
+    pub fn forget_narrowing(&self, key: &str) {
+        self.environment.borrow_mut().forget_narrowing(key)
+    }
+
+    /// Stops applying, for the rest of the innermost scope, what conditions
+    /// showed about the mutable variables visible here
+    pub fn forget_mutable_narrowings(&self) {
+        let keys = self.environment.borrow().narrowed_keys();
+        for key in keys {
+            let declared = self.environment.borrow().declared(&key);
+            if let Some(Variable {
+                mutable: true,
+                value,
+            }) = declared
+            {
+                self.narrow(key, value);
+            }
+        }
+    }
+
+    pub fn create_type(&self, name: String, definition: TypeDefinition) -> Result<(), Diagnostic> {
+        self.environment.borrow_mut().create_type(name, definition)
+    }
+
+    /// The definition of the struct called `name`
+    pub fn get_struct(&self, name: &str) -> Result<StructType, Diagnostic> {
+        match self.environment.borrow().get_type(name) {
+            Some(TypeDefinition::Struct(struct_type)) => Ok(struct_type),
+            Some(definition) => Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("\"{}\" is {}, not a struct", name, definition.kind()),
+            )),
+            None => Err(Diagnostic::error(
+                DiagnosticKind::UndefinedType,
+                format!("Struct \"{}\" does not exist", name),
+            )),
+        }
+    }
+
+    /// The definition of the enum called `name`
+    pub fn get_enum(&self, name: &str) -> Result<EnumType, Diagnostic> {
+        match self.environment.borrow().get_type(name) {
+            Some(TypeDefinition::Enum(enum_type)) => Ok(enum_type),
+            Some(definition) => Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("\"{}\" is {}, not an enum", name, definition.kind()),
+            )),
+            None => Err(Diagnostic::error(
+                DiagnosticKind::UndefinedType,
+                format!("Enum \"{}\" does not exist", name),
+            )),
+        }
+    }
+
+    /// The definition of the interface called `name`
+    pub fn get_interface(&self, name: &str) -> Result<InterfaceType, Diagnostic> {
+        match self.environment.borrow().get_type(name) {
+            Some(TypeDefinition::Interface(interface)) => Ok(interface),
+            Some(definition) => Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("\"{}\" is {}, not an interface", name, definition.kind()),
+            )),
+            None => Err(Diagnostic::error(
+                DiagnosticKind::UndefinedType,
+                format!("Interface \"{}\" does not exist", name),
+            )),
+        }
+    }
+
+    /// Replaces the names of interfaces in a type annotation with the
+    /// interfaces themselves, so the types implementing them subtype it,
+    /// aliases with the types they stand for, and unannotated parameters with
+    /// the types inferred for them
+    pub fn resolve(&self, type_: &Type) -> Type {
+        // recursive aliases are only unfolded where the shape of the type is needed
+        match self.resolve_nested(type_) {
+            Type::Alias(alias, arguments) => alias.expand(&arguments),
+            type_ => type_,
+        }
+    }
+
+    fn resolve_nested(&self, type_: &Type) -> Type {
+        let resolve_all = |types: &Vec<Type>| {
+            types
+                .iter()
+                .map(|type_| self.resolve_nested(type_))
+                .collect::<Vec<Type>>()
+        };
+
+        match type_ {
+            Type::Named(name, arguments) => match self.environment.borrow().get_type(name) {
+                Some(TypeDefinition::Interface(interface)) => Type::Interface(interface),
+                // inside its own definition the alias isn't known yet and stays a `Type::Alias`
+                Some(TypeDefinition::Alias(alias)) => alias.expand(&resolve_all(arguments)),
+                _ => Type::Named(name.clone(), resolve_all(arguments)),
+            },
+            Type::Alias(alias, arguments) => Type::Alias(alias.clone(), resolve_all(arguments)),
+            Type::Or(left, right) => Type::Or(
+                Box::from(self.resolve_nested(left)),
+                Box::from(self.resolve_nested(right)),
+            ),
+            Type::List(element_type) => Type::List(Box::from(self.resolve_nested(element_type))),
+            Type::Tuple(element_types) => Type::Tuple(resolve_all(element_types)),
+            Type::Return(return_type) => Type::Return(Box::from(self.resolve_nested(return_type))),
+            Type::Function(function_type) => match function_type.as_ref() {
+                FunctionType::Literal(argument_types, return_type) => {
+                    Type::Function(Box::from(FunctionType::Literal(
+                        resolve_all(argument_types),
+                        self.resolve_nested(return_type),
+                    )))
+                }
+                _ => type_.clone(),
+            },
+            Type::Variable(name) => match self.inferred.get(name) {
+                Some(inferred) => self.resolve_nested(inferred),
+                None => type_.clone(),
+            },
+            Type::BaseType(_) | Type::Interface(_) => type_.clone(),
+        }
+    }
+
+    pub fn create_implementation(&self, implementation: Implementation<T>) {
+        self.environment
+            .borrow_mut()
+            .create_implementation(implementation)
+    }
+
+    /// The method called `name` implemented for the type accepted by
+    /// `implemented_for`, see `Environment::get_method`
+    pub fn get_method(
+        &self,
+        name: &str,
+        interface: Option<&str>,
+        implemented_for: &dyn Fn(&Type) -> bool,
+    ) -> Option<T> {
+        self.environment
+            .borrow()
+            .get_method(name, interface, implemented_for)
+    }
+
     pub fn push_environment(&mut self) {
         self.environment = Environment::new(Some(&self.environment))
     }
 
-    pub fn pop_environment(&mut self) -> Result<(), String> {
+    pub fn pop_environment(&mut self) -> Result<(), Diagnostic> {
         self.environment = match Rc::clone(&self.environment).borrow().pop() {
             Some(environment) => environment,
-            None => return Err(String::from("Cannot pop base environment")),
+            None => {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Internal,
+                    String::from("Cannot pop base environment"),
+                ))
+            }
         };
 
         Ok(())
     }
 
+    pub fn push_loop(&mut self, label: Option<String>, accepts_value: bool) {
+        self.loops.push(LoopFrame {
+            label,
+            accepts_value,
+            break_types: Vec::new(),
+            operand: false,
+        })
+    }
+
+    pub fn pop_loop(&mut self) -> LoopFrame {
+        self.loops
+            .pop()
+            .expect("pop_loop called without a matching push_loop")
+    }
+
     pub fn print_environment(&self) -> String {
         self.environment.borrow().print()
     }
@@ -63,12 +273,32 @@ impl<T: std::clone::Clone + std::fmt::Debug> Interpreter<T> {
 
 impl<T> Clone for Interpreter<T> {
     fn clone(&self) -> Self {
+        // clones are captured by functions, whose bodies are never inside a loop
         Interpreter {
             environment: Rc::clone(&self.environment),
+            loops: Vec::new(),
+            inferred: self.inferred.clone(),
+            recursion: Rc::clone(&self.recursion),
         }
     }
 }
 
+/// The `"doc"` of a declaration in `Expression::to_string`. Declarations keep
+/// the text of the `///` comments written directly above them, one line per
+/// comment, as their `doc` field
+fn doc_to_string(doc: &Option<String>) -> String {
+    match doc {
+        Some(doc) => format!("{:?}", doc),
+        None => "null".to_owned(),
+    }
+}
+
+/// The declarations in `prelude.aa`, e.g. `Optional` and `Result`
+fn prelude() -> Vec<Box<dyn Expression>> {
+    let tokens = tokeniser::scan(include_str!("../prelude.aa")).expect("prelude should scan");
+    parser::parse(tokens).expect("prelude should parse")
+}
+
 impl Interpreter<Value> {
     pub fn seed(&mut self) {
         self.create(
@@ -88,10 +318,164 @@ impl Interpreter<Value> {
             },
         )
         .unwrap();
+
+        for expression in prelude() {
+            expression.interpret(self).unwrap();
+        }
+    }
+
+    /// Evaluates the operands of an expression in order. A `return`, `break`
+    /// or `continue` in one of them stops the rest from running and is given
+    /// back to be passed on in place of the expression's value
+    pub fn operands(
+        &mut self,
+        expressions: &[Box<dyn Expression>],
+    ) -> Result<ControlFlow<Value, Vec<Value>>, Diagnostic> {
+        let mut values = Vec::new();
+        for expression in expressions.iter() {
+            let value = expression.interpret(self)?;
+            if value.is_signal() {
+                return Ok(ControlFlow::Break(value));
+            }
+
+            values.push(value);
+        }
+
+        Ok(ControlFlow::Continue(values))
     }
 }
 
 impl Interpreter<Type> {
+    /// Gives the unannotated parameters in `actual` whose types aren't known
+    /// yet the types they are used as in `expected`, e.g. `x - 1` makes `x` a
+    /// number. Returns `actual` with what is known filled in
+    pub fn infer(&mut self, actual: &Type, expected: &Type) -> Type {
+        let actual = self.resolve(actual);
+
+        let mut found = HashMap::new();
+        actual.infer_parameters(expected, &mut found);
+        for (name, type_) in found {
+            if Type::is_inferred(&name) && !type_.is_generic() {
+                self.inferred.entry(name).or_insert(type_);
+            }
+        }
+
+        self.resolve(&actual)
+    }
+
+    /// Errors when `type_` is an unannotated parameter nothing has decided the
+    /// type of yet, for uses that need to know it, e.g. `x.name` or `x[0]`
+    pub fn known(&self, type_: Type, span: Span) -> Result<Type, Diagnostic> {
+        let type_ = self.resolve(&type_);
+        let Type::Variable(name) = &type_ else {
+            return Ok(type_);
+        };
+
+        if !Type::is_inferred(name) {
+            return Ok(type_);
+        }
+
+        let parameter = name.trim_start_matches('\'');
+        Err(Diagnostic::error(
+            DiagnosticKind::TypeMismatch,
+            format!(
+                "Cannot infer the type of `{}` from how it is used",
+                parameter
+            ),
+        )
+        .with_span(span)
+        .with_note(format!(
+            "annotate the parameter with its type, e.g. `({}: Point) => ...`",
+            parameter
+        )))
+    }
+
+    /// What testing the variable `name` against `to` shows, its type narrowed
+    /// to `to` when `is` or without `to` otherwise
+    pub fn narrowing(&self, name: &str, to: &Type, is: bool) -> Vec<(String, Type)> {
+        let Some(variable) = self.get(name.to_owned()) else {
+            return Vec::new();
+        };
+
+        let type_ = self.resolve(&variable.value);
+        let narrowed = match is {
+            true => type_.narrow(to),
+            false => Some(type_.exclude(to)),
+        };
+
+        narrowed
+            .map(|narrowed| vec![(name.to_owned(), narrowed)])
+            .unwrap_or_default()
+    }
+
+    /// Checks an expression whose value the expression around it uses, e.g. an
+    /// argument or an operand. A `break`, `continue` or `return` inside it
+    /// would leave that expression without a value, so none can jump out
+    pub fn check_operand(&mut self, operand: &dyn Expression) -> Result<Type, Diagnostic> {
+        self.loops.push(LoopFrame {
+            label: None,
+            accepts_value: false,
+            break_types: Vec::new(),
+            operand: true,
+        });
+        let result = operand.check_type(self);
+        self.loops.pop();
+        let type_ = result?;
+
+        if type_
+            .members()
+            .iter()
+            .any(|member| matches!(member, Type::Return(_)))
+        {
+            return Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                String::from("`return` cannot be used as a value"),
+            )
+            .with_span(operand.span())
+            .with_note(String::from(
+                "the expression around it would be left without a value, use `return` as a statement, e.g. `if done return x;`",
+            )));
+        }
+
+        Ok(type_)
+    }
+
+    /// Checks the body of a loop, which can run again after it assigns to a
+    /// variable. If it assigned to one a condition before the loop narrowed,
+    /// the body is checked a second time without that narrowing, as the
+    /// later runs see the assigned value
+    pub fn check_loop_body(
+        &mut self,
+        check: impl Fn(&mut Self) -> Result<Type, Diagnostic>,
+    ) -> Result<Type, Diagnostic> {
+        let narrowed = self.environment.borrow().narrowed_keys();
+        let typeof_body = check(self)?;
+
+        let still_narrowed = self.environment.borrow().narrowed_keys();
+        if narrowed.iter().all(|key| still_narrowed.contains(key)) {
+            return Ok(typeof_body);
+        }
+
+        check(self)
+    }
+
+    /// Runs `check` in a new scope where the variables in `narrowings` have
+    /// the narrower types a condition showed, see `Expression::narrowings`
+    pub fn with_narrowings<R>(
+        &mut self,
+        narrowings: &[(String, Type)],
+        check: impl FnOnce(&mut Self) -> Result<R, Diagnostic>,
+    ) -> Result<R, Diagnostic> {
+        self.push_environment();
+        for (name, type_) in narrowings {
+            self.narrow(name.clone(), type_.clone());
+        }
+
+        let result = check(self);
+        self.pop_environment()?;
+        result
+    }
+
     pub fn seed(&mut self) {
         self.create(
             "print".to_owned(),
@@ -114,11 +498,51 @@ impl Interpreter<Type> {
             },
         )
         .unwrap();
+
+        for expression in prelude() {
+            expression.check_type(self).unwrap();
+        }
     }
 }
 
 pub trait Expression {
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String>;
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String>;
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic>;
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic>;
+    fn span(&self) -> Span;
     fn to_string(&self) -> String;
+
+    /// Creates what the expression declares before the rest of its block
+    /// runs, so functions can call ones declared after them
+    fn hoist(&self, _interpreter: &mut Interpreter<Value>) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    /// `hoist` for the type checker
+    fn hoist_type(&self, _type_interpreter: &mut Interpreter<Type>) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    /// The variable the expression reads, if it's only a variable
+    fn variable_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the expression is `null` itself, for `x == null`
+    fn is_null_literal(&self) -> bool {
+        false
+    }
+
+    /// Adds the variables the expression assigns to anywhere inside it to
+    /// `names`, including in the bodies of functions it declares
+    fn assigned(&self, _names: &mut Vec<String>) {}
+
+    /// The narrower types of the variables a condition tests when it evaluates
+    /// to `truthy`, e.g. `x != null` being true means `x` isn't `null`
+    fn narrowings(
+        &self,
+        _type_interpreter: &mut Interpreter<Type>,
+        _truthy: bool,
+    ) -> Vec<(String, Type)> {
+        Vec::new()
+    }
 }
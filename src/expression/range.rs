@@ -0,0 +1,105 @@
+use std::ops::ControlFlow;
+
+use crate::{
+    data::range::Range,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{Expression, Interpreter};
+
+pub struct RangeLiteral {
+    pub start: Box<dyn Expression>,
+    pub end: Box<dyn Expression>,
+    pub step: Option<Box<dyn Expression>>,
+    pub inclusive: bool,
+    pub span: Span,
+}
+
+/// A bound of the range, or the signal to pass on when evaluating it jumped
+fn interpret_integer(
+    expression: &dyn Expression,
+    interpreter: &mut Interpreter<Value>,
+) -> Result<ControlFlow<Value, i64>, Diagnostic> {
+    match expression.interpret(interpreter)? {
+        Value::Integer(integer) => Ok(ControlFlow::Continue(integer)),
+        signal if signal.is_signal() => Ok(ControlFlow::Break(signal)),
+        value => Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            format!(
+                "Range bounds must be integers but got {}",
+                value.to_log_string()
+            ),
+        )
+        .with_span(expression.span())),
+    }
+}
+
+impl Expression for RangeLiteral {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        for bound in [Some(&self.start), Some(&self.end), self.step.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            let bound_type = type_interpreter.check_operand(bound.as_ref())?;
+            if !bound_type.is_sub_type_of(&Type::BaseType(BaseType::Integer)) {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!("Range bounds must be integers but got type {}", bound_type),
+                )
+                .with_span(bound.span()));
+            }
+        }
+
+        Ok(Type::List(Box::from(Type::BaseType(BaseType::Integer))))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let start = match interpret_integer(self.start.as_ref(), interpreter)? {
+            ControlFlow::Continue(start) => start,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+        let end = match interpret_integer(self.end.as_ref(), interpreter)? {
+            ControlFlow::Continue(end) => end,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+        let step = match &self.step {
+            Some(step) => match interpret_integer(step.as_ref(), interpreter)? {
+                ControlFlow::Continue(step) => step,
+                ControlFlow::Break(signal) => return Ok(signal),
+            },
+            None => 1,
+        };
+
+        Range::new(start, end, step, self.inclusive)
+            .map(Value::Range)
+            .map_err(|err| err.or_span(self.span))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.start.assigned(names);
+        self.end.assigned(names);
+        if let Some(step) = &self.step {
+            step.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"Range\", \"start\": {}, \"end\": {}, \"step\": {}, \"inclusive\": {} }}",
+            self.start.to_string(),
+            self.end.to_string(),
+            match &self.step {
+                Some(step) => step.to_string(),
+                None => "null".to_owned(),
+            },
+            self.inclusive
+        )
+    }
+}
@@ -1,4 +1,6 @@
 use crate::{
+    diagnostic::Diagnostic,
+    span::Span,
     types::{BaseType, Type},
     value::Value,
 };
@@ -7,28 +9,42 @@ use super::{Expression, Interpreter};
 
 pub struct Return {
     pub expression: Option<Box<dyn Expression>>,
+    pub span: Span,
 }
 
 impl Expression for Return {
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
         match &self.expression {
             None => Ok(Type::Return(Box::from(Type::BaseType(BaseType::Null)))),
             Some(expression) => Ok(Type::Return(Box::from(
-                expression.check_type(type_interpreter)?,
+                type_interpreter.check_operand(expression.as_ref())?,
             ))),
         }
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
         let mut return_value = Value::Null;
 
         if let Some(expression) = &self.expression {
             return_value = expression.interpret(interpreter)?;
+            if return_value.is_signal() {
+                return Ok(return_value);
+            }
         }
 
         Ok(Value::Return(Box::from(return_value)))
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        if let Some(expression) = &self.expression {
+            expression.assigned(names);
+        }
+    }
+
     fn to_string(&self) -> String {
         format!(
             "{{ \"type\": \"Return\", \"expression\": \"{}\" }}",
@@ -0,0 +1,127 @@
+use std::ops::ControlFlow;
+
+use crate::{
+    data::list::resolve_slice,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{Expression, Interpreter};
+
+/// `target[start..end]`, either bound can be left out
+pub struct Slice {
+    pub target: Box<dyn Expression>,
+    pub start: Option<Box<dyn Expression>>,
+    pub end: Option<Box<dyn Expression>>,
+    pub span: Span,
+}
+
+fn interpret_bound(
+    bound: &Option<Box<dyn Expression>>,
+    interpreter: &mut Interpreter<Value>,
+) -> Result<ControlFlow<Value, Option<i64>>, Diagnostic> {
+    let Some(expression) = bound else {
+        return Ok(ControlFlow::Continue(None));
+    };
+
+    match expression.interpret(interpreter)? {
+        Value::Integer(integer) => Ok(ControlFlow::Continue(Some(integer))),
+        signal if signal.is_signal() => Ok(ControlFlow::Break(signal)),
+        _ => Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            String::from("Slice bound did not resolve to an integer"),
+        )
+        .with_span(expression.span())),
+    }
+}
+
+impl Expression for Slice {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let target = type_interpreter.check_operand(self.target.as_ref())?;
+
+        for bound in [&self.start, &self.end].into_iter().flatten() {
+            let bound_type = type_interpreter.check_operand(bound.as_ref())?;
+            if !bound_type.is_sub_type_of(&Type::BaseType(BaseType::Integer)) {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!(
+                        "Cannot slice with a non integer bound of type {}",
+                        bound_type
+                    ),
+                )
+                .with_span(bound.span()));
+            }
+        }
+
+        match target {
+            Type::List(_) | Type::BaseType(BaseType::String) => Ok(target),
+            target => Err(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!("Cannot slice a value of type {}", target),
+            )
+            .with_span(self.target.span())),
+        }
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let target = self.target.interpret(interpreter)?;
+        if target.is_signal() {
+            return Ok(target);
+        }
+
+        let start = match interpret_bound(&self.start, interpreter)? {
+            ControlFlow::Continue(start) => start,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+        let end = match interpret_bound(&self.end, interpreter)? {
+            ControlFlow::Continue(end) => end,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+
+        let result = match target {
+            Value::List(list) => list.slice(start, end).map(Value::List),
+            Value::Range(range) => range.slice(start, end).map(Value::Range),
+            Value::String(string) => {
+                let chars: Vec<char> = string.chars().collect();
+                resolve_slice(chars.len(), start, end)
+                    .map(|(start, end)| Value::String(chars[start..end].iter().collect()))
+            }
+            target => {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!("Cannot slice {}", target.to_log_string()),
+                )
+                .with_span(self.target.span()))
+            }
+        };
+
+        result.map_err(|err| err.or_span(self.span))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.target.assigned(names);
+        for bound in [&self.start, &self.end].into_iter().flatten() {
+            bound.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        let bound = |bound: &Option<Box<dyn Expression>>| match bound {
+            Some(expression) => expression.to_string(),
+            None => "null".to_owned(),
+        };
+
+        format!(
+            "{{ \"type\": \"Slice\", \"target\": {}, \"start\": {}, \"end\": {} }}",
+            self.target.to_string(),
+            bound(&self.start),
+            bound(&self.end)
+        )
+    }
+}
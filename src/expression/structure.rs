@@ -0,0 +1,187 @@
+use crate::{
+    data::structure::Struct,
+    diagnostic::{Diagnostic, DiagnosticKind},
+    environment::Variable,
+    span::Span,
+    types::{BaseType, StructType, Type, TypeDefinition},
+    value::Value,
+};
+
+use super::{doc_to_string, Expression, Interpreter};
+
+/// `struct Point { x: number, mutable y: number }`, declares a type in the
+/// current scope
+pub struct StructDeclaration {
+    pub struct_type: StructType,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl Expression for StructDeclaration {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let mut struct_type = self.struct_type.clone();
+        for field in struct_type.fields.iter_mut() {
+            field.type_ = type_interpreter.resolve(&field.type_);
+        }
+
+        type_interpreter
+            .create_type(
+                self.struct_type.name.clone(),
+                TypeDefinition::Struct(struct_type),
+            )
+            .map_err(|err| err.or_span(self.span))?;
+
+        Ok(Type::BaseType(BaseType::Null))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        interpreter
+            .create_type(
+                self.struct_type.name.clone(),
+                TypeDefinition::Struct(self.struct_type.clone()),
+            )
+            .map_err(|err| err.or_span(self.span))?;
+
+        Ok(Value::Null)
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"StructDeclaration\", \"name\": \"{}\", \"doc\": {}, \"fields\": [{}] }}",
+            self.struct_type.name,
+            doc_to_string(&self.doc),
+            self.struct_type
+                .fields
+                .iter()
+                .map(|field| format!(
+                    "{{ \"name\": \"{}\", \"mutable\": {}, \"type\": \"{:?}\" }}",
+                    field.name, field.mutable, field.type_
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+/// `Point { x: 1, y: 2 }`, every field of the struct has to be given
+pub struct StructLiteral {
+    pub name: String,
+    pub fields: Vec<(String, Box<dyn Expression>)>,
+    pub span: Span,
+}
+
+impl StructLiteral {
+    fn value_of(&self, field: &str) -> Option<&dyn Expression> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value.as_ref())
+    }
+}
+
+impl Expression for StructLiteral {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let struct_type = type_interpreter
+            .get_struct(&self.name)
+            .map_err(|err| err.or_span(self.span))?;
+
+        for (name, value) in self.fields.iter() {
+            if struct_type.field(name).is_none() {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!("Struct {} has no field named `{}`", self.name, name),
+                )
+                .with_span(value.span()));
+            }
+        }
+
+        for field in struct_type.fields.iter() {
+            let Some(value) = self.value_of(&field.name) else {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!("Missing field `{}` in {}", field.name, self.name),
+                )
+                .with_span(self.span));
+            };
+
+            let value_type = type_interpreter.check_operand(value)?;
+            if !value_type.is_sub_type_of(&field.type_) {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!(
+                        "Field `{}` of {} was {} which doesn't subtype {}",
+                        field.name, self.name, value_type, field.type_
+                    ),
+                )
+                .with_span(value.span()));
+            }
+        }
+
+        Ok(Type::Named(self.name.clone(), Vec::new()))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let struct_type = interpreter
+            .get_struct(&self.name)
+            .map_err(|err| err.or_span(self.span))?;
+
+        let mut fields = Vec::new();
+        for field in struct_type.fields.iter() {
+            let Some(value) = self.value_of(&field.name) else {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!("Missing field `{}` in {}", field.name, self.name),
+                )
+                .with_span(self.span));
+            };
+
+            let value = value.interpret(interpreter)?;
+            if value.is_signal() {
+                return Ok(value);
+            }
+
+            fields.push((
+                field.name.clone(),
+                Variable {
+                    mutable: field.mutable,
+                    value,
+                },
+            ));
+        }
+
+        Ok(Value::Struct(Struct {
+            name: self.name.clone(),
+            fields,
+        }))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        for (_, value) in self.fields.iter() {
+            value.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"StructLiteral\", \"name\": \"{}\", \"fields\": [{}] }}",
+            self.name,
+            self.fields
+                .iter()
+                .map(|(name, value)| format!(
+                    "{{ \"name\": \"{}\", \"value\": {} }}",
+                    name,
+                    value.to_string()
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
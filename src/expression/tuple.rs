@@ -0,0 +1,52 @@
+use std::ops::ControlFlow;
+
+use crate::{data::tuple::Tuple, diagnostic::Diagnostic, span::Span, types::Type, value::Value};
+
+use super::{Expression, Interpreter};
+
+/// `(1, "a", true)`, a single element tuple needs a trailing comma `(1,)`
+pub struct TupleLiteral {
+    pub elements: Vec<Box<dyn Expression>>,
+    pub span: Span,
+}
+
+impl Expression for TupleLiteral {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        Ok(Type::Tuple(
+            self.elements
+                .iter()
+                .map(|expression| type_interpreter.check_operand(expression.as_ref()))
+                .collect::<Result<Vec<Type>, Diagnostic>>()?,
+        ))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let elements = match interpreter.operands(&self.elements)? {
+            ControlFlow::Continue(elements) => elements,
+            ControlFlow::Break(signal) => return Ok(signal),
+        };
+
+        Ok(Value::Tuple(Tuple { elements }))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        for element in self.elements.iter() {
+            element.assigned(names);
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"TupleLiteral\", \"elements\": [{}] }}",
+            self.elements
+                .iter()
+                .map(|elem| elem.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{
+    diagnostic::Diagnostic,
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{Expression, Interpreter};
+
+/// `x is number`, whether a value is of a type. As a condition it narrows
+/// the type of the variable it tests
+pub struct TypeTest {
+    pub value: Box<dyn Expression>,
+    pub type_: Type,
+    pub span: Span,
+}
+
+impl Expression for TypeTest {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        type_interpreter.check_operand(self.value.as_ref())?;
+
+        Ok(Type::BaseType(BaseType::Boolean))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let value = self.value.interpret(interpreter)?;
+        if value.is_signal() {
+            return Ok(value);
+        }
+
+        Ok(Value::Boolean(
+            value.is_instance_of(&interpreter.resolve(&self.type_)),
+        ))
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.value.assigned(names);
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"TypeTest\", \"value\": {}, \"tested\": \"{:?}\" }}",
+            self.value.to_string(),
+            self.type_
+        )
+    }
+
+    fn narrowings(
+        &self,
+        type_interpreter: &mut Interpreter<Type>,
+        truthy: bool,
+    ) -> Vec<(String, Type)> {
+        match self.value.variable_name() {
+            Some(name) => {
+                let tested = type_interpreter.resolve(&self.type_);
+                type_interpreter.narrowing(name, &tested, truthy)
+            }
+            None => Vec::new(),
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
     tokeniser::Operator,
     types::{BaseType, Type},
     value::Value,
@@ -9,15 +11,26 @@ use super::{Expression, Interpreter};
 pub struct Unary {
     pub operator: Operator,
     pub value: Box<dyn Expression>,
+    pub span: Span,
 }
 
 impl Expression for Unary {
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
-        let value_type = self.value.check_type(type_interpreter)?;
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        let value_type = type_interpreter.check_operand(self.value.as_ref())?;
+        let value_type = match self.operator {
+            Operator::Not => {
+                type_interpreter.infer(&value_type, &Type::BaseType(BaseType::Boolean))
+            }
+            _ => type_interpreter.infer(&value_type, &Type::BaseType(BaseType::Number)),
+        };
 
         if self.operator == Operator::Not {
             if !value_type.is_sub_type_of(&Type::BaseType(BaseType::Boolean)) {
-                return Err(String::from("Cannot not use ! on a non boolean value"));
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    String::from("Cannot not use ! on a non boolean value"),
+                )
+                .with_span(self.span));
             }
 
             return Ok(Type::BaseType(BaseType::Boolean));
@@ -25,41 +38,81 @@ impl Expression for Unary {
 
         if self.operator == Operator::Minus {
             if !value_type.is_sub_type_of(&Type::BaseType(BaseType::Number)) {
-                return Err(String::from(
-                    "Cannot not use - (minus / negation) on a non number value",
-                ));
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    String::from("Cannot not use - (minus / negation) on a non number value"),
+                )
+                .with_span(self.span));
+            }
+
+            if value_type.is_sub_type_of(&Type::BaseType(BaseType::Integer)) {
+                return Ok(Type::BaseType(BaseType::Integer));
             }
 
             return Ok(Type::BaseType(BaseType::Number));
         }
 
-        Err(format!(
-            "Could not use operator {:?} on value with type {:?}",
-            self.operator, value_type
-        ))
+        Err(Diagnostic::error(
+            DiagnosticKind::TypeMismatch,
+            format!(
+                "Could not use operator {:?} on value with type {}",
+                self.operator, value_type
+            ),
+        )
+        .with_span(self.span))
     }
 
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        let value = self.value.interpret(interpreter)?;
+        if value.is_signal() {
+            return Ok(value);
+        }
+
         if self.operator == Operator::Not {
-            return self.value.interpret(interpreter)?.not();
+            return (!value).map_err(|err| err.or_span(self.span));
         }
 
         if self.operator != Operator::Minus {
-            return Err(format!("Trying to perform a unary operation without minus"));
+            return Err(Diagnostic::error(
+                DiagnosticKind::Internal,
+                "Trying to perform a unary operation without minus".to_string(),
+            )
+            .with_span(self.span));
         }
 
-        match self.value.interpret(interpreter)? {
+        let result = match value {
+            Value::Integer(integer) => integer
+                .checked_neg()
+                .map(Value::Integer)
+                .ok_or(format!("Integer overflow when negating {}", integer)),
             Value::Number(number) => Ok(Value::Number(-number)),
-            Value::Return(_) => Err(format!("Cannot negate return")),
-            Value::List(list) => Err(format!("Cannot negate list: {}", list.to_string())),
-            Value::Null => Err(format!("Cannot negate null")),
+            Value::Return(_) => Err("Cannot negate return".to_string()),
+            Value::Break(..) | Value::Continue(_) => Err("Cannot negate loop signal".to_string()),
+            Value::List(list) => Err(format!("Cannot negate list: {}", list)),
+            Value::Range(range) => Err(format!("Cannot negate range: {}", range)),
+            Value::Tuple(tuple) => Err(format!("Cannot negate tuple: {}", tuple)),
+            Value::Struct(structure) => Err(format!("Cannot negate struct: {}", structure)),
+            Value::Enum(variant) => Err(format!("Cannot negate enum: {}", variant)),
+            Value::Null => Err("Cannot negate null".to_string()),
             Value::String(string) => Err(format!("Cannot negate string: {}", string)),
             Value::Boolean(boolean) => Err(format!("Cannot negate boolean: {}", boolean)),
             Value::Function(function) => Err(format!(
                 "Cannot negate function: {}",
                 function.borrow().signature()
             )),
-        }
+        };
+
+        result.map_err(|message| {
+            Diagnostic::error(DiagnosticKind::Runtime, message).with_span(self.span)
+        })
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.value.assigned(names);
     }
 
     fn to_string(&self) -> String {
@@ -69,4 +122,16 @@ impl Expression for Unary {
             self.value.to_string()
         )
     }
+
+    /// `!condition` shows the opposite of what `condition` does
+    fn narrowings(
+        &self,
+        type_interpreter: &mut Interpreter<Type>,
+        truthy: bool,
+    ) -> Vec<(String, Type)> {
+        match self.operator {
+            Operator::Not => self.value.narrowings(type_interpreter, !truthy),
+            _ => Vec::new(),
+        }
+    }
 }
@@ -1,33 +1,52 @@
-use crate::{types::Type, value::Value};
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::Type,
+    value::Value,
+};
 
 use super::{Expression, Interpreter};
 
 pub struct Variable {
     pub name: String,
+    pub span: Span,
 }
 
 impl Expression for Variable {
-    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, String> {
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
         match interpreter.get(self.name.clone()) {
             Some(variable) => Ok(variable.value),
-            None => Err(format!(
-                "Could not access variable: {}. It was never created.",
-                self.name
-            )),
+            None => Err(Diagnostic::error(
+                DiagnosticKind::UndefinedVariable,
+                format!(
+                    "Could not access variable: {}. It was never created.",
+                    self.name
+                ),
+            )
+            .with_span(self.span)),
         }
     }
 
-    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, String> {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
         match type_interpreter.get(self.name.clone()) {
-            Some(variable) => Ok(variable.value),
-            None => Err(format!(
-                "Cannot get type of variable with name - {}",
-                self.name
-            )),
+            Some(variable) => Ok(type_interpreter.resolve(&variable.value)),
+            None => Err(Diagnostic::error(
+                DiagnosticKind::UndefinedVariable,
+                format!("Cannot get type of variable with name - {}", self.name),
+            )
+            .with_span(self.span)),
         }
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn to_string(&self) -> String {
         format!("{{ \"type\": \"Variable\", \"name\": \"{}\" }}", self.name)
     }
+
+    fn variable_name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
 }
@@ -0,0 +1,97 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+use super::{
+    jump::{loop_control, LoopControl},
+    Expression, Interpreter,
+};
+
+pub struct While {
+    pub label: Option<String>,
+    pub condition: Box<dyn Expression>,
+    pub body: Box<dyn Expression>,
+    pub span: Span,
+}
+
+impl Expression for While {
+    fn check_type(&self, type_interpreter: &mut Interpreter<Type>) -> Result<Type, Diagnostic> {
+        type_interpreter.push_loop(self.label.clone(), false);
+        // the condition is tested again after every run of the body
+        let typeof_body = type_interpreter.check_loop_body(|type_interpreter| {
+            let typeof_condition = type_interpreter.check_operand(self.condition.as_ref())?;
+            let typeof_condition =
+                type_interpreter.infer(&typeof_condition, &Type::BaseType(BaseType::Boolean));
+
+            if !typeof_condition.is_sub_type_of(&Type::BaseType(BaseType::Boolean)) {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!("Tried to use an expression that evaluated to non boolean ({}) as the condition in a `while` loop", typeof_condition),
+                )
+                .with_span(self.condition.span()));
+            }
+
+            let narrowings = self.condition.narrowings(type_interpreter, true);
+            type_interpreter.with_narrowings(&narrowings, |type_interpreter| {
+                self.body.check_type(type_interpreter)
+            })
+        });
+        type_interpreter.pop_loop();
+
+        if let Some(return_type) = typeof_body?.get_return_type() {
+            return Ok(Type::Or(
+                Box::from(Type::BaseType(BaseType::Null)),
+                Box::from(Type::Return(Box::from(return_type))),
+            ));
+        }
+
+        Ok(Type::BaseType(BaseType::Null))
+    }
+
+    fn interpret(&self, interpreter: &mut Interpreter<Value>) -> Result<Value, Diagnostic> {
+        loop {
+            match self.condition.interpret(interpreter)? {
+                Value::Boolean(true) => (),
+                Value::Boolean(false) => return Ok(Value::Null),
+                signal if signal.is_signal() => return Ok(signal),
+                _ => {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::Runtime,
+                        "Condition for `while` did not resolve to a boolean".to_string(),
+                    )
+                    .with_span(self.condition.span()))
+                }
+            }
+
+            match loop_control(&self.label, self.body.interpret(interpreter)?) {
+                LoopControl::Next => continue,
+                LoopControl::Exit(_) => return Ok(Value::Null),
+                LoopControl::Propagate(signal) => return Ok(signal),
+            }
+        }
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn assigned(&self, names: &mut Vec<String>) {
+        self.condition.assigned(names);
+        self.body.assigned(names);
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{{ \"type\": \"While\", \"label\": {}, \"condition\": {}, \"body\": {} }}",
+            match &self.label {
+                Some(label) => format!("\"{}\"", label),
+                None => "null".to_owned(),
+            },
+            self.condition.to_string(),
+            self.body.to_string()
+        )
+    }
+}
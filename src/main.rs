@@ -1,17 +1,21 @@
 use std::{
     env, fs,
     io::{self, stdout, Write},
+    process,
 };
 
 use expression::Interpreter;
 
-use crate::{types::Type, value::Value};
+use crate::{diagnostic::Diagnostic, types::Type, value::Value};
 
 pub mod callable;
 pub mod data;
+pub mod diagnostic;
 pub mod environment;
 pub mod expression;
 pub mod parser;
+pub mod pattern;
+pub mod span;
 pub mod tokeniser;
 pub mod types;
 pub mod value;
@@ -39,48 +43,602 @@ fn repl(interpreter: &mut Interpreter<Value>) {
     for line in stdin.lines() {
         let source = &line.unwrap();
 
-        let tokens = tokeniser::scan(source).unwrap();
-        let expressions = parser::parse(tokens).unwrap();
-
-        let mut last_value = Value::Null;
-        for expression in expressions.iter() {
-            println!(
-                "Type - {:#?}",
-                expression.check_type(&mut type_checker).unwrap()
-            );
-            last_value = expression.interpret(interpreter).unwrap();
+        match interpret_line(source, interpreter, &mut type_checker) {
+            Ok(last_value) => print!("{}\n> ", last_value.to_log_string()),
+            Err(diagnostic) => print!("{}> ", diagnostic.render(source, "<repl>")),
         }
-
-        print!("{}\n> ", last_value.to_log_string());
         stdout().flush().unwrap();
     }
 }
 
-fn interpret_file(filepath: &String, interpreter: &mut Interpreter<Value>) {
-    let source = &fs::read_to_string(filepath).unwrap();
-    let tokens = tokeniser::scan(source);
+fn interpret_line(
+    source: &str,
+    interpreter: &mut Interpreter<Value>,
+    type_checker: &mut Interpreter<Type>,
+) -> Result<Value, Diagnostic> {
+    check_and_interpret(source, interpreter, type_checker, |type_| {
+        println!("Type - {:#?}", type_)
+    })
+}
+
+fn interpret_file(filepath: &str, interpreter: &mut Interpreter<Value>) {
+    let source = &match fs::read_to_string(filepath) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", filepath, err);
+            process::exit(1);
+        }
+    };
 
     let mut type_checker = Interpreter::<Type>::new();
     type_checker.seed();
 
-    match tokens {
-        Err(err) => panic!("An error occured while scanning:\n-\t{}", err),
-        Ok(vec) => {
-            // println!("{:#?}", vec);
-            let expressions = parser::parse(vec).unwrap();
+    if let Err(diagnostic) = interpret_source(source, interpreter, &mut type_checker) {
+        eprint!("{}", diagnostic.render(source, filepath));
+        process::exit(1);
+    }
+
+    println!()
+}
+
+/// Checks and runs every expression in `source`, giving the value of the last
+fn interpret_source(
+    source: &str,
+    interpreter: &mut Interpreter<Value>,
+    type_checker: &mut Interpreter<Type>,
+) -> Result<Value, Diagnostic> {
+    check_and_interpret(source, interpreter, type_checker, |_| ())
+}
+
+/// `interpret_source`, calling `checked` with the type of each expression
+/// before it runs
+fn check_and_interpret(
+    source: &str,
+    interpreter: &mut Interpreter<Value>,
+    type_checker: &mut Interpreter<Type>,
+    mut checked: impl FnMut(&Type),
+) -> Result<Value, Diagnostic> {
+    let tokens = tokeniser::scan(source)?;
+    let expressions = parser::parse(tokens)?;
+
+    // functions can be called before their `let`, so they can call each other
+    for expression in expressions.iter() {
+        expression.hoist_type(type_checker)?;
+        expression.hoist(interpreter)?;
+    }
+
+    let mut last_value = Value::Null;
+    for expression in expressions.iter() {
+        checked(&expression.check_type(type_checker)?);
+        last_value = expression.interpret(interpreter)?;
+        // println!(
+        //     "--- OUTPUT ---\ntree:\n {}\nresult: {:#?}\nenvironment: {}\n",
+        //     expression.to_string(),
+        //     expression.interpret(&mut interpreter).unwrap(),
+        //     interpreter.print_environment(),
+        // );
+    }
+
+    Ok(last_value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        diagnostic::{Diagnostic, DiagnosticKind},
+        expression::Interpreter,
+        parser, tokeniser,
+        types::Type,
+        value::Value,
+    };
+
+    use super::interpret_source;
+
+    /// Scans, parses, checks and runs `source` the way a file is, giving the
+    /// value of its last expression
+    fn run(source: &str) -> Result<Value, Diagnostic> {
+        let mut interpreter = Interpreter::<Value>::new();
+        interpreter.seed();
+        let mut type_checker = Interpreter::<Type>::new();
+        type_checker.seed();
+
+        interpret_source(source, &mut interpreter, &mut type_checker)
+    }
+
+    /// The value `source` ends with, as the REPL would show it
+    fn value(source: &str) -> String {
+        match run(source) {
+            Ok(value) => value.to_log_string(),
+            Err(diagnostic) => panic!("{} failed: {}", source, diagnostic.message),
+        }
+    }
+
+    /// The diagnostic `source` fails with
+    fn error(source: &str) -> Diagnostic {
+        match run(source) {
+            Ok(value) => panic!("{} should fail but gave {}", source, value.to_log_string()),
+            Err(diagnostic) => diagnostic,
+        }
+    }
+
+    #[test]
+    fn rejects_jumps_in_value_position() {
+        let jumps = [
+            "loop { print(if true { break; } else { 1; }); };",
+            "loop { let l = [1, if true { break; } else { 2; }]; };",
+            "loop { print(\"a${if true { break; } else { 1; }}\"); };",
+            "let v = loop { let t = (1, if true { break 5; } else { 2; }); }; v;",
+            "'outer: loop { loop { -(if true { break 'outer; } else { 1; }); }; };",
+            "for i in 0..3 { let x = i + (if true { continue; } else { 1; }); };",
+            "let f = (x: integer) => { print(if x > 0 return 1); }; f(1);",
+        ];
+
+        for source in jumps {
+            assert_eq!(
+                error(source).kind,
+                DiagnosticKind::TypeMismatch,
+                "{}",
+                source
+            );
+        }
+
+        // loops inside an operand are still theirs to leave
+        assert_eq!(value("print(loop { break 5; });"), "\"5\"");
+        assert_eq!(
+            value("let v = loop { break if true { 3; } else { 4; }; }; v;"),
+            "3"
+        );
+        assert_eq!(value("loop { if true { break; }; }; 1;"), "1");
+    }
 
+    #[test]
+    fn operands_pass_jumps_on() {
+        // without the checker a jump inside an operand still leaves its loop
+        // rather than becoming a value
+        let unchecked = |source: &str| {
+            let mut interpreter = Interpreter::<Value>::new();
+            interpreter.seed();
+            let expressions = parser::parse(tokeniser::scan(source).unwrap()).unwrap();
+            let mut last_value = Value::Null;
             for expression in expressions.iter() {
-                expression.check_type(&mut type_checker).unwrap();
-                expression.interpret(interpreter).unwrap();
-                // println!(
-                //     "--- OUTPUT ---\ntree:\n {}\nresult: {:#?}\nenvironment: {}\n",
-                //     expression.to_string(),
-                //     expression.interpret(&mut interpreter).unwrap(),
-                //     interpreter.print_environment(),
-                // );
+                last_value = expression.interpret(&mut interpreter).unwrap();
             }
+            last_value.to_log_string()
+        };
+
+        assert_eq!(
+            unchecked("loop { print(if true { break; } else { 1; }); };"),
+            "null"
+        );
+        assert_eq!(
+            unchecked("let v = loop { let t = (1, if true { break 5; } else { 2; }); }; v;"),
+            "5"
+        );
+        assert_eq!(
+            unchecked("let v = loop { [1, \"a${if true { break 6; } else { 1; }}\"]; }; v;"),
+            "6"
+        );
+        assert_eq!(
+            unchecked("let x: mutable = 0; for i in 0..3 { x = x + (if i == 1 { continue; } else { i; }); }; x;"),
+            "2"
+        );
+    }
+
+    #[test]
+    fn while_loops_break_and_continue() {
+        let odd_total = "
+            let i: mutable = 0;
+            let total: mutable = 0;
+            while i < 20 {
+                i = i + 1;
+                if i == 15 break;
+                if i / 2 * 2 == i continue;
+                total = total + i;
+            };
+            total;
+        ";
+        assert_eq!(value(odd_total), "49");
+
+        // `while` evaluates to null however it ends
+        assert_eq!(value("while true { break; };"), "null");
+        assert_eq!(value("let x: mutable = 0; while x < 3 x = x + 1;"), "null");
+
+        let labelled = "
+            let cells: mutable = 0;
+            'rows: for row in [[1, 2], [3, -4], [5, 6]] {
+                for cell in row {
+                    if cell < 0 break 'rows;
+                    if cell == 1 continue 'rows;
+                    cells = cells + 1;
+                };
+            };
+            cells;
+        ";
+        assert_eq!(value(labelled), "1");
+
+        assert_eq!(error("break;").kind, DiagnosticKind::Syntax);
+        assert_eq!(
+            error("while true { continue 'outer; };").kind,
+            DiagnosticKind::Syntax
+        );
+        assert_eq!(
+            error("while true { break 1; };").kind,
+            DiagnosticKind::TypeMismatch
+        );
+    }
+
+    #[test]
+    fn loops_evaluate_to_their_break_value() {
+        let first_square = "
+            let n: mutable = 0;
+            loop {
+                n = n + 1;
+                if n * n > 50 break n * n;
+            };
+        ";
+        assert_eq!(value(first_square), "64");
+        assert_eq!(value("loop { break; };"), "null");
+
+        let labelled = "
+            let found = 'outer: loop {
+                for i in 1..10 {
+                    if i == 4 break 'outer i * 10;
+                };
+            };
+            found;
+        ";
+        assert_eq!(value(labelled), "40");
+
+        // the type of a loop is the union of what it is broken with
+        assert_eq!(
+            value("let x: integer | string = loop { if true break 1; break \"a\"; }; x;"),
+            "1"
+        );
+        assert_eq!(
+            error("let x: integer = loop { if true break 1; break \"a\"; };").kind,
+            DiagnosticKind::TypeMismatch
+        );
+    }
 
-            println!("")
+    #[test]
+    fn match_arms_by_value_type_and_guard() {
+        let describe = "
+            let describe = (value: number | string | null) => match value {
+                0 => \"zero\",
+                string text if text.length == 0 => \"empty\",
+                string text => text,
+                integer n if n < 0 => \"negative\",
+                null => \"nothing\",
+                n => \"number\",
+            };
+        ";
+        let described = |argument: &str| value(&format!("{} describe({});", describe, argument));
+        assert_eq!(described("0"), "\"zero\"");
+        assert_eq!(described("\"\""), "\"empty\"");
+        assert_eq!(described("\"hi\""), "\"hi\"");
+        assert_eq!(described("-4"), "\"negative\"");
+        assert_eq!(described("null"), "\"nothing\"");
+        assert_eq!(described("2.5"), "\"number\"");
+    }
+
+    #[test]
+    fn rejects_non_exhaustive_matches() {
+        let missing_null = "
+            let f = (value: integer | null) => match value { integer n => n };
+            f(1);
+        ";
+        let diagnostic = error(missing_null);
+        assert_eq!(diagnostic.kind, DiagnosticKind::TypeMismatch);
+        assert!(
+            diagnostic.message.contains("null"),
+            "{}",
+            diagnostic.message
+        );
+
+        // guarded arms might not match, so they don't cover their type
+        assert_eq!(
+            error("let f = (n: integer) => match n { n if n > 0 => 1 }; f(1);").kind,
+            DiagnosticKind::TypeMismatch
+        );
+        assert_eq!(
+            value("let f = (n: integer) => match n { n if n > 0 => 1, _ => 0 }; f(-1);"),
+            "0"
+        );
+    }
+
+    #[test]
+    fn rejects_unreachable_match_arms() {
+        let after_wildcard = "
+            let f = (value: integer | null) => match value { _ => 0, null => 1 };
+            f(1);
+        ";
+        let diagnostic = error(after_wildcard);
+        assert_eq!(diagnostic.message, "Unreachable match arm");
+        assert!(diagnostic
+            .labels()
+            .iter()
+            .any(|label| label.message == "every value is already matched by this arm"));
+
+        assert_eq!(
+            error("let f = (b: boolean) => match b { true => 1, false => 0, n => 2 }; f(true);")
+                .message,
+            "Unreachable match arm"
+        );
+        assert_eq!(
+            value("let f = (n: integer) => match n { n if n > 0 => 1, n => 0, }; f(1);"),
+            "1"
+        );
+    }
+
+    #[test]
+    fn struct_fields_keep_their_mutability() {
+        let structs = "
+            struct Point { x: number, y: number };
+            struct Player { name: string, mutable position: Point, mutable score: integer };
+            let player: mutable = Player { name: \"kyle\", position: Point { x: 0, y: 0 }, score: 0 };
+        ";
+        let program = |rest: &str| format!("{} {}", structs, rest);
+
+        assert_eq!(
+            value(&program("player.score = player.score + 10; player.score;")),
+            "10"
+        );
+        assert_eq!(
+            value(&program(
+                "player.position = Point { x: 3, y: 4 }; player.position.y;"
+            )),
+            "4"
+        );
+
+        let immutable_field = error(&program("player.name = \"bob\";"));
+        assert_eq!(immutable_field.kind, DiagnosticKind::Immutable);
+        assert_eq!(
+            immutable_field.message,
+            "Field `name` of Player is not mutable"
+        );
+
+        // a mutable field inside an immutable one can't be reassigned either
+        assert_eq!(
+            error(&program("player.position.x = 1;")).kind,
+            DiagnosticKind::Immutable
+        );
+        assert_eq!(
+            error(&program("let fixed = player; fixed.score = 1;")).kind,
+            DiagnosticKind::Immutable
+        );
+        assert_eq!(
+            error(&program("player.health = 1;")).kind,
+            DiagnosticKind::TypeMismatch
+        );
+    }
+
+    #[test]
+    fn partial_application_and_pipes() {
+        let functions = "
+            let add = (a: integer, b: integer): integer => a + b;
+            let scale = (factor: integer, x: integer): integer => x * factor;
+            let increment = add(1);
+        ";
+        let program = |rest: &str| format!("{} {}", functions, rest);
+
+        assert_eq!(value(&program("increment(41);")), "42");
+        assert_eq!(
+            value(&program("5 |> add(2) |> scale(3) |> increment;")),
+            "22"
+        );
+        assert_eq!(
+            value(&program("[1, 2, 3] |> map(scale(10));")),
+            "[10,20,30]"
+        );
+
+        // a partial application is a function of the remaining parameters
+        assert_eq!(
+            value(&program("let f: (integer) => integer = add(1); f(1);")),
+            "2"
+        );
+        assert_eq!(
+            error(&program("let f: (integer, integer) => integer = add(1);")).kind,
+            DiagnosticKind::TypeMismatch
+        );
+        assert_eq!(
+            error(&program("\"a\" |> increment;")).kind,
+            DiagnosticKind::TypeMismatch
+        );
+        assert_eq!(
+            error(&program("1 |> add(1, 2);")).kind,
+            DiagnosticKind::ArgumentCount
+        );
+    }
+
+    #[test]
+    fn generic_functions_bind_their_type_parameters() {
+        let apply = "let apply = <T, U>(xs: T[], f: (T) => U): U[] => map(xs, f);";
+        assert_eq!(
+            value(&format!("{} apply([1, 2], (x: integer) => x * 2);", apply)),
+            "[2,4]"
+        );
+        assert_eq!(
+            value(&format!(
+                "{} apply([\"a\"], (s: string) => s + \"!\");",
+                apply
+            )),
+            "[a!]"
+        );
+
+        let identity = "let id = <T>(x: T): T => x;";
+        let mismatch = error(&format!("{} let y: string = id(1);", identity));
+        assert_eq!(mismatch.kind, DiagnosticKind::TypeMismatch);
+
+        // without `<T>` the `T` is a type name, not a parameter
+        assert!(run("let f = (x: T) => x; f(1);").is_err());
+    }
+
+    #[test]
+    fn functions_call_each_other_before_their_declaration() {
+        let greeting = "
+            let message = greet(\"world\");
+            let greet = (name: string) => \"hello ${name}${punctuation()}\";
+            let punctuation = () => \"!\";
+            message;
+        ";
+        assert_eq!(value(greeting), "\"hello world!\"");
+
+        let parity = "
+            let isEven = (n) => if n == 0 { true; } else { isOdd(n - 1); };
+            let isOdd = (n) => if n == 0 { false; } else { isEven(n - 1); };
+        ";
+        assert_eq!(value(&format!("{} isEven(10);", parity)), "true");
+        assert_eq!(value(&format!("{} isOdd(10);", parity)), "false");
+
+        // the return type found across the recursion is checked like any other
+        assert_eq!(
+            value(&format!("{} let even: boolean = isOdd(7); even;", parity)),
+            "true"
+        );
+        assert_eq!(
+            error(&format!("{} let even: string = isEven(2);", parity)).kind,
+            DiagnosticKind::TypeMismatch
+        );
+
+        // only functions are hoisted, other values still need their `let` first
+        assert_eq!(
+            error("let y = x + 1; let x = 1;").kind,
+            DiagnosticKind::UndefinedVariable
+        );
+    }
+
+    #[test]
+    fn ranges_are_read_only() {
+        let diagnostic = error("let r: mutable = 0..1_000_000_000_000; r[0] = 1;");
+        assert_eq!(diagnostic.kind, DiagnosticKind::Runtime);
+        assert_eq!(value("let r: mutable = 0..3; r = 1..4; r[0];"), "1");
+        assert_eq!(
+            value("let r = 0..3; let l: mutable = map(r, (x: integer) => x); l[0] = 5; l;"),
+            "[5,1,2]"
+        );
+        assert_eq!(
+            value("(0..=9223372036854775807)[-1];"),
+            "9223372036854775807"
+        );
+    }
+
+    #[test]
+    fn ranges_longer_than_an_integer() {
+        assert_eq!(
+            value("(0..9223372036854775807).length;"),
+            "9223372036854775807"
+        );
+        let diagnostic = error("(0..=9223372036854775807).length;");
+        assert_eq!(diagnostic.kind, DiagnosticKind::Runtime);
+
+        assert_eq!(
+            value("let [first, ...middle, last] = 0..=9223372036854775807; (first, middle[-1], last);"),
+            "(0,9223372036854775806,9223372036854775807)"
+        );
+    }
+
+    #[test]
+    fn rejects_aliases_defined_as_themselves() {
+        let direct = error("type Loop = integer | Loop;");
+        assert_eq!(direct.message, "Type alias Loop is defined as itself");
+
+        let mutual = error("type A = B; type B = A;");
+        assert_eq!(mutual.message, "Type alias B is defined as itself");
+        assert!(mutual
+            .notes()
+            .iter()
+            .any(|note| note.contains("`B = A = B`")));
+
+        let through_unions = error("type A = B | null; type B = C; type C = A | string;");
+        assert!(through_unions
+            .notes()
+            .iter()
+            .any(|note| note.contains("`C = A = B = C`")));
+
+        // aliases can refer to ones declared after them, and to each other
+        // when something is in between
+        assert_eq!(
+            value("type A = B[]; type B = integer | A; let x: A = [1, [2]]; x;"),
+            "[1,[2]]"
+        );
+        assert_eq!(value("type A = B; type B = integer; let x: A = 1; x;"), "1");
+    }
+
+    #[test]
+    fn loops_forget_narrowings_their_body_assigns() {
+        // the second run of the body sees the value the first one assigned
+        let assigned_after_use = [
+            "let x: mutable integer | null = 1; if x != null { let i: mutable = 0; while i < 2 { i = i + 1; print(x * 2); x = null; }; };",
+            "let x: mutable integer | null = 1; if x != null { for i in 0..2 { print(x * 2); x = null; }; };",
+            "let x: mutable integer | null = 1; if x != null { loop { print(x * 2); x = null; }; };",
+            "let x: mutable integer | null = 1; if x != null { while x > 0 { x = null; }; };",
+        ];
+
+        for source in assigned_after_use {
+            assert_eq!(
+                error(source).kind,
+                DiagnosticKind::TypeMismatch,
+                "{}",
+                source
+            );
         }
+
+        // loops that leave the variable alone, or test it again, keep what
+        // is known about it
+        assert_eq!(
+            value("let x: integer | null = 2; let t: mutable = 0; if x != null { for i in 0..3 { t = t + x; }; }; t;"),
+            "6"
+        );
+        assert_eq!(
+            value("let x: mutable integer | null = 3; let t: mutable = 0; while x != null { t = t + x; x = if x > 1 { x - 1; } else { null; }; }; t;"),
+            "6"
+        );
+    }
+
+    #[test]
+    fn functions_ignore_narrowings_of_mutable_variables_they_capture() {
+        let escaped = error(
+            "let x: mutable integer | null = 1; let f: mutable = (): integer => 0; if x != null { f = (): integer => x * 2; }; x = null; f();",
+        );
+        assert_eq!(escaped.kind, DiagnosticKind::TypeMismatch);
+
+        // an immutable variable can't change before the call
+        assert_eq!(
+            value("let y: integer | null = 1; let f: mutable = (): integer => 0; if y != null { f = (): integer => y * 2; }; f();"),
+            "2"
+        );
+        // conditions inside the body still narrow
+        assert_eq!(
+            value("let x: mutable integer | null = 1; let f = (): integer => { if x == null return 0; x * 2; }; x = 3; f();"),
+            "6"
+        );
+
+        // nor can the caller trust them after calling a function that assigns
+        let reset = error(
+            "let x: mutable integer | null = 1; let reset = () => { x = null; }; if x != null { reset(); x * 2; };",
+        );
+        assert_eq!(reset.kind, DiagnosticKind::TypeMismatch);
+        let reset_in_loop = error(
+            "let x: mutable integer | null = 1; let reset = () => { x = null; }; if x != null { for i in 0..2 { print(x * 2); reset(); }; };",
+        );
+        assert_eq!(reset_in_loop.kind, DiagnosticKind::TypeMismatch);
+    }
+
+    #[test]
+    fn conditions_forget_narrowings_their_right_side_assigns() {
+        let reassigned = error(
+            "let x: mutable integer | null = 1; if x != null & { x = null; true; } { x * 2; };",
+        );
+        assert_eq!(reassigned.kind, DiagnosticKind::TypeMismatch);
+        let nested = error(
+            "let x: mutable integer | null = 1; if (x != null & true) & { x = null; true; } { x * 2; };",
+        );
+        assert_eq!(nested.kind, DiagnosticKind::TypeMismatch);
+
+        // a test after the assignment still narrows
+        assert_eq!(
+            value("let x: mutable integer | null = null; let y: mutable = 0; if { x = 2; true; } & x != null { y = x * 2; }; y;"),
+            "4"
+        );
     }
 }
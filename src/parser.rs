@@ -1,45 +1,90 @@
 use std::rc::Rc;
 
 use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
     environment,
     expression::Expression,
     expression::{
-        assign::Assign,
+        alias::TypeAlias,
+        assign::{Accessor, Assign},
         binary::Binary,
         body::Body,
         call::Call,
         declare::Declare,
+        enumeration::{EnumConstructor, EnumDeclaration},
+        for_expression::For,
         function::{Function, FunctionArgument},
         if_expression::If,
+        index::Index,
+        interface::{Implement, InterfaceDeclaration},
+        jump::{Jump, JumpKind},
         list::ListLiteral,
         literal::Literal,
+        loop_expression::Loop,
+        match_expression::{Match, MatchArm},
+        member::Member,
+        method_call::MethodCall,
+        range::RangeLiteral,
         return_expression::Return,
+        slice::Slice,
+        structure::{StructDeclaration, StructLiteral},
+        tuple::TupleLiteral,
+        type_test::TypeTest,
         unary::Unary,
         variable::Variable,
+        while_expression::While,
+    },
+    pattern::Pattern,
+    span::Span,
+    tokeniser::{self, Keyword, Operator, StringPart, Symbol, Token, TypeLiteral},
+    types::{
+        BaseType, EnumType, EnumVariant, FunctionType, InterfaceMethod, StructField, StructType,
+        Type,
     },
-    tokeniser::{self, Keyword, Operator, Symbol, Token, TypeLiteral},
-    types::{BaseType, FunctionType, Type},
 };
 
+fn syntax_error(message: String, span: Span) -> Diagnostic {
+    Diagnostic::error(DiagnosticKind::Syntax, message).with_span(span)
+}
+
 struct Parser {
     tokens: Vec<Token>,
     index: usize,
+    /// Set while parsing the return type of a function definition, where the
+    /// `=>` after a parenthesised type belongs to the function and not the type
+    in_return_type: bool,
+    /// Set while parsing the head of an `if`, `while`, `for` or `match`, where
+    /// `name {` starts the body and not a struct literal
+    no_struct_literals: bool,
+    /// Set while parsing a match guard, where `(name) =>` is the guard
+    /// followed by the arm and not a function with an unannotated parameter
+    in_match_guard: bool,
+    /// Type variables in scope, the parameters of enclosing generic functions
+    /// and enums, or `Self` inside an interface
+    type_parameters: Vec<String>,
 }
 
 impl Parser {
+    fn new(tokens: Vec<Token>) -> Parser {
+        Parser {
+            tokens,
+            index: 0,
+            in_return_type: false,
+            no_struct_literals: false,
+            in_match_guard: false,
+            type_parameters: Vec::new(),
+        }
+    }
+
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.index += 1;
         }
-        return self.previous();
-    }
-
-    fn advance_symbol(&mut self) -> Symbol {
-        self.advance().symbol
+        self.previous()
     }
 
     fn peek(&mut self) -> Token {
-        return self.tokens[self.index].clone();
+        self.tokens[self.index].clone()
     }
 
     fn safe_peek_symbol(&mut self) -> Option<Symbol> {
@@ -51,18 +96,22 @@ impl Parser {
     }
 
     pub fn previous(&mut self) -> Token {
-        return self.tokens[self.index - 1].clone();
+        self.tokens[self.index - 1].clone()
     }
 
     fn is_at_end(&mut self) -> bool {
-        match self.tokens.get(self.index).cloned() {
+        matches!(
+            self.tokens.get(self.index),
             Some(Token {
                 symbol: Symbol::Fin,
-                line: _,
-                index: _,
-            }) => true,
-            _ => false,
-        }
+                ..
+            })
+        )
+    }
+
+    /// Span from `start` up to and including the last consumed token
+    fn span_from(&mut self, start: Span) -> Span {
+        start.to(self.previous().span)
     }
 
     fn check(&mut self, symbol: Symbol) -> bool {
@@ -70,7 +119,7 @@ impl Parser {
             return false;
         }
 
-        return self.peek().symbol == symbol;
+        self.peek().symbol == symbol
     }
 
     fn check_advance(&mut self, symbol: Symbol) -> bool {
@@ -83,7 +132,7 @@ impl Parser {
             return true;
         }
 
-        return false;
+        false
     }
 
     fn match_symbols(&mut self, symbols: &[Symbol]) -> Option<Token> {
@@ -93,7 +142,7 @@ impl Parser {
             }
         }
 
-        return None;
+        None
     }
 
     fn match_operators(&mut self, operators: &[Operator]) -> Option<Operator> {
@@ -104,7 +153,7 @@ impl Parser {
             }
         }
 
-        return None;
+        None
     }
 
     fn match_keywords(&mut self, operators: &[Keyword]) -> Option<Keyword> {
@@ -115,22 +164,22 @@ impl Parser {
             }
         }
 
-        return None;
+        None
     }
 
-    fn expect(&mut self, symbols: &[Symbol]) -> Result<Token, String> {
+    fn expect(&mut self, symbols: &[Symbol]) -> Result<Token, Diagnostic> {
         if let Some(symbol) = self.match_symbols(symbols) {
             return Ok(symbol);
         }
 
-        return Err(format!(
-            "Expected {:#?} but got ${:#?}",
-            symbols,
-            self.peek()
-        ));
+        let token = self.peek();
+        Err(syntax_error(
+            format!("Expected {:?} but got {:?}", symbols, token.symbol),
+            token.span,
+        ))
     }
 
-    fn type_base(&mut self) -> Result<Type, String> {
+    fn type_base(&mut self) -> Result<Type, Diagnostic> {
         if let Some(Symbol::Literal(tokeniser::Literal::Null)) = self.safe_peek_symbol() {
             self.advance();
             return Ok(Type::BaseType(BaseType::Null));
@@ -139,104 +188,137 @@ impl Parser {
         if self.check(Symbol::LeftParen) {
             self.advance();
 
-            if self.check(Symbol::RightParen) {
-                self.advance();
-
-                if self.check(Symbol::Arrow) {
-                    self.advance();
+            // types nested inside the parentheses are never the return type itself
+            let in_return_type = std::mem::replace(&mut self.in_return_type, false);
+            let mut element_types = Vec::new();
+            let mut trailing_comma = false;
+            while !self.check(Symbol::RightParen) {
+                element_types.push(self.type_annotation()?);
 
-                    let return_type = self.type_annotation()?;
-                    return Ok(Type::Function(Box::from(FunctionType::Literal(
-                        Vec::new(),
-                        return_type,
-                    ))));
-                } else {
-                    return Err(String::from("Expected => after ()"));
+                trailing_comma = self.check_advance(Symbol::Comma);
+                if !trailing_comma {
+                    break;
                 }
             }
 
-            let first = &self.type_annotation()?;
-
-            let mut argument_types = Vec::new();
-            argument_types.push(first.clone());
-
-            if self.check(Symbol::RightParen) {
-                self.advance();
-
-                if !self.check(Symbol::Arrow) {
-                    self.advance();
-                    return Ok(first.clone());
-                }
+            self.expect(&[Symbol::RightParen])?;
+            self.in_return_type = in_return_type;
 
+            if !in_return_type && self.check_advance(Symbol::Arrow) {
                 let return_type = self.type_annotation()?;
 
                 return Ok(Type::Function(Box::from(FunctionType::Literal(
-                    argument_types,
+                    element_types,
                     return_type,
                 ))));
             }
 
-            while self.check(Symbol::Comma) {
-                self.advance();
-                let type_expr = self.type_annotation()?;
-                argument_types.push(type_expr);
-            }
+            // `(T)` on its own only groups a type, `(T,)` is a single element tuple
+            return match element_types.len() {
+                0 => Err(syntax_error(
+                    String::from("Expected => after ()"),
+                    self.peek().span,
+                )),
+                1 if !trailing_comma => Ok(element_types.remove(0)),
+                _ => Ok(Type::Tuple(element_types)),
+            };
+        }
 
-            self.expect(&[Symbol::RightParen])?;
-            self.expect(&[Symbol::Arrow])?;
+        let token = self.advance();
+        if let Symbol::Identifier(name) = token.symbol {
+            let mut arguments = Vec::new();
+            if self.match_operators(&[Operator::LesserThan]).is_some() {
+                loop {
+                    arguments.push(self.type_annotation()?);
+
+                    if !self.check_advance(Symbol::Comma) {
+                        break;
+                    }
+                }
+
+                self.expect(&[Symbol::Operator(Operator::GreaterThan)])?;
+            }
 
-            let return_type = self.type_annotation()?;
+            if arguments.is_empty() && self.type_parameters.contains(&name) {
+                return Ok(Type::Variable(name));
+            }
 
-            return Ok(Type::Function(Box::from(FunctionType::Literal(
-                argument_types,
-                return_type,
-            ))));
+            return Ok(Type::Named(name, arguments));
         }
 
-        let Symbol::TypeLiteral(type_literal) = self.advance_symbol() else {
-            return Err("Expected type annotation".to_owned());
+        let Symbol::TypeLiteral(type_literal) = token.symbol else {
+            return Err(syntax_error(
+                format!("Expected type annotation but got {:?}", token.symbol),
+                token.span,
+            ));
         };
 
         Ok(match type_literal {
             TypeLiteral::Any => Type::BaseType(BaseType::Any),
+            TypeLiteral::Integer => Type::BaseType(BaseType::Integer),
             TypeLiteral::Number => Type::BaseType(BaseType::Number),
             TypeLiteral::String => Type::BaseType(BaseType::String),
             TypeLiteral::Boolean => Type::BaseType(BaseType::Boolean),
         })
     }
 
-    fn type_or(&mut self) -> Result<Type, String> {
+    /// `T[]`, a list of `T`, can be repeated for nested lists
+    fn type_list(&mut self) -> Result<Type, Diagnostic> {
         let mut type_expr = self.type_base()?;
 
-        while let Some(_) = self.match_operators(&[Operator::Or]) {
-            let right = self.type_base()?;
+        while self.check_advance(Symbol::LeftBracket) {
+            self.expect(&[Symbol::RightBracket])?;
+            type_expr = Type::List(Box::from(type_expr));
+        }
+
+        Ok(type_expr)
+    }
+
+    fn type_or(&mut self) -> Result<Type, Diagnostic> {
+        let mut type_expr = self.type_list()?;
+
+        while self.match_operators(&[Operator::Or]).is_some() {
+            let right = self.type_list()?;
             type_expr = Type::Or(Box::from(type_expr), Box::from(right));
         }
 
-        return Ok(type_expr);
+        Ok(type_expr)
     }
 
-    fn type_annotation(&mut self) -> Result<Type, String> {
+    fn type_annotation(&mut self) -> Result<Type, Diagnostic> {
         self.type_or()
     }
 
-    fn function_arguments(&mut self) -> Result<Vec<FunctionArgument>, String> {
+    fn function_arguments(&mut self) -> Result<Vec<FunctionArgument>, Diagnostic> {
         let mut arguments: Vec<FunctionArgument> = Vec::new();
 
         if !self.check(Symbol::RightParen) {
             loop {
-                let Symbol::Identifier(name) = self.advance_symbol() else {
-                    return Err("Expected identifier in arg list".to_owned());
+                let start = self.peek().span;
+                let pattern = self.binding_pattern("in arg list")?;
+                let span = self.span_from(start);
+
+                // without an annotation the body decides the type
+                let type_annotation = match (self.check_advance(Symbol::Colon), &pattern) {
+                    (true, _) => match self.type_annotation() {
+                        Err(err) => {
+                            Err(err.with_note(String::from("in function argument definition")))
+                        }
+                        t => t,
+                    }?,
+                    (false, Pattern::Binding(name)) => Type::inferred(name),
+                    (false, Pattern::Wildcard) => Type::inferred("_"),
+                    (false, _) => {
+                        return Err(syntax_error(
+                            String::from("Destructured parameters need a type annotation"),
+                            span,
+                        ))
+                    }
                 };
-                self.expect(&[Symbol::Colon])?;
-
-                let type_annotation = match self.type_annotation() {
-                    Err(err) => Err(format!("In function argument definition | {}", err)),
-                    t => t,
-                }?;
                 arguments.push(FunctionArgument {
-                    name,
+                    pattern,
                     type_annotation,
+                    span,
                 });
 
                 if let Some(Symbol::RightParen) = self.safe_peek_symbol() {
@@ -249,19 +331,67 @@ impl Parser {
 
         self.expect(&[Symbol::RightParen])?;
 
-        return Ok(arguments);
+        Ok(arguments)
+    }
+
+    /// `T, U>`, the names of type parameters after the opening `<`
+    fn type_parameter_list(&mut self) -> Result<Vec<String>, Diagnostic> {
+        let mut parameters: Vec<String> = Vec::new();
+        loop {
+            let token = self.advance();
+            let Symbol::Identifier(parameter) = token.symbol else {
+                return Err(syntax_error(
+                    format!("Expected type parameter name but got {:?}", token.symbol),
+                    token.span,
+                ));
+            };
+
+            if parameters.contains(&parameter) {
+                return Err(syntax_error(
+                    format!("Type parameter `{}` is declared more than once", parameter),
+                    token.span,
+                ));
+            }
+            parameters.push(parameter);
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::Operator(Operator::GreaterThan)])?;
+
+        Ok(parameters)
+    }
+
+    /// Parses `parse` with `parameters` in scope as type variables
+    fn with_type_parameters<T>(
+        &mut self,
+        parameters: &[String],
+        parse: impl FnOnce(&mut Parser) -> Result<T, Diagnostic>,
+    ) -> Result<T, Diagnostic> {
+        let outer = self.type_parameters.len();
+        self.type_parameters.extend_from_slice(parameters);
+        let result = parse(self);
+        self.type_parameters.truncate(outer);
+        result
     }
 
-    fn function_definition(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn function_definition(&mut self, start: Span) -> Result<Box<dyn Expression>, Diagnostic> {
         let arguments = self.function_arguments()?;
 
         let mut return_type = Type::BaseType(BaseType::Infer);
-        if let Some(_) = self.match_symbols(&[Symbol::Colon]) {
-            return_type = match self.type_annotation() {
-                Err(err) => Err(format!(
-                    "After function argument definition, expected return type | {}",
-                    err
-                )),
+        if self.match_symbols(&[Symbol::Colon]).is_some() {
+            // `(): (integer, string) => ...` returns a tuple, returning a
+            // function type needs another set of parentheses
+            self.in_return_type = true;
+            let annotation = self.type_annotation();
+            self.in_return_type = false;
+
+            return_type = match annotation {
+                Err(err) => Err(err.with_note(String::from(
+                    "after function argument definition, expected return type",
+                ))),
                 t => t,
             }?;
         }
@@ -273,15 +403,228 @@ impl Parser {
         Ok(Box::new(Function {
             arguments,
             return_type,
+            span: start.to(body.span()),
             body: Rc::new(body),
         }))
     }
 
-    fn bottom(&mut self) -> Result<Box<dyn Expression>, String> {
-        self.advance();
-        match self.previous().symbol {
-            Symbol::Identifier(identifier) => Ok(Box::new(Variable { name: identifier })),
-            Symbol::Literal(value) => Ok(Box::new(Literal { value })),
+    /// Whether the next tokens start a function, `(...) =>` or `<T>(...) =>`
+    fn starts_function_definition(&mut self) -> bool {
+        match self.safe_peek_symbol() {
+            Some(Symbol::Operator(Operator::LesserThan)) => true,
+            Some(Symbol::LeftParen) => {
+                self.index += 1;
+                let is_function_definition = self.is_function_definition();
+                self.index -= 1;
+
+                is_function_definition
+            }
+            _ => false,
+        }
+    }
+
+    /// Looks ahead from just after a `(` to decide whether it opens the
+    /// arguments of a function, i.e. `()`, a pattern followed by a `:` or
+    /// `(name, ...)` followed by `=>` or a return type
+    fn is_function_definition(&self) -> bool {
+        let symbol = |index: usize| self.tokens.get(index).map(|token| &token.symbol);
+
+        let mut index = self.index;
+        match symbol(index) {
+            Some(Symbol::RightParen) => return true,
+            // `name:` or a struct pattern `Name { ... }:`
+            Some(Symbol::Identifier(_))
+                if !matches!(symbol(index + 1), Some(Symbol::LeftBrace)) =>
+            {
+                index += 1
+            }
+            Some(Symbol::Identifier(_) | Symbol::LeftBracket | Symbol::LeftParen) => {
+                if let Some(Symbol::Identifier(_)) = symbol(index) {
+                    index += 1;
+                }
+
+                let mut depth = 0;
+                loop {
+                    match symbol(index) {
+                        Some(Symbol::LeftBracket | Symbol::LeftParen | Symbol::LeftBrace) => {
+                            depth += 1
+                        }
+                        Some(Symbol::RightBracket | Symbol::RightParen | Symbol::RightBrace) => {
+                            depth -= 1
+                        }
+                        Some(Symbol::Fin) | None => return false,
+                        _ => (),
+                    }
+
+                    index += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            _ => return false,
+        }
+
+        match symbol(index) {
+            Some(Symbol::Colon) => true,
+            // `(name, ...) =>`, the first parameter has no annotation
+            Some(Symbol::Comma | Symbol::RightParen) if !self.in_match_guard => {
+                let mut depth = 1;
+                loop {
+                    match symbol(index) {
+                        Some(Symbol::LeftBracket | Symbol::LeftParen | Symbol::LeftBrace) => {
+                            depth += 1
+                        }
+                        Some(Symbol::RightBracket | Symbol::RightParen | Symbol::RightBrace) => {
+                            depth -= 1
+                        }
+                        Some(Symbol::Fin) | None => return false,
+                        _ => (),
+                    }
+
+                    index += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+
+                matches!(symbol(index), Some(Symbol::Arrow | Symbol::Colon))
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses with struct literals allowed again, used inside of brackets
+    /// which end the head of an `if`, `while`, `for` or `match`
+    fn unrestricted<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<T, Diagnostic>,
+    ) -> Result<T, Diagnostic> {
+        let no_struct_literals = std::mem::replace(&mut self.no_struct_literals, false);
+        let result = parse(self);
+        self.no_struct_literals = no_struct_literals;
+
+        result
+    }
+
+    /// The head of an `if`, `while`, `for` or `match`, which is followed by
+    /// a body so `name {` can't start a struct literal
+    fn head(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        let no_struct_literals = std::mem::replace(&mut self.no_struct_literals, true);
+        let result = self.expression();
+        self.no_struct_literals = no_struct_literals;
+
+        result
+    }
+
+    /// Looks ahead from just after a name to decide whether it starts a
+    /// struct literal, i.e. `Name {}` or `Name { field` followed by `:`, `,` or `}`
+    fn is_struct_literal(&self) -> bool {
+        let symbol = |index: usize| self.tokens.get(index).map(|token| &token.symbol);
+
+        if self.no_struct_literals || !matches!(symbol(self.index), Some(Symbol::LeftBrace)) {
+            return false;
+        }
+
+        match symbol(self.index + 1) {
+            Some(Symbol::RightBrace) => true,
+            Some(Symbol::Identifier(_)) => matches!(
+                symbol(self.index + 2),
+                Some(Symbol::Colon | Symbol::Comma | Symbol::RightBrace)
+            ),
+            _ => false,
+        }
+    }
+
+    /// `Name { x: 1, y }`, a field without a value takes the variable of the same name
+    fn struct_literal(
+        &mut self,
+        name: String,
+        start: Span,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
+        self.expect(&[Symbol::LeftBrace])?;
+
+        let mut fields: Vec<(String, Box<dyn Expression>)> = Vec::new();
+        while !self.check(Symbol::RightBrace) {
+            let token = self.advance();
+            let Symbol::Identifier(field) = token.symbol else {
+                return Err(syntax_error(
+                    format!(
+                        "Expected field name in struct literal but got {:?}",
+                        token.symbol
+                    ),
+                    token.span,
+                ));
+            };
+
+            if fields.iter().any(|(name, _)| *name == field) {
+                return Err(syntax_error(
+                    format!("Field `{}` is given more than once", field),
+                    token.span,
+                ));
+            }
+
+            let value: Box<dyn Expression> = match self.check_advance(Symbol::Colon) {
+                true => self.expression()?,
+                false => Box::new(Variable {
+                    name: field.clone(),
+                    span: token.span,
+                }),
+            };
+            fields.push((field, value));
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::RightBrace])?;
+
+        Ok(Box::from(StructLiteral {
+            name,
+            fields,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn bottom(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        if self.no_struct_literals
+            && matches!(
+                self.safe_peek_symbol(),
+                Some(Symbol::LeftBrace | Symbol::LeftParen | Symbol::LeftBracket)
+            )
+        {
+            return self.unrestricted(Parser::bottom);
+        }
+
+        let token = self.advance();
+        match token.symbol {
+            Symbol::Identifier(identifier) if self.is_struct_literal() => {
+                self.struct_literal(identifier, token.span)
+            }
+            Symbol::Identifier(enum_name) if self.check(Symbol::DoubleColon) => {
+                let variant = self.enum_variant_name()?;
+                let arguments = match self.check_advance(Symbol::LeftParen) {
+                    true => self.unrestricted(Parser::call_arguments)?,
+                    false => Vec::new(),
+                };
+
+                Ok(Box::from(EnumConstructor {
+                    enum_name,
+                    variant,
+                    arguments,
+                    span: self.span_from(token.span),
+                }))
+            }
+            Symbol::Identifier(identifier) => Ok(Box::new(Variable {
+                name: identifier,
+                span: token.span,
+            })),
+            Symbol::Literal(value) => Ok(Box::new(Literal {
+                value,
+                span: token.span,
+            })),
+            Symbol::InterpolatedString(parts) => self.interpolated_string(parts, token.span),
             Symbol::LeftBrace => {
                 let mut body = Vec::new();
 
@@ -292,113 +635,360 @@ impl Parser {
 
                 self.expect(&[Symbol::RightBrace])?;
 
-                Ok(Box::new(Body { body }))
+                Ok(Box::new(Body {
+                    body,
+                    span: self.span_from(token.span),
+                }))
+            }
+            // `<T, U>(xs: T[], f: (T) => U): U[] => ...`, a generic function
+            Symbol::Operator(Operator::LesserThan) => {
+                let parameters = self.type_parameter_list()?;
+                self.expect(&[Symbol::LeftParen])?;
+                self.with_type_parameters(&parameters, |parser| {
+                    parser.function_definition(token.span)
+                })
             }
             Symbol::LeftParen => {
-                match self.safe_peek_symbol() {
-                    Some(Symbol::Identifier(_)) => return self.function_definition(),
-                    Some(Symbol::RightParen) => return self.function_definition(),
-                    _ => (),
+                if self.is_function_definition() {
+                    return self.function_definition(token.span);
                 }
 
                 let expr = self.expression()?;
+                if !self.check_advance(Symbol::Comma) {
+                    self.expect(&[Symbol::RightParen])?;
+                    return Ok(expr);
+                }
+
+                let mut elements = vec![expr];
+                while !self.check(Symbol::RightParen) {
+                    elements.push(self.expression()?);
+
+                    if !self.check_advance(Symbol::Comma) {
+                        break;
+                    }
+                }
+
                 self.expect(&[Symbol::RightParen])?;
 
-                Ok(expr)
+                Ok(Box::from(TupleLiteral {
+                    elements,
+                    span: self.span_from(token.span),
+                }))
             }
             Symbol::LeftBracket => {
                 let mut elements = Vec::new();
 
-                elements.push(self.expression()?);
-
-                while self.check_advance(Symbol::Comma) {
+                if !self.check(Symbol::RightBracket) {
                     elements.push(self.expression()?);
+
+                    while self.check_advance(Symbol::Comma) {
+                        elements.push(self.expression()?);
+                    }
                 }
 
                 self.expect(&[Symbol::RightBracket])?;
 
-                Ok(Box::from(ListLiteral { elements }))
+                Ok(Box::from(ListLiteral {
+                    elements,
+                    span: self.span_from(token.span),
+                }))
+            }
+            symbol => Err(syntax_error(
+                format!("Cannot match token: {:?}", symbol),
+                token.span,
+            )),
+        }
+    }
+
+    /// Desugars `"a${b}c"` into `"a" + b + "c"`, the chain always starts with
+    /// a string so `+` concatenates even when the interpolated values are numbers
+    fn interpolated_string(
+        &mut self,
+        parts: Vec<StringPart>,
+        span: Span,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
+        let mut expr: Option<Box<dyn Expression>> = None;
+
+        for part in parts {
+            let right: Box<dyn Expression> = match part {
+                StringPart::Literal(string) if string.is_empty() && expr.is_some() => continue,
+                StringPart::Literal(string) => Box::new(Literal {
+                    value: tokeniser::Literal::String(string),
+                    span,
+                }),
+                StringPart::Expression(tokens) => {
+                    let mut parser = Parser::new(tokens);
+                    if parser.is_at_end() {
+                        return Err(syntax_error(
+                            String::from("Expected an expression inside of ${}"),
+                            span,
+                        ));
+                    }
+
+                    let inner = parser.expression()?;
+                    if !parser.is_at_end() {
+                        return Err(syntax_error(
+                            String::from("Expected } after expression in string"),
+                            parser.peek().span,
+                        ));
+                    }
+
+                    inner
+                }
+            };
+
+            expr = Some(match expr {
+                None => right,
+                Some(left) => Box::from(Binary {
+                    span,
+                    left,
+                    operator: Operator::Plus,
+                    right,
+                }),
+            });
+        }
+
+        Ok(expr.unwrap_or(Box::new(Literal {
+            value: tokeniser::Literal::String(String::new()),
+            span,
+        })))
+    }
+
+    fn call_arguments(&mut self) -> Result<Vec<Box<dyn Expression>>, Diagnostic> {
+        // functions passed to a call in a match guard can't be the guard itself
+        let in_match_guard = std::mem::replace(&mut self.in_match_guard, false);
+        let args = self.call_argument_list();
+        self.in_match_guard = in_match_guard;
+
+        args
+    }
+
+    fn call_argument_list(&mut self) -> Result<Vec<Box<dyn Expression>>, Diagnostic> {
+        let mut args = Vec::new();
+        if !self.check(Symbol::RightParen) {
+            loop {
+                let arg = self.expression()?;
+                args.push(arg);
+
+                if !self.check(Symbol::Comma) {
+                    break;
+                }
+
+                self.advance();
             }
-            _ => Err(format!("Cannot match token: {:#?}", self.previous())),
         }
+
+        self.expect(&[Symbol::RightParen])?;
+
+        Ok(args)
+    }
+
+    fn call(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        self.postfix(None)
+    }
+
+    /// Whether the next token carries on a chain of calls, indexes and members
+    fn continues_postfix(&mut self) -> bool {
+        matches!(
+            self.safe_peek_symbol(),
+            Some(Symbol::LeftParen | Symbol::LeftBracket | Symbol::Dot)
+        )
     }
 
-    fn call(&mut self) -> Result<Box<dyn Expression>, String> {
+    /// Calls, indexes and members following a primary expression. `piped` is
+    /// the left side of a `|>`, which becomes the first argument of the last
+    /// call in the chain, or is called with on its own if there isn't one
+    fn postfix(
+        &mut self,
+        mut piped: Option<Box<dyn Expression>>,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
         let mut expr = self.bottom()?;
 
-        if let Some(_) = self.match_symbols(&[Symbol::LeftParen]) {
-            let mut args = Vec::new();
-            if !self.check(Symbol::RightParen) {
-                loop {
-                    let arg = self.expression()?;
-                    args.push(arg);
+        loop {
+            if self.match_symbols(&[Symbol::LeftParen]).is_some() {
+                let mut arguments = self.unrestricted(Parser::call_arguments)?;
 
-                    if !self.check(Symbol::Comma) {
-                        break;
+                let mut span = self.span_from(expr.span());
+                if !self.continues_postfix() {
+                    if let Some(piped) = piped.take() {
+                        span = self.span_from(piped.span());
+                        arguments.insert(0, piped);
                     }
+                }
 
-                    self.advance();
+                expr = Box::from(Call {
+                    target: expr,
+                    arguments,
+                    span,
+                });
+                continue;
+            }
+
+            if self.match_symbols(&[Symbol::LeftBracket]).is_some() {
+                // slice bounds are parsed below ranges so `..` is left for the slice
+                let start = match self.check(Symbol::DotDot) {
+                    true => None,
+                    false => Some(self.logic_or()?),
+                };
+
+                if self.check_advance(Symbol::DotDot) {
+                    let end = match self.check(Symbol::RightBracket) {
+                        true => None,
+                        false => Some(self.logic_or()?),
+                    };
+                    self.expect(&[Symbol::RightBracket])?;
+
+                    let span = self.span_from(expr.span());
+                    expr = Box::from(Slice {
+                        target: expr,
+                        start,
+                        end,
+                        span,
+                    });
+                    continue;
                 }
+
+                self.expect(&[Symbol::RightBracket])?;
+                let span = self.span_from(expr.span());
+                expr = Box::from(Index {
+                    target: expr,
+                    index: start.unwrap(),
+                    span,
+                });
+                continue;
             }
 
-            self.expect(&[Symbol::RightParen])?;
+            if self.match_symbols(&[Symbol::Dot]).is_some() {
+                let token = self.advance();
+                let name = match token.symbol {
+                    Symbol::Identifier(name) => name,
+                    // `tuple.0`, positions are members named by their index
+                    Symbol::Literal(tokeniser::Literal::Integer(position)) => position.to_string(),
+                    symbol => {
+                        return Err(syntax_error(
+                            format!("Expected member name after . but got {:?}", symbol),
+                            token.span,
+                        ))
+                    }
+                };
 
+                let span = self.span_from(expr.span());
+                let member = Member {
+                    target: expr,
+                    name,
+                    span,
+                };
+
+                if self.match_symbols(&[Symbol::LeftParen]).is_some() {
+                    let mut arguments = self.unrestricted(Parser::call_arguments)?;
+
+                    let mut span = self.span_from(member.span);
+                    if !self.continues_postfix() {
+                        if let Some(piped) = piped.take() {
+                            span = self.span_from(piped.span());
+                            arguments.insert(0, piped);
+                        }
+                    }
+
+                    expr = Box::from(MethodCall {
+                        member,
+                        arguments,
+                        span,
+                    });
+                    continue;
+                }
+
+                expr = Box::from(member);
+                continue;
+            }
+
+            break;
+        }
+
+        if let Some(piped) = piped {
+            let span = self.span_from(piped.span());
             expr = Box::from(Call {
                 target: expr,
-                arguments: args,
-            })
+                arguments: vec![piped],
+                span,
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// `value |> f(x)`, the same as `f(value, x)`
+    fn pipe(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        let mut expr = self.range()?;
+
+        while self.check_advance(Symbol::Pipe) {
+            expr = self.postfix(Some(expr))?;
         }
 
-        return Ok(expr);
+        Ok(expr)
     }
 
-    fn unary(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn unary(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        let start = self.peek().span;
         if let Some(operator) = self.match_operators(&[Operator::Minus, Operator::Not]) {
             let expression = self.unary()?;
             return Ok(Box::new(Unary {
                 operator,
+                span: start.to(expression.span()),
                 value: expression,
             }));
         }
 
-        return self.call();
+        self.call()
     }
 
-    fn factor(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn factor(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         let mut expr = self.unary()?;
 
         while let Some(operator) = self.match_operators(&[Operator::Star, Operator::Slash]) {
             let right = self.unary()?;
 
             expr = Box::from(Binary {
+                span: expr.span().to(right.span()),
                 left: expr,
-                operator: operator,
+                operator,
                 right,
             });
         }
 
-        return Ok(expr);
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn term(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         let mut expr = self.factor()?;
 
         while let Some(operator) = self.match_operators(&[Operator::Plus, Operator::Minus]) {
             let right = self.factor()?;
 
             expr = Box::from(Binary {
+                span: expr.span().to(right.span()),
                 left: expr,
-                operator: operator,
+                operator,
                 right,
             });
         }
 
-        return Ok(expr);
+        Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn comparison(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         let mut expr = self.term()?;
 
+        // `x is number`, unions are tested with `|` so only list suffixes are part of the type
+        if self.match_keywords(&[Keyword::Is]).is_some() {
+            let type_ = self.type_list()?;
+            expr = Box::from(TypeTest {
+                span: self.span_from(expr.span()),
+                value: expr,
+                type_,
+            });
+        }
+
         while let Some(operator) = self.match_operators(&[
             Operator::GreaterThan,
             Operator::GreaterThanOrEqual,
@@ -408,80 +998,144 @@ impl Parser {
             let right = self.term()?;
 
             expr = Box::from(Binary {
+                span: expr.span().to(right.span()),
                 left: expr,
-                operator: operator,
+                operator,
                 right,
             });
         }
 
-        return Ok(expr);
+        Ok(expr)
     }
 
-    fn equality(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn equality(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         let mut expr = self.comparison()?;
 
         while let Some(operator) = self.match_operators(&[Operator::Equal, Operator::NotEqual]) {
             let right = self.comparison()?;
 
             expr = Box::from(Binary {
+                span: expr.span().to(right.span()),
                 left: expr,
-                operator: operator,
+                operator,
                 right,
             });
         }
 
-        return Ok(expr);
+        Ok(expr)
     }
 
-    fn logic_and(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn logic_and(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         let mut expr = self.equality()?;
 
         while let Some(operator) = self.match_operators(&[Operator::And]) {
             let right = self.equality()?;
 
             expr = Box::from(Binary {
+                span: expr.span().to(right.span()),
                 left: expr,
-                operator: operator,
+                operator,
                 right,
             });
         }
 
-        return Ok(expr);
+        Ok(expr)
     }
 
-    fn logic_or(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn logic_or(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         let mut expr = self.logic_and()?;
 
         while let Some(operator) = self.match_operators(&[Operator::Or]) {
             let right = self.logic_and()?;
 
             expr = Box::from(Binary {
+                span: expr.span().to(right.span()),
                 left: expr,
-                operator: operator,
+                operator,
                 right,
             });
         }
 
-        return Ok(expr);
+        Ok(expr)
+    }
+
+    /// `start..end`, `start..=end`, both optionally followed by `by step`
+    fn range(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        let start = self.logic_or()?;
+
+        let Some(operator) = self.match_symbols(&[Symbol::DotDot, Symbol::DotDotEqual]) else {
+            return Ok(start);
+        };
+
+        let end = self.logic_or()?;
+        let step = match self.match_keywords(&[Keyword::By]) {
+            Some(_) => Some(self.logic_or()?),
+            None => None,
+        };
+
+        let span = self.span_from(start.span());
+        Ok(Box::from(RangeLiteral {
+            start,
+            end,
+            step,
+            inclusive: operator.symbol == Symbol::DotDotEqual,
+            span,
+        }))
+    }
+
+    /// The `[i]` parts on the left hand side of an assignment like `xs[i][j] = v`
+    fn assign_accessors(&mut self) -> Result<Vec<Accessor>, Diagnostic> {
+        let mut accessors = Vec::new();
+
+        loop {
+            if self.check_advance(Symbol::LeftBracket) {
+                let index = self.expression()?;
+                self.expect(&[Symbol::RightBracket])?;
+                accessors.push(Accessor::Index(index));
+                continue;
+            }
+
+            if self.check_advance(Symbol::Dot) {
+                let token = self.advance();
+                let Symbol::Identifier(name) = token.symbol else {
+                    return Err(syntax_error(
+                        format!("Expected field name after . but got {:?}", token.symbol),
+                        token.span,
+                    ));
+                };
+                accessors.push(Accessor::Field(name));
+                continue;
+            }
+
+            break;
+        }
+
+        Ok(accessors)
     }
 
-    fn assign(&mut self) -> Result<Box<dyn Expression>, String> {
+    fn assign(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         if let Some(Symbol::Identifier(identifier)) = self.safe_peek_symbol() {
-            self.advance();
-            if self.check(Symbol::Assign) {
-                self.advance();
-                let value = self.expression()?;
+            let index_before = self.index;
+            let start = self.advance().span;
 
-                return Ok(Box::from(Assign {
-                    key: identifier,
-                    value,
-                }));
-            } else {
-                self.index -= 1;
+            if let Ok(accessors) = self.assign_accessors() {
+                if self.check(Symbol::Assign) {
+                    self.advance();
+                    let value = self.expression()?;
+
+                    return Ok(Box::from(Assign {
+                        key: identifier,
+                        accessors,
+                        span: start.to(value.span()),
+                        value,
+                    }));
+                }
             }
+
+            self.index = index_before;
         }
 
-        self.logic_or()
+        self.pipe()
     }
 
     fn optional_type_annotation(&mut self) -> Type {
@@ -496,48 +1150,699 @@ impl Parser {
         }
     }
 
-    fn declare(&mut self) -> Result<Box<dyn Expression>, String> {
-        if let Some(_) = self.match_keywords(&[Keyword::Let]) {
-            return match self.peek().symbol {
-                Symbol::Identifier(key) => {
-                    self.advance();
-
-                    let mut assigned_type: Option<environment::Variable<Type>> = None;
-                    if self.check(Symbol::Colon) {
-                        self.advance();
+    /// Collects consecutive `///` lines into a single doc string
+    fn doc_comment(&mut self) -> Option<String> {
+        let mut lines = Vec::new();
 
-                        let mut mutable = false;
-                        if self.check(Symbol::Keyword(Keyword::Mutable)) {
-                            self.advance();
-                            mutable = true;
-                        }
+        while let Some(Symbol::DocComment(line)) = self.safe_peek_symbol() {
+            self.advance();
+            lines.push(line);
+        }
 
-                        let value = match mutable {
-                            true => self.optional_type_annotation(),
-                            false => self.type_annotation()?,
+        if lines.is_empty() {
+            return None;
+        }
+
+        Some(lines.join("\n"))
+    }
+
+    /// A `match` arm pattern: `_`, `name`, a literal, or a type optionally
+    /// followed by a name like `string s`
+    fn pattern(&mut self) -> Result<Pattern, Diagnostic> {
+        let token = self.peek();
+
+        match token.symbol {
+            Symbol::Identifier(name) => {
+                self.advance();
+                match self.safe_peek_symbol() {
+                    Some(Symbol::LeftBrace) => return self.struct_pattern(name, token.span),
+                    Some(Symbol::DoubleColon) => return self.enum_pattern(name, token.span),
+                    // `Point p` or `Point[] ps`, matches values of a named type
+                    Some(Symbol::Identifier(_) | Symbol::LeftBracket) => {
+                        let mut type_ = Type::Named(name, Vec::new());
+                        while self.check_advance(Symbol::LeftBracket) {
+                            self.expect(&[Symbol::RightBracket])?;
+                            type_ = Type::List(Box::from(type_));
+                        }
+
+                        let inner = match self.safe_peek_symbol() {
+                            Some(Symbol::Identifier(_)) => self.pattern()?,
+                            _ => Pattern::Wildcard,
                         };
+                        return Ok(Pattern::Typed(type_, Box::from(inner)));
+                    }
+                    _ => (),
+                }
 
-                        assigned_type = Some(environment::Variable { mutable, value })
+                Ok(match name.as_str() {
+                    "_" => Pattern::Wildcard,
+                    _ => Pattern::Binding(name),
+                })
+            }
+            Symbol::Literal(value) => {
+                self.advance();
+                Ok(Pattern::Literal(Literal {
+                    value,
+                    span: token.span,
+                }))
+            }
+            Symbol::Operator(Operator::Minus) => {
+                self.advance();
+                let literal = self.advance();
+                let value = match literal.symbol {
+                    Symbol::Literal(tokeniser::Literal::Integer(integer)) => {
+                        tokeniser::Literal::Integer(-integer)
+                    }
+                    Symbol::Literal(tokeniser::Literal::Number(number)) => {
+                        tokeniser::Literal::Number(-number)
+                    }
+                    symbol => {
+                        return Err(syntax_error(
+                            format!("Expected a number after - in pattern, got: {:?}", symbol),
+                            literal.span,
+                        ))
                     }
+                };
 
-                    self.expect(&[Symbol::Assign])?;
-                    let value = self.expression()?;
-                    Ok(Box::from(Declare {
-                        key,
-                        assigned_type,
-                        value,
-                    }))
+                Ok(Pattern::Literal(Literal {
+                    value,
+                    span: token.span.to(literal.span),
+                }))
+            }
+            Symbol::LeftBracket => {
+                self.advance();
+
+                let mut before = Vec::new();
+                let mut rest = None;
+                let mut after = Vec::new();
+
+                while !self.check(Symbol::RightBracket) {
+                    let spread = self.peek();
+                    if self.check_advance(Symbol::Ellipsis) {
+                        if rest.is_some() {
+                            return Err(syntax_error(
+                                String::from("Only one `...` is allowed in a list pattern"),
+                                spread.span,
+                            ));
+                        }
+
+                        rest = Some(Box::from(match self.safe_peek_symbol() {
+                            Some(Symbol::Identifier(_)) => self.pattern()?,
+                            _ => Pattern::Wildcard,
+                        }));
+                    } else {
+                        let element = self.pattern()?;
+                        match rest {
+                            None => before.push(element),
+                            Some(_) => after.push(element),
+                        }
+                    }
+
+                    if !self.check_advance(Symbol::Comma) {
+                        break;
+                    }
                 }
-                symbol => Err(format!(
-                    "Expected Identifier after `let` keyword, got: {:#?}",
-                    symbol
-                )),
+
+                self.expect(&[Symbol::RightBracket])?;
+                self.unique_bindings(
+                    Pattern::List {
+                        before,
+                        rest,
+                        after,
+                    },
+                    token.span,
+                )
+            }
+            Symbol::LeftParen => {
+                self.advance();
+
+                let mut elements = Vec::new();
+                let mut trailing_comma = false;
+                while !self.check(Symbol::RightParen) {
+                    elements.push(self.pattern()?);
+
+                    trailing_comma = self.check_advance(Symbol::Comma);
+                    if !trailing_comma {
+                        break;
+                    }
+                }
+
+                self.expect(&[Symbol::RightParen])?;
+
+                // like tuple literals, `(a)` only groups and `(a,)` is a tuple
+                match (elements.len(), trailing_comma) {
+                    (0, _) => Err(syntax_error(
+                        String::from("Expected a pattern inside of ()"),
+                        self.span_from(token.span),
+                    )),
+                    (1, false) => Ok(elements.remove(0)),
+                    _ => self.unique_bindings(Pattern::Tuple(elements), token.span),
+                }
+            }
+            Symbol::TypeLiteral(_) => {
+                let type_ = self.type_list()?;
+                let inner = match self.safe_peek_symbol() {
+                    Some(Symbol::Identifier(_)) => self.pattern()?,
+                    _ => Pattern::Wildcard,
+                };
+
+                Ok(Pattern::Typed(type_, Box::from(inner)))
+            }
+            symbol => Err(syntax_error(
+                format!("Expected a pattern, got: {:?}", symbol),
+                token.span,
+            )),
+        }
+    }
+
+    /// `Point { x, y: [first, ...] }`, fields that are left out are not matched
+    fn struct_pattern(&mut self, name: String, start: Span) -> Result<Pattern, Diagnostic> {
+        self.expect(&[Symbol::LeftBrace])?;
+
+        let mut fields: Vec<(String, Pattern)> = Vec::new();
+        while !self.check(Symbol::RightBrace) {
+            let token = self.advance();
+            let Symbol::Identifier(field) = token.symbol else {
+                return Err(syntax_error(
+                    format!(
+                        "Expected field name in struct pattern but got {:?}",
+                        token.symbol
+                    ),
+                    token.span,
+                ));
+            };
+
+            if fields.iter().any(|(name, _)| *name == field) {
+                return Err(syntax_error(
+                    format!("Field `{}` is matched more than once", field),
+                    token.span,
+                ));
+            }
+
+            let pattern = match self.check_advance(Symbol::Colon) {
+                true => self.pattern()?,
+                false => Pattern::Binding(field.clone()),
+            };
+            fields.push((field, pattern));
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::RightBrace])?;
+        self.unique_bindings(Pattern::Struct { name, fields }, start)
+    }
+
+    /// `Shape::Rect(width, _)` or `Optional::None`
+    fn enum_pattern(&mut self, enum_name: String, start: Span) -> Result<Pattern, Diagnostic> {
+        let variant = self.enum_variant_name()?;
+
+        let mut fields = Vec::new();
+        if self.check_advance(Symbol::LeftParen) {
+            while !self.check(Symbol::RightParen) {
+                fields.push(self.pattern()?);
+
+                if !self.check_advance(Symbol::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(&[Symbol::RightParen])?;
+        }
+
+        self.unique_bindings(
+            Pattern::Enum {
+                enum_name,
+                variant,
+                fields,
+            },
+            start,
+        )
+    }
+
+    /// Rejects patterns that bind the same name twice, e.g. `[a, a]`
+    fn unique_bindings(&mut self, pattern: Pattern, start: Span) -> Result<Pattern, Diagnostic> {
+        let mut names = pattern.names();
+        names.sort();
+        if let Some(name) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(syntax_error(
+                format!("`{}` is bound more than once in the same pattern", name[0]),
+                self.span_from(start),
+            ));
+        }
+
+        Ok(pattern)
+    }
+
+    /// A pattern that always binds something when its type matches: a name,
+    /// `_`, or a list or tuple pattern. Used by `let`, `for` and function arguments
+    fn binding_pattern(&mut self, context: &str) -> Result<Pattern, Diagnostic> {
+        match self.safe_peek_symbol() {
+            Some(Symbol::Identifier(_) | Symbol::LeftBracket | Symbol::LeftParen) => self.pattern(),
+            symbol => Err(syntax_error(
+                format!(
+                    "Expected Identifier or pattern {}, got: {:?}",
+                    context, symbol
+                ),
+                self.peek().span,
+            )),
+        }
+    }
+
+    fn match_arms(&mut self) -> Result<Vec<MatchArm>, Diagnostic> {
+        self.expect(&[Symbol::LeftBrace])?;
+
+        let mut arms = Vec::new();
+        while !self.check(Symbol::RightBrace) {
+            let start = self.peek().span;
+            let pattern = self.pattern()?;
+            let guard = match self.match_keywords(&[Keyword::If]) {
+                Some(_) => {
+                    let in_match_guard = std::mem::replace(&mut self.in_match_guard, true);
+                    let guard = self.expression();
+                    self.in_match_guard = in_match_guard;
+                    Some(guard?)
+                }
+                None => None,
             };
+
+            self.expect(&[Symbol::Arrow])?;
+            let body = self.expression()?;
+
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                span: self.span_from(start),
+                body,
+            });
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::RightBrace])?;
+        Ok(arms)
+    }
+
+    /// `'label:` in front of a loop, lets `break 'label` target a loop other
+    /// than the innermost one
+    fn loop_label(&mut self) -> Result<Option<String>, Diagnostic> {
+        let Some(Symbol::Label(label)) = self.safe_peek_symbol() else {
+            return Ok(None);
+        };
+
+        self.advance();
+        self.expect(&[Symbol::Colon])?;
+
+        if !matches!(
+            self.safe_peek_symbol(),
+            Some(Symbol::Keyword(
+                Keyword::Loop | Keyword::While | Keyword::For
+            ))
+        ) {
+            return Err(syntax_error(
+                format!("Expected a loop after the label '{}", label),
+                self.peek().span,
+            ));
+        }
+
+        Ok(Some(label))
+    }
+
+    /// `struct Name { field: type, mutable other: type }`, the `struct` keyword
+    /// has already been consumed
+    fn struct_declaration(
+        &mut self,
+        start: Span,
+        doc: Option<String>,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
+        let token = self.advance();
+        let Symbol::Identifier(name) = token.symbol else {
+            return Err(syntax_error(
+                format!(
+                    "Expected struct name after `struct` but got {:?}",
+                    token.symbol
+                ),
+                token.span,
+            ));
+        };
+
+        self.expect(&[Symbol::LeftBrace])?;
+
+        let mut fields: Vec<StructField> = Vec::new();
+        while !self.check(Symbol::RightBrace) {
+            let mutable = self.check_advance(Symbol::Keyword(Keyword::Mutable));
+
+            let token = self.advance();
+            let Symbol::Identifier(field) = token.symbol else {
+                return Err(syntax_error(
+                    format!(
+                        "Expected field name in struct {} but got {:?}",
+                        name, token.symbol
+                    ),
+                    token.span,
+                ));
+            };
+
+            if fields.iter().any(|existing| existing.name == field) {
+                return Err(syntax_error(
+                    format!(
+                        "Field `{}` is declared more than once in struct {}",
+                        field, name
+                    ),
+                    token.span,
+                ));
+            }
+
+            self.expect(&[Symbol::Colon])?;
+            let type_ = self.type_annotation()?;
+            fields.push(StructField {
+                name: field,
+                mutable,
+                type_,
+            });
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::RightBrace])?;
+
+        Ok(Box::from(StructDeclaration {
+            struct_type: StructType { name, fields },
+            doc,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// `enum Name<T> { Variant(T, number), Other }`, the `enum` keyword has
+    /// already been consumed
+    fn enum_declaration(
+        &mut self,
+        start: Span,
+        doc: Option<String>,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
+        let token = self.advance();
+        let Symbol::Identifier(name) = token.symbol else {
+            return Err(syntax_error(
+                format!("Expected enum name after `enum` but got {:?}", token.symbol),
+                token.span,
+            ));
+        };
+
+        let mut parameters = Vec::new();
+        if self.match_operators(&[Operator::LesserThan]).is_some() {
+            parameters = self.type_parameter_list()?;
+        }
+
+        self.expect(&[Symbol::LeftBrace])?;
+
+        let mut variants: Vec<EnumVariant> = Vec::new();
+        while !self.check(Symbol::RightBrace) {
+            let token = self.advance();
+            let Symbol::Identifier(variant) = token.symbol else {
+                return Err(syntax_error(
+                    format!(
+                        "Expected variant name in enum {} but got {:?}",
+                        name, token.symbol
+                    ),
+                    token.span,
+                ));
+            };
+
+            if variants.iter().any(|existing| existing.name == variant) {
+                return Err(syntax_error(
+                    format!(
+                        "Variant `{}` is declared more than once in enum {}",
+                        variant, name
+                    ),
+                    token.span,
+                ));
+            }
+
+            let mut fields = Vec::new();
+            if self.check_advance(Symbol::LeftParen) {
+                while !self.check(Symbol::RightParen) {
+                    fields.push(self.with_type_parameters(&parameters, Parser::type_annotation)?);
+
+                    if !self.check_advance(Symbol::Comma) {
+                        break;
+                    }
+                }
+
+                self.expect(&[Symbol::RightParen])?;
+            }
+
+            variants.push(EnumVariant {
+                name: variant,
+                fields,
+            });
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::RightBrace])?;
+
+        Ok(Box::from(EnumDeclaration {
+            doc,
+            enum_type: EnumType {
+                name,
+                parameters,
+                variants,
+            },
+            span: self.span_from(start),
+        }))
+    }
+
+    /// `type Id = number | string`, the `type` keyword has already been
+    /// consumed. The alias's own name can be used inside its definition
+    fn alias_declaration(
+        &mut self,
+        start: Span,
+        doc: Option<String>,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
+        let token = self.advance();
+        let Symbol::Identifier(name) = token.symbol else {
+            return Err(syntax_error(
+                format!(
+                    "Expected alias name after `type` but got {:?}",
+                    token.symbol
+                ),
+                token.span,
+            ));
+        };
+
+        let mut parameters = Vec::new();
+        if self.match_operators(&[Operator::LesserThan]).is_some() {
+            parameters = self.type_parameter_list()?;
+        }
+
+        self.expect(&[Symbol::Assign])?;
+        let type_ = self.with_type_parameters(&parameters, Parser::type_annotation)?;
+
+        Ok(Box::from(TypeAlias {
+            name,
+            doc,
+            parameters,
+            type_,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// The `::Variant` of `Enum::Variant`
+    fn enum_variant_name(&mut self) -> Result<String, Diagnostic> {
+        self.expect(&[Symbol::DoubleColon])?;
+
+        let token = self.advance();
+        match token.symbol {
+            Symbol::Identifier(variant) => Ok(variant),
+            symbol => Err(syntax_error(
+                format!("Expected variant name after :: but got {:?}", symbol),
+                token.span,
+            )),
+        }
+    }
+
+    /// The method name at the start of an interface or impl entry, which
+    /// can't repeat an earlier one
+    fn method_name(&mut self, seen: &[String], context: &str) -> Result<String, Diagnostic> {
+        let token = self.advance();
+        let Symbol::Identifier(name) = token.symbol else {
+            return Err(syntax_error(
+                format!(
+                    "Expected method name in {} but got {:?}",
+                    context, token.symbol
+                ),
+                token.span,
+            ));
+        };
+
+        if seen.contains(&name) {
+            return Err(syntax_error(
+                format!("Method `{}` is given more than once in {}", name, context),
+                token.span,
+            ));
+        }
+
+        self.expect(&[Symbol::Colon])?;
+        Ok(name)
+    }
+
+    /// `interface Name { method: (Self, number) => Self }`, the `interface`
+    /// keyword has already been consumed
+    fn interface_declaration(
+        &mut self,
+        start: Span,
+        doc: Option<String>,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
+        let token = self.advance();
+        let Symbol::Identifier(name) = token.symbol else {
+            return Err(syntax_error(
+                format!(
+                    "Expected interface name after `interface` but got {:?}",
+                    token.symbol
+                ),
+                token.span,
+            ));
+        };
+
+        self.expect(&[Symbol::LeftBrace])?;
+
+        let mut methods: Vec<InterfaceMethod> = Vec::new();
+        while !self.check(Symbol::RightBrace) {
+            let seen: Vec<String> = methods.iter().map(|method| method.name.clone()).collect();
+            let method = self.method_name(&seen, &format!("interface {}", name))?;
+            let self_type = [String::from("Self")];
+            methods.push(InterfaceMethod {
+                name: method,
+                type_: self.with_type_parameters(&self_type, Parser::type_annotation)?,
+            });
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::RightBrace])?;
+
+        Ok(Box::from(InterfaceDeclaration {
+            name,
+            doc,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// `impl Interface for Type { method: (self: Type) => ... }`, the `impl`
+    /// keyword has already been consumed
+    fn implementation(
+        &mut self,
+        start: Span,
+        doc: Option<String>,
+    ) -> Result<Box<dyn Expression>, Diagnostic> {
+        let token = self.advance();
+        let Symbol::Identifier(interface) = token.symbol else {
+            return Err(syntax_error(
+                format!(
+                    "Expected interface name after `impl` but got {:?}",
+                    token.symbol
+                ),
+                token.span,
+            ));
+        };
+
+        self.expect(&[Symbol::Keyword(Keyword::For)])?;
+        let for_type = self.type_annotation()?;
+        self.expect(&[Symbol::LeftBrace])?;
+
+        let mut methods: Vec<(String, Box<dyn Expression>)> = Vec::new();
+        while !self.check(Symbol::RightBrace) {
+            let seen: Vec<String> = methods.iter().map(|(name, _)| name.clone()).collect();
+            let method = self.method_name(&seen, &format!("impl {}", interface))?;
+            methods.push((method, self.expression()?));
+
+            if !self.check_advance(Symbol::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&[Symbol::RightBrace])?;
+
+        Ok(Box::from(Implement {
+            interface,
+            doc,
+            for_type,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn declare(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        let doc_start = self.peek().span;
+        let doc = self.doc_comment();
+        let declares = matches!(
+            self.safe_peek_symbol(),
+            Some(Symbol::Keyword(
+                Keyword::Let
+                    | Keyword::Struct
+                    | Keyword::Enum
+                    | Keyword::Interface
+                    | Keyword::Type
+                    | Keyword::Impl
+            ))
+        );
+        if doc.is_some() && !declares {
+            return Err(syntax_error(
+                String::from("Doc comments must be followed by a declaration"),
+                self.span_from(doc_start),
+            ));
+        }
+
+        let start = self.peek().span;
+        if self.match_keywords(&[Keyword::Let]).is_some() {
+            let pattern = self.binding_pattern("after `let` keyword")?;
+
+            let mut assigned_type: Option<environment::Variable<Type>> = None;
+            if self.check(Symbol::Colon) {
+                self.advance();
+
+                let mut mutable = false;
+                if self.check(Symbol::Keyword(Keyword::Mutable)) {
+                    self.advance();
+                    mutable = true;
+                }
+
+                let value = match mutable {
+                    true => self.optional_type_annotation(),
+                    false => self.type_annotation()?,
+                };
+
+                assigned_type = Some(environment::Variable { mutable, value })
+            }
+
+            self.expect(&[Symbol::Assign])?;
+            let hoisted = assigned_type.is_none()
+                && matches!(pattern, Pattern::Binding(_))
+                && self.starts_function_definition();
+            let value = self.expression()?;
+            return Ok(Box::from(Declare {
+                pattern,
+                doc,
+                assigned_type,
+                hoisted,
+                span: start.to(value.span()),
+                value,
+            }));
         }
 
         if let Some(Symbol::Keyword(Keyword::If)) = self.safe_peek_symbol() {
             self.advance();
-            let condition = self.expression()?;
+            let condition = self.head()?;
             let body = self.expression()?;
             let mut else_body: Option<Box<dyn Expression>> = None;
 
@@ -547,46 +1852,491 @@ impl Parser {
             }
 
             return Ok(Box::from(If {
+                span: self.span_from(start),
                 condition,
                 body,
                 else_body,
             }));
         }
 
+        if self.match_keywords(&[Keyword::Match]).is_some() {
+            let subject = self.head()?;
+            let arms = self.match_arms()?;
+
+            return Ok(Box::from(Match {
+                span: self.span_from(start),
+                subject,
+                arms,
+            }));
+        }
+
+        if self.match_keywords(&[Keyword::Struct]).is_some() {
+            return self.struct_declaration(start, doc);
+        }
+
+        if self.match_keywords(&[Keyword::Enum]).is_some() {
+            return self.enum_declaration(start, doc);
+        }
+
+        if self.match_keywords(&[Keyword::Interface]).is_some() {
+            return self.interface_declaration(start, doc);
+        }
+
+        if self.match_keywords(&[Keyword::Type]).is_some() {
+            return self.alias_declaration(start, doc);
+        }
+
+        if self.match_keywords(&[Keyword::Impl]).is_some() {
+            return self.implementation(start, doc);
+        }
+
+        let label = self.loop_label()?;
+
+        if self.match_keywords(&[Keyword::Loop]).is_some() {
+            let body = self.expression()?;
+
+            return Ok(Box::from(Loop {
+                span: self.span_from(start),
+                label,
+                body,
+            }));
+        }
+
+        if self.match_keywords(&[Keyword::While]).is_some() {
+            let condition = self.head()?;
+            let body = self.expression()?;
+
+            return Ok(Box::from(While {
+                span: self.span_from(start),
+                label,
+                condition,
+                body,
+            }));
+        }
+
+        if self.match_keywords(&[Keyword::For]).is_some() {
+            let pattern = self.binding_pattern("after `for` keyword")?;
+            self.expect(&[Symbol::Keyword(Keyword::In)])?;
+            let iterable = self.head()?;
+            let body = self.expression()?;
+
+            return Ok(Box::from(For {
+                span: self.span_from(start),
+                label,
+                pattern,
+                iterable,
+                body,
+            }));
+        }
+
+        if let Some(keyword) = self.match_keywords(&[Keyword::Break, Keyword::Continue]) {
+            let label = match self.safe_peek_symbol() {
+                Some(Symbol::Label(label)) => {
+                    self.advance();
+                    Some(label)
+                }
+                _ => None,
+            };
+
+            let kind = match keyword {
+                Keyword::Break => JumpKind::Break,
+                _ => JumpKind::Continue,
+            };
+
+            let ends_jump = matches!(
+                self.safe_peek_symbol(),
+                Some(Symbol::Semi | Symbol::RightBrace | Symbol::Keyword(Keyword::Else))
+            );
+            let value = match kind == JumpKind::Break && !ends_jump {
+                true => Some(self.expression()?),
+                false => None,
+            };
+
+            return Ok(Box::from(Jump {
+                kind,
+                label,
+                value,
+                span: self.span_from(start),
+            }));
+        }
+
         if let Some(Symbol::Keyword(Keyword::Return)) = self.safe_peek_symbol() {
             self.advance();
 
             if let Some(Symbol::Semi) = self.safe_peek_symbol() {
-                return Ok(Box::from(Return { expression: None }));
+                return Ok(Box::from(Return {
+                    expression: None,
+                    span: start,
+                }));
             }
 
-            let expression = Some(self.expression()?);
+            let expression = self.expression()?;
 
-            return Ok(Box::from(Return { expression }));
+            return Ok(Box::from(Return {
+                span: start.to(expression.span()),
+                expression: Some(expression),
+            }));
         }
 
         self.assign()
     }
 
-    fn expression(&mut self) -> Result<Box<dyn Expression>, String> {
-        return self.declare();
+    fn expression(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
+        self.declare()
     }
 
-    pub fn next(&mut self) -> Result<Box<dyn Expression>, String> {
+    pub fn next(&mut self) -> Result<Box<dyn Expression>, Diagnostic> {
         let expr = self.expression()?;
         self.expect(&[Symbol::Semi])?;
-        return Ok(expr);
+        Ok(expr)
     }
 }
 
-pub fn parse(tokens: Vec<Token>) -> Result<Vec<Box<dyn Expression>>, String> {
+pub fn parse(tokens: Vec<Token>) -> Result<Vec<Box<dyn Expression>>, Diagnostic> {
     let mut expressions: Vec<Box<dyn Expression>> = Vec::new();
-    let mut parser = Parser { index: 0, tokens };
+    let mut parser = Parser::new(tokens);
 
     while !parser.is_at_end() {
         let expr = parser.next()?;
         expressions.push(expr);
     }
 
-    return Ok(expressions);
+    Ok(expressions)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{diagnostic::Diagnostic, expression::Expression, tokeniser::scan};
+
+    use super::parse;
+
+    /// Scans and parses `source`, scan errors fail the same way parse errors do
+    fn parse_source(source: &str) -> Result<Vec<Box<dyn Expression>>, Diagnostic> {
+        parse(scan(source)?)
+    }
+
+    /// The tree of the first expression in `source`
+    fn tree(source: &str) -> String {
+        parse_source(source).unwrap()[0].to_string()
+    }
+
+    #[test]
+    fn attaches_doc_comments_to_declarations() {
+        let expressions = parse_source("/// first\n/// second\nlet x = 1;").unwrap();
+        assert!(expressions[0]
+            .to_string()
+            .contains("\"doc\": \"first\\nsecond\""));
+
+        assert!(parse_source("/// dangling\nprint(1);").is_err());
+    }
+
+    #[test]
+    fn attaches_doc_comments_to_type_declarations() {
+        let declarations = [
+            "/// documented\nstruct Point { x: number };",
+            "/// documented\nenum Shape { Circle(number) };",
+            "/// documented\ninterface Show { show: (Self) => string };",
+            "/// documented\nimpl Show for Point { show: (p: Point) => \"point\" };",
+            "/// documented\ntype Id = integer | string;",
+        ];
+
+        for source in declarations {
+            assert!(
+                tree(source).contains("\"doc\": \"documented\""),
+                "{}",
+                source
+            );
+        }
+
+        assert!(tree("struct Point { x: number };").contains("\"doc\": null"));
+        assert!(parse_source("/// dangling\n'outer: loop { break; };").is_err());
+    }
+
+    #[test]
+    fn parses_postfix_chains() {
+        let expressions = parse_source("f(x)(y)[0].length;").unwrap();
+        let tree = expressions[0].to_string();
+
+        let member = tree.find("Member").unwrap();
+        let index = tree.find("Index").unwrap();
+        let outer_call = tree.find("Call").unwrap();
+        assert!(member < index && index < outer_call);
+        assert_eq!(tree.matches("\"Call\"").count(), 2);
+    }
+
+    #[test]
+    fn parses_slices_and_index_assignment() {
+        let expressions = parse_source("xs[..2]; xs[1..]; xs[0][1] = 2;").unwrap();
+
+        assert!(expressions[0].to_string().contains("\"Slice\""));
+        assert!(expressions[1].to_string().contains("\"Slice\""));
+
+        let assign = expressions[2].to_string();
+        assert!(assign.starts_with("{ \"type\": \"Assign\""));
+        assert_eq!(assign.matches("\"Index\"").count(), 2);
+    }
+
+    #[test]
+    fn parses_ranges_below_logic_operators() {
+        let expressions = parse_source("1 + 1..2 * 3 by 2; xs[0..2];").unwrap();
+
+        let range = expressions[0].to_string();
+        assert!(range.starts_with("{ \"type\": \"Range\""));
+        assert!(range.contains("\"inclusive\": false"));
+        assert_eq!(range.matches("\"Binary\"").count(), 2);
+
+        assert!(expressions[1].to_string().contains("\"Slice\""));
+    }
+
+    #[test]
+    fn parses_empty_lists_and_list_types() {
+        let expressions = parse_source("let xs: mutable (number | string)[][] = [];").unwrap();
+        let tree = expressions[0].to_string();
+
+        assert!(tree.contains("\"elements\": []"));
+        assert_eq!(tree.matches("List(").count(), 2);
+        assert!(tree.contains("Or("));
+
+        assert!(parse_source("let x: number[ = [];").is_err());
+    }
+
+    #[test]
+    fn parses_tuples_without_confusing_functions() {
+        assert!(tree("(1, \"a\",);").starts_with("{ \"type\": \"TupleLiteral\""));
+        assert!(tree("(1);").starts_with("{ \"type\": \"Literal\""));
+        assert!(tree("((a, b): (integer, string)) => a;").starts_with("{ \"type\": \"Function\""));
+        assert!(tree("pair.0;").contains("\"name\": \"0\""));
+
+        let typed =
+            tree("let f: (integer, (string,)) => (integer) = (): (integer, string) => (1, \"a\");");
+        // only `(string,)` is a tuple type, the return type is followed by the body
+        assert_eq!(typed.matches("Tuple(").count(), 1);
+        assert!(typed.contains("TupleLiteral"));
+
+        assert!(parse_source("let () = x;").is_err());
+    }
+
+    #[test]
+    fn parses_structs_outside_of_loop_and_if_heads() {
+        let declaration = tree("struct Point { x: number, mutable y: Point | null };");
+        assert!(declaration.contains("{ \"name\": \"y\", \"mutable\": true"));
+        assert!(declaration.contains("Named("));
+
+        assert!(tree("Point { x, y: 1 };").starts_with("{ \"type\": \"StructLiteral\""));
+        assert!(tree("if p { x; };").contains("\"condition\": { \"type\": \"Variable\""));
+        assert!(tree("if (p == Point {}) {};").contains("StructLiteral"));
+        assert!(tree("p.to.y = 1;").contains("{ \"type\": \"Field\", \"name\": \"to\" }"));
+        assert!(tree("let Point { x, y: [a] } = p;").contains("\"pattern\": \"Struct\""));
+
+        assert!(parse_source("struct P { x: number, x: number };").is_err());
+        assert!(parse_source("P { x: 1, x: 2 };").is_err());
+    }
+
+    #[test]
+    fn parses_enums_and_variant_patterns() {
+        let declaration = tree("enum Result<T, E> { Ok(T), Err(E), };");
+        assert!(declaration.contains("\"parameters\": [\"T\", \"E\"]"));
+        assert!(declaration.contains("{ \"name\": \"Err\""));
+
+        assert!(tree("Shape::Rect(1, 2);").starts_with("{ \"type\": \"EnumConstructor\""));
+        assert!(tree("Optional::None;").contains("\"arguments\": []"));
+        let annotated = tree("let x: Result<integer, string[]> = r;");
+        assert!(annotated.contains("Named(") && annotated.contains("\"Result\""));
+        assert!(annotated.contains("List("));
+        assert!(
+            tree("match s { Shape::Rect(w, _) => w, _ => 0 };").contains("\"pattern\": \"Enum\"")
+        );
+
+        assert!(parse_source("enum E { A, A };").is_err());
+        assert!(parse_source("let Shape::Rect(w, w) = s;").is_err());
+    }
+
+    #[test]
+    fn parses_interfaces_impls_and_method_calls() {
+        let interface = tree("interface Add { add: (Self, Self) => Self, };");
+        assert!(interface.starts_with("{ \"type\": \"InterfaceDeclaration\""));
+        assert!(interface.contains("\"name\": \"add\""));
+
+        let implementation = tree("impl Add for Point { add: (a: Point, b: Point) => a };");
+        assert!(implementation.starts_with("{ \"type\": \"Implement\", \"interface\": \"Add\""));
+        assert!(implementation.contains("\"type\": \"Function\""));
+
+        let call = tree("p.add(q).x;");
+        assert!(call.starts_with("{ \"type\": \"Member\", \"target\": { \"type\": \"MethodCall\""));
+        assert!(call.contains("\"method\": \"add\""));
+        assert!(tree("t.0;").starts_with("{ \"type\": \"Member\""));
+
+        assert!(parse_source("interface A { f: () => null, f: () => null };").is_err());
+        assert!(parse_source("impl A Point { };").is_err());
+    }
+
+    #[test]
+    fn parses_pipes_into_the_last_call() {
+        let piped = tree("xs |> map(f) |> sum;");
+        assert!(piped.starts_with(
+            "{ \"type\": \"Call\", \"target\": { \"type\": \"Variable\", \"name\": \"sum\""
+        ));
+        assert!(piped.contains("map"));
+        assert_eq!(piped.matches("Call").count(), 2);
+
+        // only the last call in the chain gets the piped value
+        let chained = tree("x |> make(1)(2);");
+        assert!(chained.starts_with(
+            "{ \"type\": \"Call\", \"target\": { \"type\": \"Call\", \"target\": { \"type\": \"Variable\", \"name\": \"make\""
+        ));
+        assert!(chained.find("\\\"value\\\": 2").unwrap() > chained.find("\\\"x\\\"").unwrap());
+
+        assert!(tree("1..3 |> f;").contains("Range"));
+        assert!(parse_source("xs |>;").is_err());
+    }
+
+    #[test]
+    fn parses_generic_functions() {
+        let generic = tree("<T, U>(xs: T[], f: (T) => U): U[] => map(xs, f);");
+        assert!(generic.starts_with("{ \"type\": \"Function\""));
+
+        // type parameters are only in scope inside their function
+        assert!(tree("(x: T) => x;").contains("Named"));
+        assert!(tree("enum Box<T> { Full(T) };").contains("Variable(\"T\")"));
+        assert!(tree("interface Add { add: (Self, Self) => Self };").contains("Variable(\"Self\")"));
+
+        assert!(parse_source("<T, T>(x: T) => x;").is_err());
+        assert!(parse_source("<T> x;").is_err());
+    }
+
+    #[test]
+    fn parses_unannotated_parameters() {
+        let function = tree("(f, x: integer) => f(x);");
+        assert!(function.starts_with("{ \"type\": \"Function\""));
+        assert!(function.contains("\"'f\""));
+        assert!(tree("(x): number => x;").starts_with("{ \"type\": \"Function\""));
+
+        // without a `=>` after them they are still tuples and groups
+        assert!(tree("(a, b);").starts_with("{ \"type\": \"TupleLiteral\""));
+        assert!(tree("(a);").starts_with("{ \"type\": \"Variable\""));
+
+        // a parenthesised guard is followed by the arm, not a function body
+        let guarded = tree("match x { n if (ok) => n, _ => map(xs, (y) => y) };");
+        assert_eq!(guarded.matches("Function").count(), 1);
+
+        assert!(parse_source("([a, b]) => a;").is_err());
+    }
+
+    #[test]
+    fn hoists_function_declarations() {
+        assert!(tree("let f = (x) => x;").contains("\"hoisted\": true"));
+        assert!(tree("let f = <T>(x: T): T => x;").contains("\"hoisted\": true"));
+
+        // annotated or destructured declarations, and values that only start
+        // with a function, keep their place
+        assert!(tree("let f: (integer) => integer = (x) => x;").contains("\"hoisted\": false"));
+        assert!(tree("let (a, b) = (1, 2);").contains("\"hoisted\": false"));
+        assert!(tree("let y = ((x) => x)(1);").contains("\"hoisted\": false"));
+        assert!(tree("let y = (x);").contains("\"hoisted\": false"));
+    }
+
+    #[test]
+    fn parses_type_aliases() {
+        let alias = tree("type Id = number | string;");
+        assert!(alias.contains("\"name\": \"Id\""));
+        assert!(alias.contains("Or(BaseType(Number), BaseType(String))"));
+
+        // the alias's own name is a use of it, its parameters are variables
+        let alias = tree("type Tree<T> = T | Tree<T>[];");
+        assert!(alias.contains("\"parameters\": [\"T\"]"));
+        assert!(alias.contains("List(Named(\"Tree\", [Variable(\"T\")]))"));
+
+        assert!(parse_source("type = integer;").is_err());
+        assert!(parse_source("type Id integer;").is_err());
+    }
+
+    #[test]
+    fn parses_type_tests() {
+        let test = tree("x is integer[];");
+        assert!(test.starts_with("{ \"type\": \"TypeTest\""));
+        assert!(test.contains("List(BaseType(Integer))"));
+
+        // `is` binds tighter than `&` and `|`, which join tests
+        let test = tree("x is number | x is string;");
+        assert!(test.starts_with("{ \"type\": \"Binary\""));
+        assert_eq!(test.matches("TypeTest").count(), 2);
+
+        assert!(parse_source("x is;").is_err());
+    }
+
+    #[test]
+    fn parses_while_loops() {
+        let expressions = parse_source("while i < 10 { if i == 3 continue; break; };").unwrap();
+        let tree = expressions[0].to_string();
+
+        assert!(tree.starts_with("{ \"type\": \"While\""));
+        assert!(tree.contains("\"Continue\""));
+        assert!(tree.contains("\"Break\""));
+    }
+
+    #[test]
+    fn parses_for_loops() {
+        let expressions = parse_source("for x in 0..3 print(x);").unwrap();
+        let tree = expressions[0].to_string();
+
+        assert!(tree.starts_with(
+            "{ \"type\": \"For\", \"label\": null, \"pattern\": { \"pattern\": \"Binding\", \"name\": \"x\" }"
+        ));
+        assert!(tree.contains("\"Range\""));
+
+        assert!(parse_source("for 1 in xs print(x);").is_err());
+        assert!(parse_source("for x xs print(x);").is_err());
+    }
+
+    #[test]
+    fn parses_destructuring_patterns() {
+        let expressions = parse_source("let [head, ...tail] = xs;").unwrap();
+        assert!(expressions[0].to_string().contains(
+            "\"pattern\": { \"pattern\": \"List\", \"before\": [{ \"pattern\": \"Binding\", \"name\": \"head\" }], \"rest\": { \"pattern\": \"Binding\", \"name\": \"tail\" }, \"after\": [] }"
+        ));
+
+        let expressions = parse_source("let f = ([a, _]: integer[], b: string) => a;").unwrap();
+        assert!(expressions[0].to_string().contains("\"Wildcard\""));
+
+        assert!(parse_source("let [a, a] = xs;").is_err());
+        assert!(parse_source("let [...a, ...b] = xs;").is_err());
+        assert!(parse_source("let 1 = xs;").is_err());
+    }
+
+    #[test]
+    fn parses_labelled_loops_and_break_values() {
+        let expressions =
+            parse_source("'outer: loop { for x in xs { break 'outer x; }; };").unwrap();
+        let tree = expressions[0].to_string();
+
+        assert!(tree.starts_with("{ \"type\": \"Loop\", \"label\": \"outer\""));
+        assert!(tree.contains(
+            "{ \"type\": \"Break\", \"label\": \"outer\", \"value\": { \"type\": \"Variable\""
+        ));
+
+        let expressions = parse_source("loop { continue; break; };").unwrap();
+        assert!(expressions[0]
+            .to_string()
+            .contains("{ \"type\": \"Break\", \"label\": null, \"value\": null }"));
+
+        assert!(parse_source("'outer: x;").is_err());
+    }
+
+    #[test]
+    fn parses_match_arms() {
+        let expressions =
+            parse_source("match x { -1 => a, string s => b, n if n > 1 => c, _ => d, };").unwrap();
+        let tree = expressions[0].to_string();
+
+        assert!(tree.starts_with("{ \"type\": \"Match\""));
+        assert!(tree.contains("\"value\": -1"));
+        assert!(tree.contains("\"pattern\": \"Typed\""));
+        assert!(tree.contains(
+            "\"pattern\": \"Binding\", \"name\": \"n\" }, \"guard\": { \"type\": \"Binary\""
+        ));
+        assert!(tree.contains("\"pattern\": \"Wildcard\""));
+
+        assert!(parse_source("match x { 1 2 };").is_err());
+        assert!(parse_source("match x { + => 1 };").is_err());
+    }
 }
@@ -0,0 +1,586 @@
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    expression::{literal::Literal, Expression, Interpreter},
+    span::Span,
+    types::{BaseType, Type},
+    value::Value,
+};
+
+/// The left hand side of a `match` arm, `let` declaration or function
+/// argument, tests a value and binds parts of it
+#[derive(Clone)]
+pub enum Pattern {
+    /// `_`, matches anything without binding it
+    Wildcard,
+    /// `name`, matches anything and binds it to `name`
+    Binding(String),
+    /// `1`, `"a"`, `null`... matches values equal to the literal
+    Literal(Literal),
+    /// `string s`, matches values of the type and then the inner pattern
+    Typed(Type, Box<Pattern>),
+    /// `[first, ...rest, last]`, matches lists element by element, `rest`
+    /// binds whatever is left in the middle as a list
+    List {
+        before: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+        after: Vec<Pattern>,
+    },
+    /// `(a, b)`, matches tuples of the same length position by position
+    Tuple(Vec<Pattern>),
+    /// `Point { x, y: 0 }`, matches structs of the type field by field
+    Struct {
+        name: String,
+        fields: Vec<(String, Pattern)>,
+    },
+    /// `Shape::Rect(w, h)`, matches one variant of an enum and then its values
+    Enum {
+        enum_name: String,
+        variant: String,
+        fields: Vec<Pattern>,
+    },
+}
+
+/// The type of the elements of a list type, unions of lists give the union
+/// of their element types
+fn element_type(list_type: &Type) -> Option<Type> {
+    list_type
+        .members()
+        .into_iter()
+        .map(|member| match member {
+            Type::List(element_type) => Some(*element_type),
+            Type::BaseType(BaseType::Any) => Some(member),
+            _ => None,
+        })
+        .reduce(|left, right| Some(left?.union(right?)))
+        .flatten()
+}
+
+/// The type at each position of the tuples of `length` in `tuple_type`,
+/// unions of tuples give the union of the types at each position
+fn tuple_element_types(tuple_type: &Type, length: usize) -> Option<Vec<Type>> {
+    tuple_type
+        .members()
+        .into_iter()
+        .filter_map(|member| match member {
+            Type::Tuple(element_types) if element_types.len() == length => Some(element_types),
+            Type::BaseType(BaseType::Any) => Some(vec![member; length]),
+            _ => None,
+        })
+        .reduce(|left, right| {
+            left.into_iter()
+                .zip(right)
+                .map(|(left, right)| left.union(right))
+                .collect()
+        })
+}
+
+impl Pattern {
+    /// The type of the values this pattern always matches, literals only
+    /// match some values of their type so they cover nothing (except `null`).
+    /// Enum patterns only cover one variant, see `covers_variant`
+    pub fn covers(&self) -> Option<Type> {
+        match self {
+            Pattern::Wildcard | Pattern::Binding(_) => Some(Type::BaseType(BaseType::Any)),
+            Pattern::Literal(literal) => match literal.value {
+                crate::tokeniser::Literal::Null => Some(Type::BaseType(BaseType::Null)),
+                _ => None,
+            },
+            Pattern::Typed(type_, inner) => inner.covers().map(|_| type_.clone()),
+            Pattern::List {
+                before,
+                rest: Some(rest),
+                after,
+            } if before.is_empty() && after.is_empty() => rest
+                .covers()
+                .map(|_| Type::List(Box::from(Type::BaseType(BaseType::Any)))),
+            Pattern::List { .. } => None,
+            Pattern::Tuple(elements) => elements
+                .iter()
+                .map(Pattern::covers)
+                .collect::<Option<Vec<Type>>>()
+                .map(Type::Tuple),
+            // field patterns only cover the whole field when they match anything
+            Pattern::Struct { name, fields } => fields
+                .iter()
+                .all(|(_, pattern)| matches!(pattern.covers(), Some(Type::BaseType(BaseType::Any))))
+                .then(|| Type::Named(name.clone(), Vec::new())),
+            Pattern::Enum { .. } => None,
+        }
+    }
+
+    /// The enum and variant an enum pattern always matches, if its values
+    /// match anything
+    pub fn covers_variant(&self) -> Option<(&str, &str)> {
+        let Pattern::Enum {
+            enum_name,
+            variant,
+            fields,
+        } = self
+        else {
+            return None;
+        };
+
+        fields
+            .iter()
+            .all(|pattern| matches!(pattern.covers(), Some(Type::BaseType(BaseType::Any))))
+            .then_some((enum_name.as_str(), variant.as_str()))
+    }
+
+    /// For list patterns that match any elements, the number of elements they
+    /// need and whether they take more (`[a, ...rest]`) or exactly that many
+    pub fn list_length(&self) -> Option<(usize, bool)> {
+        let Pattern::List {
+            before,
+            rest,
+            after,
+        } = self
+        else {
+            return None;
+        };
+
+        let matches_anything =
+            |pattern: &Pattern| matches!(pattern.covers(), Some(Type::BaseType(BaseType::Any)));
+
+        match before
+            .iter()
+            .chain(rest.as_deref())
+            .chain(after.iter())
+            .all(matches_anything)
+        {
+            true => Some((before.len() + after.len(), rest.is_some())),
+            false => None,
+        }
+    }
+
+    /// Every name bound by the pattern, in order
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            Pattern::Wildcard | Pattern::Literal(_) => Vec::new(),
+            Pattern::Binding(name) => vec![name.clone()],
+            Pattern::Typed(_, inner) => inner.names(),
+            Pattern::List {
+                before,
+                rest,
+                after,
+            } => before
+                .iter()
+                .chain(rest.as_deref())
+                .chain(after.iter())
+                .flat_map(Pattern::names)
+                .collect(),
+            Pattern::Tuple(elements) => elements.iter().flat_map(Pattern::names).collect(),
+            Pattern::Struct { fields, .. } => fields
+                .iter()
+                .flat_map(|(_, pattern)| pattern.names())
+                .collect(),
+            Pattern::Enum { fields, .. } => fields.iter().flat_map(Pattern::names).collect(),
+        }
+    }
+
+    /// Checks the pattern can match a value of `matched` and returns the types
+    /// of the variables it binds
+    pub fn bind_types(
+        &self,
+        matched: &Type,
+        span: Span,
+        type_interpreter: &mut Interpreter<Type>,
+    ) -> Result<Vec<(String, Type)>, Diagnostic> {
+        let never_matches = |pattern_type: &Type| {
+            Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "Pattern of type {} can never match a value of type {}",
+                    pattern_type, matched
+                ),
+            )
+            .with_span(span)
+        };
+
+        // patterns naming a type decide the type of an unannotated parameter
+        let matched = &match self {
+            Pattern::Wildcard | Pattern::Binding(_) => matched.clone(),
+            Pattern::Literal(literal) => {
+                let literal_type = literal.check_type(type_interpreter)?;
+                type_interpreter.infer(matched, &literal_type)
+            }
+            Pattern::Struct { name, .. }
+            | Pattern::Enum {
+                enum_name: name, ..
+            } => type_interpreter.infer(matched, &Type::Named(name.clone(), Vec::new())),
+            Pattern::Typed(..) | Pattern::List { .. } | Pattern::Tuple(_) => {
+                type_interpreter.known(matched.clone(), span)?
+            }
+        };
+
+        match self {
+            Pattern::Wildcard => Ok(Vec::new()),
+            Pattern::Binding(name) => Ok(vec![(name.clone(), matched.clone())]),
+            Pattern::Literal(literal) => {
+                let literal_type = literal.check_type(type_interpreter)?;
+                match matched.narrow(&literal_type) {
+                    Some(_) => Ok(Vec::new()),
+                    None => Err(never_matches(&literal_type)),
+                }
+            }
+            Pattern::Typed(type_, inner) => {
+                let type_ = type_interpreter.resolve(type_);
+                match matched.narrow(&type_) {
+                    Some(narrowed) => inner.bind_types(&narrowed, span, type_interpreter),
+                    None => Err(never_matches(&type_)),
+                }
+            }
+            Pattern::List {
+                before,
+                rest,
+                after,
+            } => {
+                let any_list = Type::List(Box::from(Type::BaseType(BaseType::Any)));
+                let Some(element) = matched.narrow(&any_list).as_ref().and_then(element_type)
+                else {
+                    return Err(never_matches(&any_list));
+                };
+
+                let mut bindings = Vec::new();
+                for pattern in before.iter().chain(after.iter()) {
+                    bindings.extend(pattern.bind_types(&element, span, type_interpreter)?);
+                }
+
+                if let Some(rest) = rest {
+                    let rest_type = Type::List(Box::from(element));
+                    bindings.extend(rest.bind_types(&rest_type, span, type_interpreter)?);
+                }
+
+                Ok(bindings)
+            }
+            Pattern::Tuple(elements) => {
+                let Some(element_types) = tuple_element_types(matched, elements.len()) else {
+                    return Err(never_matches(&Type::Tuple(vec![
+                        Type::BaseType(
+                            BaseType::Any
+                        );
+                        elements.len()
+                    ])));
+                };
+
+                let mut bindings = Vec::new();
+                for (pattern, element_type) in elements.iter().zip(element_types.iter()) {
+                    bindings.extend(pattern.bind_types(element_type, span, type_interpreter)?);
+                }
+
+                Ok(bindings)
+            }
+            Pattern::Struct { name, fields } => {
+                let struct_type = Type::Named(name.clone(), Vec::new());
+                if matched.narrow(&struct_type).is_none() {
+                    return Err(never_matches(&struct_type));
+                }
+
+                let definition = type_interpreter
+                    .get_struct(name)
+                    .map_err(|err| err.or_span(span))?;
+
+                let mut bindings = Vec::new();
+                for (field, pattern) in fields.iter() {
+                    let Some(field_type) = definition.field(field) else {
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!("Struct {} has no field named `{}`", name, field),
+                        )
+                        .with_span(span));
+                    };
+
+                    bindings.extend(pattern.bind_types(
+                        &field_type.type_,
+                        span,
+                        type_interpreter,
+                    )?);
+                }
+
+                Ok(bindings)
+            }
+            Pattern::Enum {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                let definition = type_interpreter
+                    .get_enum(enum_name)
+                    .map_err(|err| err.or_span(span))?;
+                let Some(variant_type) = definition.variant(variant) else {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::TypeMismatch,
+                        format!("Enum {} has no variant named `{}`", enum_name, variant),
+                    )
+                    .with_span(span));
+                };
+
+                if variant_type.fields.len() != fields.len() {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::ArgumentCount,
+                        format!(
+                            "{}::{} holds {} values but the pattern has {}",
+                            enum_name,
+                            variant,
+                            variant_type.fields.len(),
+                            fields.len()
+                        ),
+                    )
+                    .with_span(span));
+                }
+
+                // every member of `matched` that is this enum adds its type arguments
+                let Some(field_types) = matched
+                    .members()
+                    .into_iter()
+                    .filter_map(|member| match member {
+                        Type::Named(name, arguments) if name == *enum_name => Some(arguments),
+                        Type::BaseType(BaseType::Any) => Some(Vec::new()),
+                        _ => None,
+                    })
+                    .map(|arguments| definition.field_types(variant_type, &arguments))
+                    .reduce(|left, right| {
+                        left.into_iter()
+                            .zip(right)
+                            .map(|(left, right)| left.union(right))
+                            .collect()
+                    })
+                else {
+                    return Err(never_matches(&Type::Named(enum_name.clone(), Vec::new())));
+                };
+
+                let mut bindings = Vec::new();
+                for (pattern, field_type) in fields.iter().zip(field_types.iter()) {
+                    bindings.extend(pattern.bind_types(field_type, span, type_interpreter)?);
+                }
+
+                Ok(bindings)
+            }
+        }
+    }
+
+    /// Tests `value` against the pattern, returning what it binds if it matches
+    pub fn matches(
+        &self,
+        value: &Value,
+        interpreter: &mut Interpreter<Value>,
+    ) -> Result<Option<Vec<(String, Value)>>, Diagnostic> {
+        match self {
+            Pattern::Wildcard => Ok(Some(Vec::new())),
+            Pattern::Binding(name) => Ok(Some(vec![(name.clone(), value.clone())])),
+            Pattern::Literal(literal) => {
+                let literal = literal.interpret(interpreter)?;
+                let equal = match (&literal, value) {
+                    (Value::Null, Value::Null) => true,
+                    _ => matches!(literal.equals(value.clone())?, Value::Boolean(true)),
+                };
+
+                Ok(equal.then(Vec::new))
+            }
+            Pattern::Typed(type_, inner) => match value.is_instance_of(&interpreter.resolve(type_))
+            {
+                true => inner.matches(value, interpreter),
+                false => Ok(None),
+            },
+            Pattern::List {
+                before,
+                rest,
+                after,
+            } => {
+                let length = match value {
+                    Value::List(list) => list.vector.len() as i128,
+                    Value::Range(range) => range.length(),
+                    _ => return Ok(None),
+                };
+
+                let fixed = (before.len() + after.len()) as i128;
+                if length < fixed || (rest.is_none() && length != fixed) {
+                    return Ok(None);
+                }
+
+                // ranges are read element by element so they are never materialized
+                let element = |index: i64| match value {
+                    Value::Range(range) => range.get(index),
+                    Value::List(list) => list.get(index),
+                    _ => unreachable!(),
+                };
+
+                let mut bindings = Vec::new();
+                // the elements after the rest are counted back from the end, as
+                // a range can be longer than an i64
+                let after_len = after.len() as i64;
+                let indices = (0..before.len() as i64).chain(-after_len..0);
+                for (pattern, index) in before.iter().chain(after.iter()).zip(indices) {
+                    match pattern.matches(&element(index)?, interpreter)? {
+                        Some(matched) => bindings.extend(matched),
+                        None => return Ok(None),
+                    }
+                }
+
+                if let Some(rest) = rest {
+                    let start = Some(before.len() as i64);
+                    let end = (after_len > 0).then_some(-after_len);
+                    let middle = match value {
+                        Value::Range(range) => Value::Range(range.slice(start, end)?),
+                        Value::List(list) => Value::List(list.slice(start, end)?),
+                        _ => unreachable!(),
+                    };
+
+                    match rest.matches(&middle, interpreter)? {
+                        Some(matched) => bindings.extend(matched),
+                        None => return Ok(None),
+                    }
+                }
+
+                Ok(Some(bindings))
+            }
+            Pattern::Tuple(elements) => {
+                let Value::Tuple(tuple) = value else {
+                    return Ok(None);
+                };
+
+                if tuple.elements.len() != elements.len() {
+                    return Ok(None);
+                }
+
+                let mut bindings = Vec::new();
+                for (pattern, element) in elements.iter().zip(tuple.elements.iter()) {
+                    match pattern.matches(element, interpreter)? {
+                        Some(matched) => bindings.extend(matched),
+                        None => return Ok(None),
+                    }
+                }
+
+                Ok(Some(bindings))
+            }
+            Pattern::Struct { name, fields } => {
+                let Value::Struct(structure) = value else {
+                    return Ok(None);
+                };
+
+                if structure.name != *name {
+                    return Ok(None);
+                }
+
+                let mut bindings = Vec::new();
+                for (field, pattern) in fields.iter() {
+                    let Some(field_value) = structure.get(field) else {
+                        return Ok(None);
+                    };
+
+                    match pattern.matches(&field_value, interpreter)? {
+                        Some(matched) => bindings.extend(matched),
+                        None => return Ok(None),
+                    }
+                }
+
+                Ok(Some(bindings))
+            }
+            Pattern::Enum {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                let Value::Enum(value) = value else {
+                    return Ok(None);
+                };
+
+                if value.enum_name != *enum_name
+                    || value.variant != *variant
+                    || value.values.len() != fields.len()
+                {
+                    return Ok(None);
+                }
+
+                let mut bindings = Vec::new();
+                for (pattern, field_value) in fields.iter().zip(value.values.iter()) {
+                    match pattern.matches(field_value, interpreter)? {
+                        Some(matched) => bindings.extend(matched),
+                        None => return Ok(None),
+                    }
+                }
+
+                Ok(Some(bindings))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            Pattern::Wildcard => String::from("{ \"pattern\": \"Wildcard\" }"),
+            Pattern::Binding(name) => {
+                format!("{{ \"pattern\": \"Binding\", \"name\": \"{}\" }}", name)
+            }
+            Pattern::Literal(literal) => format!(
+                "{{ \"pattern\": \"Literal\", \"value\": {} }}",
+                literal.to_string()
+            ),
+            Pattern::Typed(type_, inner) => format!(
+                "{{ \"pattern\": \"Typed\", \"type\": \"{:?}\", \"inner\": {} }}",
+                type_,
+                inner
+            ),
+            Pattern::List {
+                before,
+                rest,
+                after,
+            } => {
+                let patterns = |patterns: &Vec<Pattern>| {
+                    patterns
+                        .iter()
+                        .map(Pattern::to_string)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                };
+
+                format!(
+                    "{{ \"pattern\": \"List\", \"before\": [{}], \"rest\": {}, \"after\": [{}] }}",
+                    patterns(before),
+                    match rest {
+                        Some(rest) => rest.to_string(),
+                        None => "null".to_owned(),
+                    },
+                    patterns(after)
+                )
+            }
+            Pattern::Struct { name, fields } => format!(
+                "{{ \"pattern\": \"Struct\", \"name\": \"{}\", \"fields\": [{}] }}",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, pattern)| format!(
+                        "{{ \"name\": \"{}\", \"pattern\": {} }}",
+                        field,
+                        pattern
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Pattern::Enum {
+                enum_name,
+                variant,
+                fields,
+            } => format!(
+                "{{ \"pattern\": \"Enum\", \"enum\": \"{}\", \"variant\": \"{}\", \"fields\": [{}] }}",
+                enum_name,
+                variant,
+                fields
+                    .iter()
+                    .map(Pattern::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Pattern::Tuple(elements) => format!(
+                "{{ \"pattern\": \"Tuple\", \"elements\": [{}] }}",
+                elements
+                    .iter()
+                    .map(Pattern::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        };
+
+        f.write_str(&string)
+    }
+}
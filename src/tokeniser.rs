@@ -1,5 +1,10 @@
-use std::iter::{Enumerate, Peekable};
-use std::str::Chars;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind},
+    span::{Position, Span},
+};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Operator {
@@ -22,7 +27,8 @@ pub enum Operator {
 pub enum Literal {
     Null,
     String(String),
-    Number(f32),
+    Integer(i64),
+    Number(f64),
     Boolean(bool),
 }
 
@@ -30,25 +36,19 @@ pub enum Literal {
 pub enum TypeLiteral {
     Any,
     String,
+    Integer,
     Number,
     Boolean,
 }
 
-impl Literal {
-    pub fn to_string(self) -> String {
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Literal::Null => String::from("null"),
-            Literal::Number(number) => format!("{}", number),
-            Literal::String(string) => format!("\"{}\"", string),
-            Literal::Boolean(boolean) => {
-                format!(
-                    "{}",
-                    match boolean {
-                        true => "true",
-                        _ => "false",
-                    }
-                )
-            }
+            Literal::Null => f.write_str("null"),
+            Literal::Integer(integer) => write!(f, "{}", integer),
+            Literal::Number(number) => write!(f, "{:?}", number),
+            Literal::String(string) => write!(f, "\"{}\"", string),
+            Literal::Boolean(boolean) => write!(f, "{}", boolean),
         }
     }
 }
@@ -60,6 +60,28 @@ pub enum Keyword {
     Else,
     Return,
     Mutable,
+    By,
+    While,
+    Break,
+    Continue,
+    For,
+    In,
+    Loop,
+    Match,
+    Struct,
+    Enum,
+    Interface,
+    Impl,
+    Type,
+    Is,
+}
+
+/// A piece of a string containing `${...}`, expressions are kept as the
+/// tokens between the braces and are parsed by the parser
+#[derive(Debug, PartialEq, Clone)]
+pub enum StringPart {
+    Literal(String),
+    Expression(Vec<Token>),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -67,15 +89,25 @@ pub enum Symbol {
     TypeLiteral(TypeLiteral),
     Identifier(String),
     Literal(Literal),
+    InterpolatedString(Vec<StringPart>),
     Operator(Operator),
     Keyword(Keyword),
+    /// A loop label like `'outer`, stored without the quote
+    Label(String),
 
     Arrow,
+    /// `|>`, passes the left side as the first argument of the call on the right
+    Pipe,
 
     Comma,
+    Dot,
+    DotDot,
+    DotDotEqual,
+    Ellipsis,
     Semi,
     Assign,
     Colon,
+    DoubleColon,
 
     LeftParen,
     RightParen,
@@ -84,17 +116,74 @@ pub enum Symbol {
     LeftBracket,
     RightBracket,
 
+    DocComment(String),
     Newline,
     Fin,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
-    pub line: u16,
-    pub index: usize,
+    pub span: Span,
     pub symbol: Symbol,
 }
 
+/// Walks over the characters of a source file while keeping track of the
+/// line, column and byte offset of the next character.
+pub struct Cursor<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    position: Position,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(source: &'a str) -> Cursor<'a> {
+        Cursor {
+            source,
+            chars: source.char_indices().peekable(),
+            position: Position::start(),
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Whether a single `.` comes right before `position`, as in `tuple.0`
+    pub fn follows_member_dot(&self, position: Position) -> bool {
+        let before = &self.source[..position.offset];
+        before.ends_with('.') && !before.ends_with("..")
+    }
+
+    pub fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, char)| *char)
+    }
+
+    pub fn peek_second(&self) -> Option<char> {
+        self.chars.clone().nth(1).map(|(_, char)| char)
+    }
+
+    pub fn advance(&mut self) -> Option<char> {
+        let (offset, char) = self.chars.next()?;
+
+        self.position.offset = offset + char.len_utf8();
+        if char == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+
+        Some(char)
+    }
+
+    pub fn token(&self, start: Position, symbol: Symbol) -> Token {
+        Token {
+            span: Span::new(start, self.position),
+            symbol,
+        }
+    }
+}
+
 fn scan_symbol(char: char) -> Option<Symbol> {
     match char {
         '+' => Option::Some(Symbol::Operator(Operator::Plus)),
@@ -104,6 +193,7 @@ fn scan_symbol(char: char) -> Option<Symbol> {
         '(' => Option::Some(Symbol::LeftParen),
         ')' => Option::Some(Symbol::RightParen),
         ',' => Option::Some(Symbol::Comma),
+        '.' => Option::Some(Symbol::Dot),
         ';' => Option::Some(Symbol::Semi),
         '=' => Option::Some(Symbol::Assign),
         '{' => Option::Some(Symbol::LeftBrace),
@@ -121,60 +211,345 @@ fn scan_symbol(char: char) -> Option<Symbol> {
     }
 }
 
-fn scan_string(index: usize, chars: &mut Peekable<Enumerate<Chars>>) -> Result<Token, String> {
+fn scan_escape(chars: &mut Cursor) -> Result<char, Diagnostic> {
+    let start = chars.position();
+    let error = |message: String, chars: &Cursor| {
+        Diagnostic::error(DiagnosticKind::Scan, message)
+            .with_span(Span::new(start, chars.position()))
+    };
+
+    match chars.advance() {
+        Some('n') => Ok('\n'),
+        Some('t') => Ok('\t'),
+        Some('r') => Ok('\r'),
+        Some('0') => Ok('\0'),
+        Some('\\') => Ok('\\'),
+        Some('"') => Ok('"'),
+        Some('$') => Ok('$'),
+        Some('u') => {
+            if chars.advance() != Some('{') {
+                return Err(error(String::from("Expected { after \\u"), chars));
+            }
+
+            let mut digits = String::new();
+            loop {
+                match chars.advance() {
+                    Some('}') => break,
+                    Some(char) if char.is_ascii_hexdigit() && digits.len() < 6 => digits.push(char),
+                    _ => {
+                        return Err(error(
+                            String::from("Expected 1 to 6 hex digits followed by } in \\u{...}"),
+                            chars,
+                        ))
+                    }
+                }
+            }
+
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| error(format!("Invalid unicode escape \\u{{{}}}", digits), chars))
+        }
+        Some(char) => Err(error(format!("Unknown escape sequence \\{}", char), chars)),
+        None => Err(error(String::from("Unterminated escape sequence"), chars)),
+    }
+}
+
+/// Scans the tokens of a `${...}` block inside of a string, up to the closing brace
+fn scan_interpolation(chars: &mut Cursor) -> Result<Vec<Token>, Diagnostic> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+
+    loop {
+        let token = get_next_token(chars)?;
+
+        match token.symbol {
+            Symbol::LeftBrace => depth += 1,
+            Symbol::RightBrace if depth == 0 => break,
+            Symbol::RightBrace => depth -= 1,
+            Symbol::Fin => {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Scan,
+                    String::from("Unterminated ${ in string"),
+                )
+                .with_span(token.span))
+            }
+            _ => (),
+        }
+
+        tokens.push(token);
+    }
+
+    let end = chars.position();
+    tokens.push(Token {
+        span: Span::new(end, end),
+        symbol: Symbol::Fin,
+    });
+
+    Ok(tokens)
+}
+
+fn scan_string(start: Position, chars: &mut Cursor) -> Result<Token, Diagnostic> {
     let mut value = String::new();
+    let mut parts = Vec::new();
+
+    loop {
+        if let Some(char) = chars.advance() {
+            match char {
+                '"' => break,
+                '\\' => value.push(scan_escape(chars)?),
+                '$' if chars.peek() == Some('{') => {
+                    chars.advance();
+                    parts.push(StringPart::Literal(value));
+                    parts.push(StringPart::Expression(scan_interpolation(chars)?));
+                    value = String::new();
+                }
+                _ => value.push(char),
+            }
+            continue;
+        }
+
+        return Err(Diagnostic::error(
+            DiagnosticKind::Scan,
+            format!("Unterminated string (\"{})", value),
+        )
+        .with_span(Span::new(start, chars.position())));
+    }
+
+    if parts.is_empty() {
+        return Ok(chars.token(start, Symbol::Literal(Literal::String(value))));
+    }
+
+    parts.push(StringPart::Literal(value));
+    Ok(chars.token(start, Symbol::InterpolatedString(parts)))
+}
+
+/// Scans `r"..."` or `r#"..."#`, where the contents are taken verbatim. The
+/// number of `#`s around the quotes can be increased to allow `"#` inside.
+fn scan_raw_string(start: Position, chars: &mut Cursor) -> Result<Token, Diagnostic> {
+    let mut hashes = 0;
+    while chars.peek() == Some('#') {
+        chars.advance();
+        hashes += 1;
+    }
 
+    if chars.advance() != Some('"') {
+        return Err(Diagnostic::error(
+            DiagnosticKind::Scan,
+            String::from("Expected \" to start raw string"),
+        )
+        .with_span(Span::new(start, chars.position())));
+    }
+
+    let mut value = String::new();
     loop {
-        if let Some((_, char)) = chars.next() {
-            if char == '"' {
-                break;
+        match chars.advance() {
+            Some('"') => {
+                let mut closing = 0;
+                while closing < hashes && chars.peek() == Some('#') {
+                    chars.advance();
+                    closing += 1;
+                }
+
+                if closing == hashes {
+                    break;
+                }
+
+                value.push('"');
+                value.push_str(&"#".repeat(closing));
+            }
+            Some(char) => value.push(char),
+            None => {
+                return Err(Diagnostic::error(
+                    DiagnosticKind::Scan,
+                    String::from("Unterminated raw string"),
+                )
+                .with_span(Span::new(start, chars.position())))
             }
+        }
+    }
+
+    Ok(chars.token(start, Symbol::Literal(Literal::String(value))))
+}
+
+/// Consumes digits valid in `radix` along with `_` separators, the
+/// separators are dropped from the returned digits
+fn scan_digits(radix: u32, chars: &mut Cursor) -> String {
+    let mut digits = String::new();
 
-            value.push(char);
+    while let Some(char) = chars.peek() {
+        if char == '_' {
+            chars.advance();
             continue;
         }
 
-        return Err(format!(
-            "Unterminated string (\"{}), at index: {}",
-            value, index
-        ));
+        if !char.is_digit(radix) {
+            break;
+        }
+
+        digits.push(char);
+        chars.advance();
     }
 
-    return Ok(Token {
-        line: 0,
-        index,
-        symbol: Symbol::Literal(Literal::String(value)),
-    });
+    digits
 }
 
 fn scan_numeric(
     start_char: char,
-    start_index: usize,
-    chars: &mut Peekable<Enumerate<Chars>>,
-) -> Result<Token, String> {
-    let mut source = String::from(start_char);
-    let mut has_matched_period = false;
+    start: Position,
+    chars: &mut Cursor,
+) -> Result<Token, Diagnostic> {
+    let error = |message: String, chars: &Cursor| {
+        Diagnostic::error(DiagnosticKind::Scan, message)
+            .with_span(Span::new(start, chars.position()))
+    };
+
+    let radix = match (start_char, chars.peek()) {
+        ('0', Some('x')) => Some(16),
+        ('0', Some('o')) => Some(8),
+        ('0', Some('b')) => Some(2),
+        _ => None,
+    };
+
+    let literal = match radix {
+        Some(radix) => {
+            chars.advance();
+            let digits = scan_digits(radix, chars);
+            if digits.is_empty() {
+                return Err(error(
+                    String::from("Expected digits after base prefix"),
+                    chars,
+                ));
+            }
 
-    loop {
-        match chars.peek() {
-            None => break,
-            Some((_, char)) if char.is_whitespace() => break,
-            Some((_, char)) if char.is_numeric() => source.push(*char),
-            Some((_, char)) if *char == '.' && !has_matched_period => {
-                has_matched_period = true;
-                source.push(*char);
+            match i64::from_str_radix(&digits, radix) {
+                Ok(integer) => Literal::Integer(integer),
+                Err(_) => return Err(error(String::from("Integer literal is too large"), chars)),
+            }
+        }
+        None => {
+            let mut source = String::from(start_char);
+            source += &scan_digits(10, chars);
+            let mut is_float = false;
+
+            // Only treat `.` as a decimal point when a digit follows, so `1..2` stays a range,
+            // and never straight after a member `.`, so `tuple.0.1` is two accesses
+            if chars.peek() == Some('.')
+                && chars.peek_second().is_some_and(|ch| ch.is_ascii_digit())
+                && !chars.follows_member_dot(start)
+            {
+                chars.advance();
+                is_float = true;
+                source.push('.');
+                source += &scan_digits(10, chars);
+            }
+
+            if let Some('e' | 'E') = chars.peek() {
+                chars.advance();
+                is_float = true;
+                source.push('e');
+
+                if let Some(sign @ ('+' | '-')) = chars.peek() {
+                    chars.advance();
+                    source.push(sign);
+                }
+
+                let exponent = scan_digits(10, chars);
+                if exponent.is_empty() {
+                    return Err(error(String::from("Expected digits in exponent"), chars));
+                }
+                source += &exponent;
+            }
+
+            match is_float {
+                true => match source.parse::<f64>() {
+                    // `1e400` parses as infinity rather than failing
+                    Ok(number) if number.is_infinite() => {
+                        return Err(error(String::from("Number literal is too large"), chars))
+                    }
+                    Ok(number) => Literal::Number(number),
+                    Err(_) => return Err(error(format!("Invalid number {}", source), chars)),
+                },
+                false => match source.parse() {
+                    Ok(integer) => Literal::Integer(integer),
+                    Err(_) => {
+                        return Err(error(String::from("Integer literal is too large"), chars))
+                    }
+                },
             }
-            Some((_, _)) => break,
         }
-        chars.next();
+    };
+
+    if chars.peek().is_some_and(|ch| ch.is_alphanumeric()) {
+        while chars.peek().is_some_and(|ch| ch.is_alphanumeric()) {
+            chars.advance();
+        }
+
+        return Err(error(
+            String::from("Invalid digit in numeric literal"),
+            chars,
+        ));
     }
 
-    let value: f32 = source.parse().unwrap();
-    return Ok(Token {
-        line: 0,
-        index: start_index,
-        symbol: Symbol::Literal(Literal::Number(value)),
-    });
+    Ok(chars.token(start, Symbol::Literal(literal)))
+}
+
+/// Skips over a comment, `///` doc comments are kept as a token so they can
+/// be attached to the declaration that follows them
+fn scan_comment(start: Position, chars: &mut Cursor) -> Result<Option<Token>, Diagnostic> {
+    if let Some('*') = chars.advance() {
+        let mut depth = 1;
+        while depth > 0 {
+            match chars.advance() {
+                Some('*') if chars.peek() == Some('/') => {
+                    chars.advance();
+                    depth -= 1;
+                }
+                Some('/') if chars.peek() == Some('*') => {
+                    chars.advance();
+                    depth += 1;
+                }
+                Some(_) => continue,
+                None => {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::Scan,
+                        String::from("Unterminated block comment"),
+                    )
+                    .with_span(Span::new(start, chars.position())))
+                }
+            }
+        }
+
+        return Ok(None);
+    }
+
+    // `////...` is a regular comment, only exactly three slashes make a doc comment
+    let mut is_doc = false;
+    if chars.peek() == Some('/') {
+        chars.advance();
+        is_doc = chars.peek() != Some('/');
+    }
+
+    let mut value = String::new();
+    while let Some(char) = chars.peek() {
+        if char == '\n' {
+            break;
+        }
+
+        value.push(char);
+        chars.advance();
+    }
+
+    if !is_doc {
+        return Ok(None);
+    }
+
+    let value = value
+        .strip_prefix(' ')
+        .unwrap_or(&value)
+        .trim_end()
+        .to_owned();
+    Ok(Some(chars.token(start, Symbol::DocComment(value))))
 }
 
 fn get_symbol_from_identifier(identifier: String) -> Symbol {
@@ -186,135 +561,181 @@ fn get_symbol_from_identifier(identifier: String) -> Symbol {
         "false" => Symbol::Literal(Literal::Boolean(false)),
         "null" => Symbol::Literal(Literal::Null),
         "any" => Symbol::TypeLiteral(TypeLiteral::Any),
+        "integer" => Symbol::TypeLiteral(TypeLiteral::Integer),
         "number" => Symbol::TypeLiteral(TypeLiteral::Number),
         "string" => Symbol::TypeLiteral(TypeLiteral::String),
         "boolean" => Symbol::TypeLiteral(TypeLiteral::Boolean),
         "mutable" => Symbol::Keyword(Keyword::Mutable),
         "return" => Symbol::Keyword(Keyword::Return),
+        "by" => Symbol::Keyword(Keyword::By),
+        "while" => Symbol::Keyword(Keyword::While),
+        "break" => Symbol::Keyword(Keyword::Break),
+        "continue" => Symbol::Keyword(Keyword::Continue),
+        "for" => Symbol::Keyword(Keyword::For),
+        "in" => Symbol::Keyword(Keyword::In),
+        "loop" => Symbol::Keyword(Keyword::Loop),
+        "match" => Symbol::Keyword(Keyword::Match),
+        "struct" => Symbol::Keyword(Keyword::Struct),
+        "enum" => Symbol::Keyword(Keyword::Enum),
+        "interface" => Symbol::Keyword(Keyword::Interface),
+        "impl" => Symbol::Keyword(Keyword::Impl),
+        "type" => Symbol::Keyword(Keyword::Type),
+        "is" => Symbol::Keyword(Keyword::Is),
         _ => Symbol::Identifier(identifier),
     }
 }
 
 fn scan_indentifier(
     start_char: char,
-    start_index: usize,
-    chars: &mut Peekable<Enumerate<Chars>>,
-) -> Result<Token, String> {
+    start: Position,
+    chars: &mut Cursor,
+) -> Result<Token, Diagnostic> {
     let mut value = String::from(start_char);
 
     loop {
         match chars.peek() {
             None => break,
-            Some((_, char)) if char.is_whitespace() => break,
-            Some((_, char)) if *char == '_' || char.is_alphanumeric() => value.push(*char),
-            Some((_, _)) => break,
+            Some(char) if char.is_whitespace() => break,
+            Some(char) if char == '_' || char.is_alphanumeric() => value.push(char),
+            Some(_) => break,
         };
-        chars.next();
+        chars.advance();
     }
 
-    return Ok(Token {
-        line: 0,
-        index: start_index,
-        symbol: get_symbol_from_identifier(value),
-    });
+    Ok(chars.token(start, get_symbol_from_identifier(value)))
+}
+
+/// Scans the name of a loop label, the leading `'` has already been consumed
+fn scan_label(start: Position, chars: &mut Cursor) -> Token {
+    let mut name = String::new();
+
+    while let Some(char) = chars.peek().filter(|ch| *ch == '_' || ch.is_alphanumeric()) {
+        name.push(char);
+        chars.advance();
+    }
+
+    chars.token(start, Symbol::Label(name))
 }
 
-fn scan_alphanumeric(
-    char: char,
-    index: usize,
-    chars: &mut Peekable<Enumerate<Chars>>,
-) -> Result<Token, String> {
-    if char.is_numeric() {
-        return scan_numeric(char, index, chars);
+fn scan_alphanumeric(char: char, start: Position, chars: &mut Cursor) -> Result<Token, Diagnostic> {
+    if char.is_ascii_digit() {
+        return scan_numeric(char, start, chars);
     }
 
-    return scan_indentifier(char, index, chars);
+    scan_indentifier(char, start, chars)
 }
 
-fn scan_special(
-    char: char,
-    index: usize,
-    chars: &mut Peekable<Enumerate<Chars>>,
-) -> Result<Token, String> {
+fn scan_special(char: char, start: Position, chars: &mut Cursor) -> Result<Token, Diagnostic> {
     match char {
-        '\"' => scan_string(index, chars),
-        '!' if chars.peek().is_some_and(|(_, ch)| *ch == '=') => {
-            if let Some((_, '=')) = chars.next() {
-                Ok(Token {
-                    line: 0,
-                    index: index,
-                    symbol: Symbol::Operator(Operator::NotEqual),
-                })
+        '\"' => scan_string(start, chars),
+        '!' if chars.peek().is_some_and(|ch| ch == '=') => {
+            if let Some('=') = chars.advance() {
+                Ok(chars.token(start, Symbol::Operator(Operator::NotEqual)))
             } else {
-                Err(String::from("! was not followed by ="))
+                Err(Diagnostic::error(
+                    DiagnosticKind::Scan,
+                    String::from("! was not followed by ="),
+                )
+                .with_span(Span::new(start, chars.position())))
             }
         }
-        '<' | '>' if chars.peek().is_some_and(|(_, ch)| *ch == '=') => {
-            chars.next();
-            Ok(Token {
-                line: 0,
-                index,
-                symbol: match char {
-                    '>' => Symbol::Operator(Operator::GreaterThanOrEqual),
-                    '<' => Symbol::Operator(Operator::LesserThanOrEqual),
-                    _ => return Err(String::from("Help!")),
-                },
-            })
+        '<' | '>' if chars.peek().is_some_and(|ch| ch == '=') => {
+            chars.advance();
+            let symbol = match char {
+                '>' => Symbol::Operator(Operator::GreaterThanOrEqual),
+                '<' => Symbol::Operator(Operator::LesserThanOrEqual),
+                _ => {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::Internal,
+                        format!("Cannot scan comparison starting with {}", char),
+                    )
+                    .with_span(Span::new(start, chars.position())))
+                }
+            };
+
+            Ok(chars.token(start, symbol))
         }
-        '=' if chars.peek().is_some_and(|(_, ch)| *ch == '>' || *ch == '=') => match chars.next() {
-            Some((_, '>')) => Ok(Token {
-                line: 0,
-                index,
-                symbol: Symbol::Arrow,
-            }),
-            Some((_, '=')) => Ok(Token {
-                line: 0,
-                index,
-                symbol: Symbol::Operator(Operator::Equal),
-            }),
-            _ => Err(String::from("gg")),
+        '=' if chars.peek().is_some_and(|ch| ch == '>' || ch == '=') => match chars.advance() {
+            Some('>') => Ok(chars.token(start, Symbol::Arrow)),
+            Some('=') => Ok(chars.token(start, Symbol::Operator(Operator::Equal))),
+            _ => Err(Diagnostic::error(
+                DiagnosticKind::Internal,
+                String::from("Expected => or == after ="),
+            )
+            .with_span(Span::new(start, chars.position()))),
         },
-        _ => Err("scan special was given a char that it cannot process".to_owned()),
+        _ => Err(Diagnostic::error(
+            DiagnosticKind::Internal,
+            "scan special was given a char that it cannot process".to_owned(),
+        )
+        .with_span(Span::new(start, chars.position()))),
     }
 }
 
-fn get_next_token(chars: &mut Peekable<Enumerate<Chars>>) -> Result<Token, String> {
-    match chars.next() {
-        Some((index, char)) => match char {
-            '\"' => scan_special(char, index, chars),
-            '!' if chars.peek().is_some_and(|(_, ch)| *ch == '=') => {
-                scan_special(char, index, chars)
+fn get_next_token(chars: &mut Cursor) -> Result<Token, Diagnostic> {
+    let start = chars.position();
+
+    match chars.advance() {
+        Some(char) => match char {
+            '\"' => scan_special(char, start, chars),
+            'r' if chars.peek().is_some_and(|ch| ch == '"' || ch == '#') => {
+                scan_raw_string(start, chars)
+            }
+            '!' if chars.peek().is_some_and(|ch| ch == '=') => scan_special(char, start, chars),
+            '<' | '>' if chars.peek().is_some_and(|ch| ch == '=') => {
+                scan_special(char, start, chars)
+            }
+            '=' if chars.peek().is_some_and(|ch| ch == '>' || ch == '=') => {
+                scan_special(char, start, chars)
+            }
+            '\'' if chars.peek().is_some_and(|ch| ch.is_alphabetic()) => {
+                Ok(scan_label(start, chars))
+            }
+            '|' if chars.peek() == Some('>') => {
+                chars.advance();
+                Ok(chars.token(start, Symbol::Pipe))
+            }
+            ':' if chars.peek() == Some(':') => {
+                chars.advance();
+                Ok(chars.token(start, Symbol::DoubleColon))
             }
-            '<' | '>' if chars.peek().is_some_and(|(_, ch)| *ch == '=') => {
-                scan_special(char, index, chars)
+            '.' if chars.peek() == Some('.') => {
+                chars.advance();
+                match chars.peek() {
+                    Some('=') => {
+                        chars.advance();
+                        Ok(chars.token(start, Symbol::DotDotEqual))
+                    }
+                    Some('.') => {
+                        chars.advance();
+                        Ok(chars.token(start, Symbol::Ellipsis))
+                    }
+                    _ => Ok(chars.token(start, Symbol::DotDot)),
+                }
             }
-            '=' if chars.peek().is_some_and(|(_, ch)| *ch == '>' || *ch == '=') => {
-                scan_special(char, index, chars)
+            '/' if chars.peek().is_some_and(|ch| ch == '/' || ch == '*') => {
+                match scan_comment(start, chars)? {
+                    Some(token) => Ok(token),
+                    None => get_next_token(chars),
+                }
             }
             _ if char.is_whitespace() => get_next_token(chars),
-            _ if char.is_alphanumeric() => scan_alphanumeric(char, index, chars),
+            _ if char.is_alphanumeric() || char == '_' => scan_alphanumeric(char, start, chars),
             _ => match scan_symbol(char) {
-                Some(symbol) => Ok(Token {
-                    line: 0,
-                    index,
-                    symbol,
-                }),
-                None => Err(format!(
-                    "Unrecognized symbol: \"{}\", at index: {}",
-                    char, index
-                )),
+                Some(symbol) => Ok(chars.token(start, symbol)),
+                None => Err(Diagnostic::error(
+                    DiagnosticKind::Scan,
+                    format!("Unrecognized symbol: \"{}\"", char),
+                )
+                .with_span(Span::new(start, chars.position()))),
             },
         },
-        None => Ok(Token {
-            line: 0,
-            index: 0,
-            symbol: Symbol::Fin,
-        }),
+        None => Ok(chars.token(start, Symbol::Fin)),
     }
 }
 
-pub fn scan(source: &String) -> Result<Vec<Token>, String> {
-    let mut chars = source.chars().enumerate().peekable();
+pub fn scan(source: &str) -> Result<Vec<Token>, Diagnostic> {
+    let mut chars = Cursor::new(source);
     let mut tokens = Vec::new();
 
     loop {
@@ -326,5 +747,161 @@ pub fn scan(source: &String) -> Result<Vec<Token>, String> {
         }
     }
 
-    return Result::Ok(tokens);
+    Result::Ok(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{scan, Keyword, Literal, Operator, StringPart, Symbol};
+
+    #[test]
+    fn tracks_line_and_column() {
+        let tokens = scan("let x = 1;\n  x + \"é\";").unwrap();
+
+        let x = &tokens[5];
+        assert_eq!(x.symbol, Symbol::Identifier("x".to_owned()));
+        assert_eq!((x.span.start.line, x.span.start.column), (2, 3));
+        assert_eq!(x.span.start.offset, 13);
+
+        let string = &tokens[7];
+        assert_eq!((string.span.start.column, string.span.end.column), (7, 10));
+        assert_eq!(string.span.end.offset - string.span.start.offset, 4);
+    }
+
+    #[test]
+    fn skips_comments_and_keeps_doc_comments() {
+        let source = "// line\n/* outer /* nested */ still */ 1 /// doc\n//// not doc\n2 / 3.5";
+        let symbols: Vec<Symbol> = scan(source)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.symbol)
+            .collect();
+
+        assert_eq!(
+            symbols,
+            vec![
+                Symbol::Literal(Literal::Integer(1)),
+                Symbol::DocComment("doc".to_owned()),
+                Symbol::Literal(Literal::Integer(2)),
+                Symbol::Operator(Operator::Slash),
+                Symbol::Literal(Literal::Number(3.5)),
+                Symbol::Fin,
+            ]
+        );
+
+        assert!(scan("/* /* */").is_err());
+    }
+
+    #[test]
+    fn scans_escapes_raw_strings_and_interpolation() {
+        let string = |source: &str| scan(source).unwrap()[0].symbol.clone();
+
+        assert_eq!(
+            string(r#""a\n\t\\\"\u{e9}\${x}""#),
+            Symbol::Literal(Literal::String("a\n\t\\\"é${x}".to_owned()))
+        );
+        assert_eq!(
+            string(r###"r#"no \n "escapes""#"###),
+            Symbol::Literal(Literal::String("no \\n \"escapes\"".to_owned()))
+        );
+
+        let Symbol::InterpolatedString(parts) = string(r#""a${ {x} }b""#) else {
+            panic!("Expected an interpolated string");
+        };
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], StringPart::Literal("a".to_owned()));
+        assert_eq!(parts[2], StringPart::Literal("b".to_owned()));
+        let StringPart::Expression(tokens) = &parts[1] else {
+            panic!("Expected tokens of the interpolated expression");
+        };
+        assert_eq!(tokens.len(), 4);
+
+        assert!(scan(r#""\q""#).is_err());
+        assert!(scan(r#""${x""#).is_err());
+    }
+
+    #[test]
+    fn scans_numeric_literals() {
+        let number = |source: &str| scan(source).unwrap()[0].symbol.clone();
+
+        assert_eq!(number("0xFF"), Symbol::Literal(Literal::Integer(255)));
+        assert_eq!(number("0b1010"), Symbol::Literal(Literal::Integer(10)));
+        assert_eq!(number("0o17"), Symbol::Literal(Literal::Integer(15)));
+        assert_eq!(
+            number("1_000_000"),
+            Symbol::Literal(Literal::Integer(1_000_000))
+        );
+        assert_eq!(number("1.5e-3"), Symbol::Literal(Literal::Number(1.5e-3)));
+        assert_eq!(number("2E10"), Symbol::Literal(Literal::Number(2e10)));
+        assert_eq!(number("1_0.2_5"), Symbol::Literal(Literal::Number(10.25)));
+
+        assert!(scan("0x").is_err());
+        assert!(scan("0b102").is_err());
+        assert!(scan("1e").is_err());
+        assert!(scan("99999999999999999999").is_err());
+        assert!(scan("1e400").is_err());
+        assert!(scan("1.5e308").is_ok());
+        assert!(scan("١٢").is_ok());
+    }
+
+    #[test]
+    fn scans_ranges() {
+        let symbols = |source: &str| {
+            scan(source)
+                .unwrap()
+                .into_iter()
+                .map(|token| token.symbol)
+                .collect::<Vec<Symbol>>()
+        };
+
+        assert_eq!(
+            symbols("1..=10 by 2"),
+            [
+                Symbol::Literal(Literal::Integer(1)),
+                Symbol::DotDotEqual,
+                Symbol::Literal(Literal::Integer(10)),
+                Symbol::Keyword(Keyword::By),
+                Symbol::Literal(Literal::Integer(2)),
+                Symbol::Fin
+            ]
+        );
+        assert_eq!(symbols("1..2")[1], Symbol::DotDot);
+        assert_eq!(symbols("[...rest]")[1], Symbol::Ellipsis);
+    }
+
+    #[test]
+    fn scans_tuple_positions_as_integers() {
+        let symbols: Vec<Symbol> = scan("pair.0.1 + 0.5")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.symbol)
+            .collect();
+
+        assert_eq!(symbols[2], Symbol::Literal(Literal::Integer(0)));
+        assert_eq!(symbols[3], Symbol::Dot);
+        assert_eq!(symbols[4], Symbol::Literal(Literal::Integer(1)));
+        assert_eq!(symbols[6], Symbol::Literal(Literal::Number(0.5)));
+    }
+
+    #[test]
+    fn scans_loop_labels() {
+        let tokens = scan("break 'outer_2;").unwrap();
+        assert_eq!(tokens[1].symbol, Symbol::Label(String::from("outer_2")));
+        assert_eq!(tokens[1].span.start.column, 7);
+        assert_eq!(tokens[2].symbol, Symbol::Semi);
+
+        assert!(scan("' outer").is_err());
+    }
+
+    #[test]
+    fn scans_pipes_apart_from_or() {
+        let symbols: Vec<Symbol> = scan("xs |> f | g")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.symbol)
+            .collect();
+
+        assert_eq!(symbols[1], Symbol::Pipe);
+        assert_eq!(symbols[3], Symbol::Operator(Operator::Or));
+    }
 }
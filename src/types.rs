@@ -1,11 +1,9 @@
-use core::fmt::Debug;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use crate::callable::Callable;
-
-trait ApplyType: Debug + Clone {
-    fn apply(&self, argument_types: Vec<Type>) -> bool;
-}
+use crate::{
+    callable::Callable,
+    diagnostic::{Diagnostic, DiagnosticKind},
+};
 
 #[derive(Debug, Clone)]
 pub enum FunctionType {
@@ -15,7 +13,9 @@ pub enum FunctionType {
 }
 
 impl FunctionType {
-    pub fn apply(&self, argument_types: Vec<Type>) -> Result<Type, String> {
+    /// The type returned by calling the function, supplying only some of the
+    /// arguments gives a function taking the rest
+    pub fn apply(&self, argument_types: Vec<Type>) -> Result<Type, Diagnostic> {
         match self {
             Self::WithBody(function_instance) => function_instance
                 .borrow()
@@ -23,28 +23,72 @@ impl FunctionType {
                 .get_type()?
                 .apply(argument_types),
             Self::Literal(expected_argument_types, expected_return_type) => {
-                if argument_types.len() != expected_argument_types.len() {
-                    return Err(String::from("Argument supplied length doesn't match"));
+                // type variables take the types of the arguments given for them,
+                // partial applications leave the rest to later arguments
+                let mut inferred = HashMap::new();
+                for (expected_argument_type, argument_type) in
+                    expected_argument_types.iter().zip(argument_types.iter())
+                {
+                    expected_argument_type.infer_parameters(argument_type, &mut inferred);
+                }
+                let expected_argument_types: Vec<Type> = expected_argument_types
+                    .iter()
+                    .map(|argument_type| argument_type.substitute(&inferred))
+                    .collect();
+                let expected_return_type = expected_return_type.substitute(&inferred);
+
+                if argument_types.len() > expected_argument_types.len()
+                    || (argument_types.is_empty() && !expected_argument_types.is_empty())
+                {
+                    return Err(Diagnostic::error(
+                        DiagnosticKind::ArgumentCount,
+                        format!(
+                            "Function expects {} arguments but {} were supplied",
+                            expected_argument_types.len(),
+                            argument_types.len()
+                        ),
+                    ));
                 }
 
                 for (i, argument_type) in argument_types.iter().enumerate() {
                     let expected_argument_type = &expected_argument_types[i];
 
                     if !argument_type.is_sub_type_of(expected_argument_type) {
-                        return Err(String::from("Argument supplied type doesn't match"));
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!(
+                                "Argument {} was {} which doesn't subtype {}",
+                                i + 1,
+                                argument_type,
+                                expected_argument_type
+                            ),
+                        ));
                     }
                 }
 
+                if argument_types.len() < expected_argument_types.len() {
+                    return Ok(Type::Function(Box::from(FunctionType::Literal(
+                        expected_argument_types[argument_types.len()..].to_vec(),
+                        expected_return_type.clone(),
+                    ))));
+                }
+
                 Ok(expected_return_type.clone())
             }
             Self::ArrayArgs(expected_array_argument_type, return_type) => {
                 for argument_type in argument_types.iter() {
                     if !argument_type.is_sub_type_of(expected_array_argument_type) {
-                        return Err(String::from("Argument list doesn't match contract"));
+                        return Err(Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!(
+                                "Argument was {} which doesn't subtype {}",
+                                argument_type, expected_array_argument_type
+                            ),
+                        ));
                     }
                 }
 
-                return Ok(return_type.clone());
+                Ok(return_type.clone())
             }
         }
     }
@@ -60,7 +104,7 @@ impl FunctionType {
         if let FunctionType::WithBody(function_instance) = other {
             return match function_instance.borrow().clone().get_type() {
                 Err(_) => false,
-                Ok(type_) => type_.is_sub_type_of(other),
+                Ok(type_) => self.is_sub_type_of(&type_),
             };
         }
 
@@ -70,10 +114,26 @@ impl FunctionType {
                     return false;
                 }
 
+                // a generic function stands in for any of its instances,
+                // `(x) => x` is a `(integer) => integer`
+                let mut inferred = HashMap::new();
+                for (argument_type, other_argument_type) in
+                    argument_types.iter().zip(other_argument_types.iter())
+                {
+                    argument_type.infer_parameters(other_argument_type, &mut inferred);
+                }
+                let argument_types: Vec<Type> = argument_types
+                    .iter()
+                    .map(|argument_type| argument_type.substitute(&inferred))
+                    .collect();
+                let return_type = return_type.substitute(&inferred);
+
+                // a function can stand in for another when it accepts at least
+                // everything the other one does
                 for (i, argument_type) in argument_types.iter().enumerate() {
                     let other_argument_type = &other_argument_types[i];
 
-                    if !argument_type.is_sub_type_of(other_argument_type) {
+                    if !other_argument_type.is_sub_type_of(argument_type) {
                         return false;
                     }
                 }
@@ -92,33 +152,292 @@ impl FunctionType {
             }
         }
 
-        return false;
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StructField {
+    pub name: String,
+    pub mutable: bool,
+    pub type_: Type,
+}
+
+/// `struct Name { field: type, mutable other: type }`
+#[derive(Debug, Clone)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<StructField>,
+}
+
+impl StructType {
+    pub fn field(&self, name: &str) -> Option<&StructField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    pub name: String,
+    pub fields: Vec<Type>,
+}
+
+/// `enum Name<T> { Variant(T, number), Other }`
+#[derive(Debug, Clone)]
+pub struct EnumType {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub variants: Vec<EnumVariant>,
+}
+
+impl EnumType {
+    pub fn variant(&self, name: &str) -> Option<&EnumVariant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    /// The types of a variant's fields for the given type arguments, missing
+    /// arguments are `any`
+    pub fn field_types(&self, variant: &EnumVariant, arguments: &[Type]) -> Vec<Type> {
+        let parameters = self
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(i, parameter)| {
+                (
+                    parameter.clone(),
+                    arguments
+                        .get(i)
+                        .cloned()
+                        .unwrap_or(Type::BaseType(BaseType::Any)),
+                )
+            })
+            .collect();
+
+        variant
+            .fields
+            .iter()
+            .map(|field| field.substitute(&parameters))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterfaceMethod {
+    pub name: String,
+    pub type_: Type,
+}
+
+/// `interface Name { method: (Self, number) => Self }`, `Self` stands for
+/// whichever type implements it
+#[derive(Clone)]
+pub struct InterfaceType {
+    pub name: String,
+    pub methods: Vec<InterfaceMethod>,
+    /// The types with an `impl` of the interface, shared between copies so
+    /// annotations resolved before an `impl` still see it
+    pub implementors: Rc<RefCell<Vec<Type>>>,
+}
+
+impl InterfaceType {
+    pub fn method(&self, name: &str) -> Option<&InterfaceMethod> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+
+    /// The type of a method when implemented for `self_type`
+    pub fn method_type(&self, method: &InterfaceMethod, self_type: &Type) -> Type {
+        method
+            .type_
+            .substitute(&HashMap::from([(String::from("Self"), self_type.clone())]))
+    }
+
+    pub fn is_implemented_by(&self, type_: &Type) -> bool {
+        self.implementors
+            .borrow()
+            .iter()
+            .any(|implementor| type_.is_sub_type_of(implementor))
+    }
+}
+
+impl std::fmt::Debug for InterfaceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// `type Tree = integer | Tree[]`, a name for another type. Uses of the name
+/// are replaced with the type it stands for, except inside its own definition
+/// where they stay a `Type::Alias` so recursive aliases don't expand forever
+#[derive(Clone)]
+pub struct AliasType {
+    pub name: String,
+    pub parameters: Vec<String>,
+    /// The type the alias stands for, `None` while its own definition is being
+    /// resolved. Shared with the recursive uses inside it
+    pub definition: Rc<RefCell<Option<Type>>>,
+    /// The comparisons involving the alias that are in progress, see
+    /// `Type::is_sub_type_of`
+    assumed: Rc<RefCell<Vec<String>>>,
+}
+
+impl AliasType {
+    pub fn new(name: String, parameters: Vec<String>) -> AliasType {
+        AliasType {
+            name,
+            parameters,
+            definition: Rc::new(RefCell::new(None)),
+            assumed: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub fn is_defined(&self) -> bool {
+        self.definition.borrow().is_some()
+    }
+
+    /// The type the alias stands for with its parameters replaced by
+    /// `arguments`, parameters without an argument accept anything
+    pub fn expand(&self, arguments: &[Type]) -> Type {
+        let Some(definition) = self.definition.borrow().clone() else {
+            return Type::Alias(self.clone(), arguments.to_vec());
+        };
+
+        let parameters = self
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(i, parameter)| {
+                let argument = arguments
+                    .get(i)
+                    .cloned()
+                    .unwrap_or(Type::BaseType(BaseType::Any));
+                (parameter.clone(), argument)
+            })
+            .collect();
+
+        definition.substitute(&parameters)
+    }
+
+    /// Runs `check` unless the comparison called `key` is already in progress,
+    /// in which case it's assumed to hold. A recursive alias coming back to the
+    /// same comparison has found nothing that breaks it
+    fn assuming(&self, key: String, check: impl FnOnce() -> bool) -> bool {
+        if self.assumed.borrow().contains(&key) {
+            return true;
+        }
+
+        self.assumed.borrow_mut().push(key.clone());
+        let result = check();
+
+        let mut assumed = self.assumed.borrow_mut();
+        if let Some(position) = assumed.iter().position(|existing| *existing == key) {
+            assumed.remove(position);
+        }
+
+        result
+    }
+}
+
+impl std::fmt::Debug for AliasType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// What a type name declared in the program refers to
+#[derive(Debug, Clone)]
+pub enum TypeDefinition {
+    Struct(StructType),
+    Enum(EnumType),
+    Interface(InterfaceType),
+    Alias(AliasType),
+}
+
+impl TypeDefinition {
+    /// What kind of type this is, for error messages
+    pub fn kind(&self) -> &str {
+        match self {
+            TypeDefinition::Struct(_) => "a struct",
+            TypeDefinition::Enum(_) => "an enum",
+            TypeDefinition::Interface(_) => "an interface",
+            TypeDefinition::Alias(_) => "a type alias",
+        }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum BaseType {
     Infer,
+    /// The type of a value that can never exist, e.g. the elements of `[]`,
+    /// it subtypes every other type
+    Never,
     Any,
     Null,
     String,
+    /// A whole number, every `integer` is also a `number`
+    Integer,
     Number,
     Boolean,
 }
 
+impl std::fmt::Display for BaseType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BaseType::Infer => "_",
+            BaseType::Never => "never",
+            BaseType::Any => "any",
+            BaseType::Null => "null",
+            BaseType::String => "string",
+            BaseType::Integer => "integer",
+            BaseType::Number => "number",
+            BaseType::Boolean => "boolean",
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Type {
     BaseType(BaseType),
     Or(Box<Type>, Box<Type>),
     List(Box<Type>),
+    /// `(integer, string)`, a fixed length list with a type per position
+    Tuple(Vec<Type>),
+    /// A user defined struct or enum, types with the same name are the same
+    /// type. Generic enums carry their type arguments, `Optional<integer>`
+    Named(String, Vec<Type>),
+    /// A type parameter like the `T` of `<T>(x: T) => x`, it stands for
+    /// whatever type is used where it's declared
+    Variable(String),
+    /// A value of any type that implements the interface
+    Interface(InterfaceType),
+    /// A use of a recursive alias inside its own definition, e.g. the `Tree`
+    /// of `type Tree = integer | Tree[]`, with its type arguments
+    Alias(AliasType, Vec<Type>),
     Function(Box<FunctionType>),
     Return(Box<Type>),
 }
 
 impl Type {
+    /// A type that both `self` and `other` subtype, avoiding an `Or` when one
+    /// already contains the other
+    pub fn union(self, other: Type) -> Type {
+        if other.is_sub_type_of(&self) {
+            return self;
+        }
+
+        if self.is_sub_type_of(&other) {
+            return other;
+        }
+
+        Type::Or(Box::from(self), Box::from(other))
+    }
+
     pub fn get_return_type(&self) -> Option<Type> {
         match self {
             Self::List(_) => None,
+            Self::Tuple(_) => None,
+            Self::Named(..) => None,
+            Self::Interface(_) => None,
+            Self::Alias(..) => None,
+            Self::Variable(_) => None,
             Self::BaseType(_) => None,
             Self::Function(_) => None,
             Self::Return(return_type) => Some((**return_type).clone()),
@@ -135,28 +454,279 @@ impl Type {
         }
     }
 
+    /// The type variable standing for the type of the unannotated parameter
+    /// `name` until the function body decides it
+    pub fn inferred(name: &str) -> Type {
+        Type::Variable(format!("'{}", name))
+    }
+
+    /// Whether the type variable called `name` is one made by `Type::inferred`
+    pub fn is_inferred(name: &str) -> bool {
+        name.starts_with('\'')
+    }
+
+    /// Whether there are type variables anywhere in the type
+    pub fn is_generic(&self) -> bool {
+        match self {
+            Type::Variable(_) => true,
+            Type::Named(_, arguments) | Type::Alias(_, arguments) => {
+                arguments.iter().any(Type::is_generic)
+            }
+            Type::Or(left, right) => left.is_generic() || right.is_generic(),
+            Type::List(element_type) => element_type.is_generic(),
+            Type::Tuple(element_types) => element_types.iter().any(Type::is_generic),
+            Type::Return(return_type) => return_type.is_generic(),
+            Type::Function(function_type) => match function_type.as_ref() {
+                FunctionType::Literal(argument_types, return_type) => {
+                    argument_types.iter().any(Type::is_generic) || return_type.is_generic()
+                }
+                _ => false,
+            },
+            Type::BaseType(_) | Type::Interface(_) => false,
+        }
+    }
+
+    /// Replaces the type variables in `parameters` with their types
+    pub fn substitute(&self, parameters: &HashMap<String, Type>) -> Type {
+        let substitute_all = |types: &Vec<Type>| {
+            types
+                .iter()
+                .map(|type_| type_.substitute(parameters))
+                .collect::<Vec<Type>>()
+        };
+
+        match self {
+            Type::Variable(name) => parameters
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| self.clone()),
+            Type::Named(name, arguments) => Type::Named(name.clone(), substitute_all(arguments)),
+            Type::Alias(alias, arguments) => Type::Alias(alias.clone(), substitute_all(arguments)),
+            Type::Or(left, right) => Type::Or(
+                Box::from(left.substitute(parameters)),
+                Box::from(right.substitute(parameters)),
+            ),
+            Type::List(element_type) => Type::List(Box::from(element_type.substitute(parameters))),
+            Type::Tuple(element_types) => Type::Tuple(substitute_all(element_types)),
+            Type::Return(return_type) => {
+                Type::Return(Box::from(return_type.substitute(parameters)))
+            }
+            Type::Function(function_type) => match function_type.as_ref() {
+                FunctionType::Literal(argument_types, return_type) => {
+                    Type::Function(Box::from(FunctionType::Literal(
+                        substitute_all(argument_types),
+                        return_type.substitute(parameters),
+                    )))
+                }
+                _ => self.clone(),
+            },
+            Type::BaseType(_) | Type::Interface(_) => self.clone(),
+        }
+    }
+
+    /// Works out the types of the type variables in `self` from a matching
+    /// `actual` type, e.g. `T[]` and `integer[]` give `T = integer`
+    pub fn infer_parameters(&self, actual: &Type, inferred: &mut HashMap<String, Type>) {
+        let infer_all = |expected: &Vec<Type>, actual: &Vec<Type>, inferred: &mut _| {
+            if expected.len() == actual.len() {
+                for (expected, actual) in expected.iter().zip(actual.iter()) {
+                    expected.infer_parameters(actual, inferred);
+                }
+            }
+        };
+
+        match (self, actual) {
+            (Type::Variable(name), _) => {
+                let inferred_type = match inferred.remove(name) {
+                    Some(existing) => existing.union(actual.clone()),
+                    None => actual.clone(),
+                };
+                inferred.insert(name.clone(), inferred_type);
+            }
+            (Type::Named(name, arguments), Type::Named(actual_name, actual_arguments))
+                if name == actual_name =>
+            {
+                infer_all(arguments, actual_arguments, inferred)
+            }
+            (Type::Alias(alias, arguments), Type::Alias(actual_alias, actual_arguments))
+                if alias.name == actual_alias.name =>
+            {
+                infer_all(arguments, actual_arguments, inferred)
+            }
+            (Type::Alias(..), Type::Alias(..)) => (),
+            (Type::Alias(alias, arguments), _) => {
+                alias.expand(arguments).infer_parameters(actual, inferred)
+            }
+            (_, Type::Alias(actual_alias, actual_arguments)) => {
+                self.infer_parameters(&actual_alias.expand(actual_arguments), inferred)
+            }
+            (Type::List(element_type), Type::List(actual_element_type)) => {
+                element_type.infer_parameters(actual_element_type, inferred)
+            }
+            (Type::Tuple(element_types), Type::Tuple(actual_element_types)) => {
+                infer_all(element_types, actual_element_types, inferred)
+            }
+            (Type::Function(function_type), Type::Function(actual_function_type)) => {
+                let FunctionType::Literal(argument_types, return_type) = function_type.as_ref()
+                else {
+                    return;
+                };
+
+                // a function's body has to be checked before its return type is known
+                let actual_function_type = match actual_function_type.as_ref() {
+                    FunctionType::WithBody(function_instance) => {
+                        match function_instance.borrow().clone().get_type() {
+                            Ok(function_type) => function_type,
+                            Err(_) => return,
+                        }
+                    }
+                    function_type => function_type.clone(),
+                };
+
+                if let FunctionType::Literal(actual_argument_types, actual_return_type) =
+                    actual_function_type
+                {
+                    // the arguments a function accepts don't widen a variable
+                    // already decided by a value, the function is checked against it
+                    let mut from_arguments = HashMap::new();
+                    infer_all(argument_types, &actual_argument_types, &mut from_arguments);
+                    for (name, type_) in from_arguments {
+                        inferred.entry(name).or_insert(type_);
+                    }
+
+                    // a generic function returns whatever it's given, so its
+                    // return type depends on the arguments it will be called with
+                    let mut instance = HashMap::new();
+                    for (argument_type, actual_argument_type) in
+                        argument_types.iter().zip(actual_argument_types.iter())
+                    {
+                        actual_argument_type
+                            .infer_parameters(&argument_type.substitute(inferred), &mut instance);
+                    }
+                    instance.retain(|_, type_| !type_.is_generic());
+
+                    return_type
+                        .infer_parameters(&actual_return_type.substitute(&instance), inferred);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The members of a union, e.g. `number | string | null` gives all three
+    pub fn members(&self) -> Vec<Type> {
+        match self {
+            Type::Or(left, right) => {
+                let mut members = left.members();
+                members.extend(right.members());
+                members
+            }
+            Type::Alias(alias, arguments) if alias.is_defined() => {
+                alias.expand(arguments).members()
+            }
+            _ => vec![self.clone()],
+        }
+    }
+
+    /// The part of `self` that is also a `to`, or `None` if a value of `self`
+    /// can never be a `to`. `(number | string).narrow(integer)` is `integer`
+    pub fn narrow(&self, to: &Type) -> Option<Type> {
+        if self.is_sub_type_of(to) {
+            return Some(self.clone());
+        }
+
+        if let Type::Alias(alias, arguments) = self {
+            return alias.expand(arguments).narrow(to);
+        }
+
+        if let Type::Or(left, right) = self {
+            return match (left.narrow(to), right.narrow(to)) {
+                (Some(left), Some(right)) => Some(left.union(right)),
+                (left, right) => left.or(right),
+            };
+        }
+
+        if to.is_sub_type_of(self) {
+            return Some(to.clone());
+        }
+
+        None
+    }
+
+    /// The members of `self` that aren't a `type_`, e.g. `(number | null)
+    /// .exclude(null)` is `number`. Excluding every member leaves `never`
+    pub fn exclude(&self, type_: &Type) -> Type {
+        self.members()
+            .into_iter()
+            .filter(|member| !member.is_sub_type_of(type_))
+            .reduce(Type::union)
+            .unwrap_or(Type::BaseType(BaseType::Never))
+    }
+
     pub fn is_sub_type_of(&self, other: &Type) -> bool {
         if let Type::BaseType(BaseType::Any) = other {
             return true;
         }
 
+        if let Type::BaseType(BaseType::Never) = self {
+            return true;
+        }
+
         if let Type::BaseType(BaseType::Any) = self {
             return false;
         }
 
-        if let Type::Or(left, right) = other {
-            if let Type::Or(self_left, self_right) = self {
-                return (self_left.is_sub_type_of(left) || self_left.is_sub_type_of(right))
-                    && (self_right.is_sub_type_of(left) || self_right.is_sub_type_of(right));
+        // a recursive alias is unfolded one level at a time, coming back to a
+        // comparison that is already being made means it holds
+        if let (Type::Alias(alias, arguments), Type::Alias(other_alias, other_arguments)) =
+            (self, other)
+        {
+            if alias.name == other_alias.name
+                && arguments.len() == other_arguments.len()
+                && arguments
+                    .iter()
+                    .zip(other_arguments.iter())
+                    .all(|(argument, other_argument)| argument.is_sub_type_of(other_argument))
+            {
+                return true;
             }
+        }
 
-            return self.is_sub_type_of(left) || self.is_sub_type_of(right);
+        if let Type::Alias(alias, arguments) = self {
+            return alias.assuming(format!("{:?} <: {:?}", self, other), || {
+                alias.expand(arguments).is_sub_type_of(other)
+            });
+        }
+
+        if let Type::Alias(alias, arguments) = other {
+            return alias.assuming(format!("{:?} <: {:?}", self, other), || {
+                self.is_sub_type_of(&alias.expand(arguments))
+            });
         }
 
+        // a union subtypes `other` when every one of its members does, this has
+        // to be checked before splitting `other` so nested unions line up
         if let Type::Or(left, right) = self {
             return left.is_sub_type_of(other) && right.is_sub_type_of(other);
         }
 
+        if let Type::Or(left, right) = other {
+            return self.is_sub_type_of(left) || self.is_sub_type_of(right);
+        }
+
+        if let Type::Interface(interface) = other {
+            return match self {
+                Type::Interface(self_interface) => self_interface.name == interface.name,
+                _ => interface.is_implemented_by(self),
+            };
+        }
+
+        // inside a generic function nothing is known about `T`, so it only
+        // subtypes itself
+        if let Type::Variable(name) = self {
+            return matches!(other, Type::Variable(other_name) if name == other_name);
+        }
+
         if let Type::Function(function_type) = self {
             if let Type::Function(other_function_type) = other {
                 return function_type.is_sub_type_of(other_function_type);
@@ -167,18 +737,387 @@ impl Type {
 
         if let Type::List(list_type) = self {
             if let Type::List(other_list_type) = other {
-                return list_type.is_sub_type_of(&other_list_type);
+                return list_type.is_sub_type_of(other_list_type);
+            }
+
+            return false;
+        }
+
+        if let Type::Tuple(element_types) = self {
+            if let Type::Tuple(other_element_types) = other {
+                return element_types.len() == other_element_types.len()
+                    && element_types.iter().zip(other_element_types.iter()).all(
+                        |(element_type, other_element_type)| {
+                            element_type.is_sub_type_of(other_element_type)
+                        },
+                    );
             }
 
             return false;
         }
 
+        if let Type::Named(name, arguments) = self {
+            let Type::Named(other_name, other_arguments) = other else {
+                return false;
+            };
+
+            // a name without arguments, e.g. `Optional`, accepts any arguments
+            return name == other_name
+                && (other_arguments.is_empty()
+                    || (arguments.len() == other_arguments.len()
+                        && arguments.iter().zip(other_arguments.iter()).all(
+                            |(argument, other_argument)| argument.is_sub_type_of(other_argument),
+                        )));
+        }
+
         if let Type::BaseType(base_type) = self {
             if let Type::BaseType(other_base_type) = other {
-                return base_type == other_base_type;
+                return base_type == other_base_type
+                    || (*base_type == BaseType::Integer && *other_base_type == BaseType::Number);
+            }
+        }
+
+        false
+    }
+}
+
+/// Writes the type the way it would be annotated in a program, for messages
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // function types and unions have to be grouped when nested, otherwise
+        // `((integer) => integer)[]` would read as a function returning a list
+        let grouped = |type_: &Type| match type_ {
+            Type::Or(..) | Type::Function(_) => format!("({})", type_),
+            _ => type_.to_string(),
+        };
+
+        match self {
+            Type::BaseType(base_type) => write!(f, "{}", base_type),
+            Type::Or(left, right) => {
+                let left = match left.as_ref() {
+                    Type::Function(_) => grouped(left),
+                    _ => left.to_string(),
+                };
+                write!(f, "{} | {}", left, grouped(right))
+            }
+            Type::List(element) => write!(f, "{}[]", grouped(element)),
+            Type::Tuple(elements) if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            Type::Tuple(elements) => write!(f, "({})", join(elements)),
+            Type::Named(name, arguments) | Type::Alias(AliasType { name, .. }, arguments) => {
+                match arguments.is_empty() {
+                    true => f.write_str(name),
+                    false => write!(f, "{}<{}>", name, join(arguments)),
+                }
+            }
+            Type::Variable(name) if Type::is_inferred(name) => f.write_str("_"),
+            Type::Variable(name) => f.write_str(name),
+            Type::Interface(interface) => f.write_str(&interface.name),
+            Type::Function(function) => write!(f, "{}", function),
+            Type::Return(type_) => write!(f, "{}", type_),
+        }
+    }
+}
+
+impl std::fmt::Display for FunctionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FunctionType::Literal(argument_types, return_type) => {
+                write!(f, "({}) => {}", join(argument_types), return_type)
+            }
+            FunctionType::ArrayArgs(argument_type, return_type) => {
+                write!(f, "(...{}) => {}", argument_type, return_type)
             }
+            FunctionType::WithBody(function_instance) => {
+                match function_instance.borrow().clone().get_type() {
+                    Ok(type_) => write!(f, "{}", type_),
+                    Err(_) => f.write_str("function"),
+                }
+            }
+        }
+    }
+}
+
+fn join(types: &[Type]) -> String {
+    types
+        .iter()
+        .map(|type_| type_.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use super::{AliasType, BaseType, FunctionType, InterfaceType, Type};
+
+    fn list(element: Type) -> Type {
+        Type::List(Box::from(element))
+    }
+
+    #[test]
+    fn empty_list_subtypes_every_list() {
+        let empty = list(Type::BaseType(BaseType::Never));
+        let numbers = list(Type::BaseType(BaseType::Number));
+
+        assert!(empty.is_sub_type_of(&numbers));
+        assert!(empty.is_sub_type_of(&list(numbers.clone())));
+        assert!(!numbers.is_sub_type_of(&empty));
+        assert!(!empty.is_sub_type_of(&Type::BaseType(BaseType::Number)));
+    }
+
+    #[test]
+    fn tuples_subtype_element_wise() {
+        let base = |base_type: BaseType| Type::BaseType(base_type);
+        let pair = Type::Tuple(vec![base(BaseType::Integer), base(BaseType::String)]);
+
+        assert!(pair.is_sub_type_of(&Type::Tuple(vec![
+            base(BaseType::Number),
+            base(BaseType::String)
+        ])));
+        assert!(!pair.is_sub_type_of(&Type::Tuple(vec![
+            base(BaseType::String),
+            base(BaseType::Integer)
+        ])));
+        assert!(!pair.is_sub_type_of(&Type::Tuple(vec![base(BaseType::Integer)])));
+        assert!(!pair.is_sub_type_of(&list(base(BaseType::Any))));
+    }
+
+    #[test]
+    fn infers_and_substitutes_type_parameters() {
+        let integer = Type::BaseType(BaseType::Integer);
+        let parameter = Type::Variable(String::from("T"));
+        let optional = |argument: Type| Type::Named(String::from("Optional"), vec![argument]);
+
+        let mut inferred = HashMap::new();
+        list(optional(parameter.clone()))
+            .infer_parameters(&list(optional(integer.clone())), &mut inferred);
+        assert!(matches!(
+            inferred.get("T"),
+            Some(Type::BaseType(BaseType::Integer))
+        ));
+
+        let substituted = Type::Tuple(vec![parameter, integer.clone()]).substitute(&inferred);
+        assert!(substituted.is_sub_type_of(&Type::Tuple(vec![integer.clone(), integer.clone()])));
+
+        assert!(
+            optional(integer.clone()).is_sub_type_of(&optional(Type::BaseType(BaseType::Number)))
+        );
+        assert!(!optional(Type::BaseType(BaseType::Number)).is_sub_type_of(&optional(integer)));
+    }
+
+    #[test]
+    fn applies_generic_functions() {
+        let integer = Type::BaseType(BaseType::Integer);
+        let string = Type::BaseType(BaseType::String);
+        let variable = |name: &str| Type::Variable(String::from(name));
+        let function = |arguments: Vec<Type>, return_type: Type| {
+            Type::Function(Box::from(FunctionType::Literal(arguments, return_type)))
+        };
+
+        // <T, U>(xs: T[], f: (T) => U): U[]
+        let Type::Function(map) = function(
+            vec![
+                list(variable("T")),
+                function(vec![variable("T")], variable("U")),
+            ],
+            list(variable("U")),
+        ) else {
+            unreachable!()
+        };
+
+        let mapped = map
+            .apply(vec![
+                list(integer.clone()),
+                function(vec![integer.clone()], string.clone()),
+            ])
+            .unwrap();
+        assert!(mapped.is_sub_type_of(&list(string.clone())));
+        assert!(list(string.clone()).is_sub_type_of(&mapped));
+
+        // the function has to accept the elements it's given
+        assert!(map
+            .apply(vec![
+                list(string.clone()),
+                function(vec![integer.clone()], string.clone()),
+            ])
+            .is_err());
+
+        // partially applied, `U` is still decided by the next argument
+        let Ok(Type::Function(partial)) = map.apply(vec![list(integer.clone())]) else {
+            panic!("expected a function")
+        };
+        let mapped = partial
+            .apply(vec![function(vec![integer.clone()], integer.clone())])
+            .unwrap();
+        assert!(mapped.is_sub_type_of(&list(integer)));
+
+        assert!(variable("T").is_sub_type_of(&variable("T")));
+        assert!(!variable("T").is_sub_type_of(&variable("U")));
+        assert!(!string.is_sub_type_of(&variable("T")));
+    }
+
+    #[test]
+    fn generic_functions_stand_in_for_their_instances() {
+        let integer = Type::BaseType(BaseType::Integer);
+        let string = Type::BaseType(BaseType::String);
+        let function = |arguments: Vec<Type>, return_type: Type| {
+            Type::Function(Box::from(FunctionType::Literal(arguments, return_type)))
+        };
+
+        // `(x) => x`
+        let identity = function(vec![Type::inferred("x")], Type::inferred("x"));
+        assert!(identity.is_sub_type_of(&function(vec![integer.clone()], integer.clone())));
+        assert!(identity.is_sub_type_of(&function(vec![string.clone()], string.clone())));
+        assert!(!identity.is_sub_type_of(&function(vec![integer.clone()], string.clone())));
+
+        assert!(identity.is_generic());
+        assert!(!function(vec![integer.clone()], string).is_generic());
+        assert!(list(Type::inferred("x")).is_generic());
+        assert!(!list(integer).is_generic());
+    }
+
+    #[test]
+    fn implementors_subtype_their_interfaces() {
+        let named = |name: &str| Type::Named(String::from(name), Vec::new());
+        let interface = Type::Interface(InterfaceType {
+            name: String::from("Area"),
+            methods: Vec::new(),
+            implementors: Rc::new(RefCell::new(vec![named("Circle")])),
+        });
+
+        assert!(named("Circle").is_sub_type_of(&interface));
+        assert!(!named("Point").is_sub_type_of(&interface));
+        assert!(!interface.is_sub_type_of(&named("Circle")));
+
+        if let Type::Interface(area) = &interface {
+            area.implementors.borrow_mut().push(named("Point"));
         }
+        assert!(
+            Type::Or(Box::from(named("Circle")), Box::from(named("Point")))
+                .is_sub_type_of(&interface)
+        );
+    }
+
+    #[test]
+    fn narrows_unions_to_matching_members() {
+        let integer = Type::BaseType(BaseType::Integer);
+        let string = Type::BaseType(BaseType::String);
+        let number_or_string = Type::Or(
+            Box::from(Type::BaseType(BaseType::Number)),
+            Box::from(string.clone()),
+        );
+
+        assert_eq!(number_or_string.members().len(), 2);
+        assert!(matches!(
+            number_or_string.narrow(&integer),
+            Some(Type::BaseType(BaseType::Integer))
+        ));
+        assert!(matches!(
+            number_or_string.narrow(&string),
+            Some(Type::BaseType(BaseType::String))
+        ));
+        assert!(integer.narrow(&string).is_none());
+
+        assert!(matches!(
+            number_or_string.exclude(&string),
+            Type::BaseType(BaseType::Number)
+        ));
+        // a number might not be an integer, so excluding integers keeps it
+        assert_eq!(number_or_string.exclude(&integer).members().len(), 2);
+        assert!(matches!(
+            string.exclude(&number_or_string),
+            Type::BaseType(BaseType::Never)
+        ));
+    }
+
+    #[test]
+    fn nested_unions_subtype_regardless_of_grouping() {
+        let base = |base_type: BaseType| Type::BaseType(base_type);
+        let or = |left: Type, right: Type| Type::Or(Box::from(left), Box::from(right));
+
+        let left = or(
+            or(base(BaseType::Integer), base(BaseType::String)),
+            base(BaseType::Null),
+        );
+        let right = or(
+            base(BaseType::Number),
+            or(base(BaseType::Null), base(BaseType::String)),
+        );
+
+        assert!(left.is_sub_type_of(&right));
+        assert!(!right.is_sub_type_of(&left));
+    }
+
+    #[test]
+    fn recursive_aliases_subtype_without_unfolding_forever() {
+        let integer = Type::BaseType(BaseType::Integer);
+        let or = |left: Type, right: Type| Type::Or(Box::from(left), Box::from(right));
+
+        // `type Tree = integer | Tree[]` and `type Nested<T> = T | Nested<T>[]`
+        let tree = AliasType::new(String::from("Tree"), Vec::new());
+        *tree.definition.borrow_mut() = Some(or(
+            integer.clone(),
+            list(Type::Alias(tree.clone(), Vec::new())),
+        ));
+        let nested = AliasType::new(String::from("Nested"), vec![String::from("T")]);
+        *nested.definition.borrow_mut() = Some(or(
+            Type::Variable(String::from("T")),
+            list(Type::Alias(
+                nested.clone(),
+                vec![Type::Variable(String::from("T"))],
+            )),
+        ));
+
+        let tree_type = tree.expand(&[]);
+        let nested_integers = nested.expand(&[Type::BaseType(BaseType::Integer)]);
+        let nested_strings = nested.expand(&[Type::BaseType(BaseType::String)]);
+
+        assert!(list(list(integer.clone())).is_sub_type_of(&tree_type));
+        assert!(!list(Type::BaseType(BaseType::String)).is_sub_type_of(&tree_type));
+        assert!(tree_type.is_sub_type_of(&nested_integers));
+        assert!(nested_integers.is_sub_type_of(&tree_type));
+        assert!(!tree_type.is_sub_type_of(&nested_strings));
+        assert!(!list(tree_type.clone()).is_sub_type_of(&list(integer)));
+    }
+
+    #[test]
+    fn displays_types_in_annotation_syntax() {
+        let integer = Type::BaseType(BaseType::Integer);
+        let or = |left: Type, right: Type| Type::Or(Box::from(left), Box::from(right));
+        let function = |arguments: Vec<Type>, return_type: Type| {
+            Type::Function(Box::from(FunctionType::Literal(arguments, return_type)))
+        };
+
+        let nullable = or(
+            Type::BaseType(BaseType::Number),
+            Type::BaseType(BaseType::Null),
+        );
+        assert_eq!(nullable.to_string(), "number | null");
+        assert_eq!(list(nullable.clone()).to_string(), "(number | null)[]");
+        assert_eq!(
+            Type::Tuple(vec![integer.clone(), Type::BaseType(BaseType::String)]).to_string(),
+            "(integer, string)"
+        );
+        assert_eq!(Type::Tuple(vec![integer.clone()]).to_string(), "(integer,)");
+        assert_eq!(
+            Type::Named(String::from("Optional"), vec![integer.clone()]).to_string(),
+            "Optional<integer>"
+        );
 
-        return false;
+        let increment = function(vec![integer.clone()], integer.clone());
+        assert_eq!(increment.to_string(), "(integer) => integer");
+        assert_eq!(
+            list(increment.clone()).to_string(),
+            "((integer) => integer)[]"
+        );
+        assert_eq!(
+            or(increment, Type::BaseType(BaseType::Null)).to_string(),
+            "((integer) => integer) | null"
+        );
+        assert_eq!(
+            function(vec![Type::inferred("x")], nullable).to_string(),
+            "(_) => number | null"
+        );
     }
 }
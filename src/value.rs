@@ -1,19 +1,29 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
 
 use crate::{
     callable::Callable,
-    data::list::List,
-    types::{BaseType, FunctionType, Type},
+    data::{enumeration::Variant, list::List, range::Range, structure::Struct, tuple::Tuple},
+    diagnostic::{Diagnostic, DiagnosticKind},
+    types::{BaseType, Type},
 };
 
 #[derive(Clone)]
 pub enum Value {
     Null,
     String(String),
-    Number(f32),
+    Integer(i64),
+    Number(f64),
     Boolean(bool),
     List(List),
+    Range(Range),
+    Tuple(Tuple),
+    Struct(Struct),
+    Enum(Variant),
     Return(Box<Value>),
+    /// Signals raised by `break` and `continue`, consumed by the loop with a
+    /// matching label (or the innermost loop when there is no label)
+    Break(Option<String>, Box<Value>),
+    Continue(Option<String>),
     Function(Rc<RefCell<dyn Callable>>),
 }
 
@@ -28,96 +38,188 @@ fn string_add<L: std::fmt::Display, R: std::fmt::Display>(left: L, right: R) ->
     format!("{}{}", left, right)
 }
 
-impl Value {
-    pub fn to_string(&self) -> String {
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Value::List(list) => list.to_string(),
-            Value::Return(_) => panic!("Cannot stringify return value"),
-            Value::Null => String::from("null"),
-            Value::Number(number) => number.to_string(),
-            Value::String(string) => format!("{}", string),
-            Value::Boolean(boolean) => format!(
-                "{}",
-                match boolean {
-                    true => "true",
-                    _ => "false",
-                }
-            ),
-            Value::Function(function) => format!("(fn:{})", function.borrow().signature()),
+            Value::List(list) => write!(f, "{}", list),
+            Value::Range(range) => write!(f, "{}", range),
+            Value::Tuple(tuple) => write!(f, "{}", tuple),
+            Value::Struct(structure) => write!(f, "{}", structure),
+            Value::Enum(variant) => write!(f, "{}", variant),
+            Value::Return(_) => f.write_str("return"),
+            Value::Break(..) => f.write_str("break"),
+            Value::Continue(_) => f.write_str("continue"),
+            Value::Null => f.write_str("null"),
+            Value::Integer(integer) => write!(f, "{}", integer),
+            Value::Number(number) => write!(f, "{:?}", number),
+            Value::String(string) => f.write_str(string),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+            Value::Function(function) => write!(f, "(fn:{})", function.borrow().signature()),
         }
     }
+}
 
+impl Value {
     pub fn to_log_string(&self) -> String {
         match self {
             Value::List(list) => list.to_string(),
-            Value::Return(_) => panic!("Cannot stringify return value"),
+            Value::Range(range) => range.to_string(),
+            Value::Tuple(tuple) => tuple.to_string(),
+            Value::Struct(structure) => structure.to_string(),
+            Value::Enum(variant) => variant.to_string(),
+            Value::Return(_) | Value::Break(..) | Value::Continue(_) => self.to_string(),
             Value::Null => String::from("null"),
-            Value::Number(number) => number.to_string(),
+            Value::Integer(integer) => integer.to_string(),
+            Value::Number(number) => format!("{:?}", number),
             Value::String(string) => format!("\"{}\"", string),
-            Value::Boolean(boolean) => format!(
-                "{}",
-                match boolean {
-                    true => "true",
-                    _ => "false",
-                }
-            ),
+            Value::Boolean(boolean) => (match boolean {
+                true => "true",
+                _ => "false",
+            })
+            .to_string(),
             Value::Function(function) => format!("Function: \"{}\"", function.borrow().signature()),
         }
     }
 
-    pub fn not(self) -> Result<Value, String> {
-        if let Value::Boolean(boolean) = self {
-            return Ok(Value::Boolean(!boolean));
+    /// Whether the value is a `return`, `break` or `continue` on its way out to
+    /// the block or loop it leaves, rather than a value of the program.
+    /// Expressions that use the values of their operands pass these on as
+    /// soon as one turns up, like `Body` does
+    pub fn is_signal(&self) -> bool {
+        matches!(
+            self,
+            Value::Return(_) | Value::Break(..) | Value::Continue(_)
+        )
+    }
+
+    /// Errors when the value is a signal, for the places that need a value of
+    /// the program, e.g. printing it
+    pub fn expect_value(self) -> Result<Value, Diagnostic> {
+        if self.is_signal() {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Internal,
+                format!("Cannot use `{}` as a value", self),
+            ));
         }
 
-        return Err("Cannot negate non bool".to_owned());
+        Ok(self)
     }
 
-    pub fn add(self, right: Value) -> Result<Value, String> {
-        Ok(match self {
-            Value::Number(left_num) => match right {
-                Value::Number(right_num) => Value::Number(left_num + right_num),
-                value => Value::String(string_add(left_num.to_string(), value.to_string())),
-            },
-            value => Value::String(string_add(value.to_string(), right.to_string())),
-        })
+    /// The elements of anything that can be iterated over, strings iterate
+    /// over their characters
+    pub fn elements(&self) -> Option<Box<dyn Iterator<Item = Value> + '_>> {
+        match self {
+            Value::List(list) => Some(Box::from(list.vector.iter().cloned())),
+            Value::Range(range) => Some(Box::from(range.iter())),
+            Value::String(string) => Some(Box::from(
+                string.chars().map(|char| Value::String(char.to_string())),
+            )),
+            _ => None,
+        }
     }
 
-    pub fn sub(self, right: Value) -> Result<Value, String> {
-        if let Value::Number(left_number) = self {
-            if let Value::Number(right_number) = right {
-                return Ok(Value::Number(left_number - right_number));
+    /// Whether the value belongs to `type_`, functions do not keep their full
+    /// type at runtime so any function matches any function type
+    pub fn is_instance_of(&self, type_: &Type) -> bool {
+        match (self, type_) {
+            (_, Type::BaseType(BaseType::Any)) => true,
+            (_, Type::Or(left, right)) => self.is_instance_of(left) || self.is_instance_of(right),
+            (Value::Null, Type::BaseType(BaseType::Null)) => true,
+            (Value::String(_), Type::BaseType(BaseType::String)) => true,
+            (Value::Integer(_), Type::BaseType(BaseType::Integer | BaseType::Number)) => true,
+            (Value::Number(_), Type::BaseType(BaseType::Number)) => true,
+            (Value::Boolean(_), Type::BaseType(BaseType::Boolean)) => true,
+            (Value::List(list), Type::List(element_type)) => list
+                .vector
+                .iter()
+                .all(|element| element.is_instance_of(element_type)),
+            (Value::Range(_), Type::List(element_type)) => {
+                Type::BaseType(BaseType::Integer).is_sub_type_of(element_type)
             }
+            (Value::Tuple(tuple), Type::Tuple(element_types)) => {
+                tuple.elements.len() == element_types.len()
+                    && tuple
+                        .elements
+                        .iter()
+                        .zip(element_types.iter())
+                        .all(|(element, element_type)| element.is_instance_of(element_type))
+            }
+            (Value::Struct(structure), Type::Named(name, _)) => structure.name == *name,
+            (Value::Enum(variant), Type::Named(name, _)) => variant.enum_name == *name,
+            (Value::Function(_), Type::Function(_)) => true,
+            // type variables are only known while type checking
+            (_, Type::Variable(_)) => true,
+            (_, Type::Alias(alias, arguments)) => self.is_instance_of(&alias.expand(arguments)),
+            (_, Type::Interface(interface)) => interface
+                .implementors
+                .borrow()
+                .iter()
+                .any(|implementor| self.is_instance_of(implementor)),
+            _ => false,
         }
+    }
 
-        return Err("Cannot subtract non number values".to_owned());
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Integer(integer) => Some(*integer as f64),
+            Value::Number(number) => Some(*number),
+            _ => None,
+        }
     }
 
-    pub fn mul(self, right: Value) -> Result<Value, String> {
-        if let Value::Number(left_number) = self {
-            if let Value::Number(right_number) = right {
-                return Ok(Value::Number(left_number * right_number));
-            }
+    /// Applies an arithmetic operator, integers stay integers (erroring on
+    /// overflow) and anything involving a float is computed as a float
+    fn arithmetic(
+        &self,
+        right: &Value,
+        verb: &str,
+        integer: fn(i64, i64) -> Option<i64>,
+        float: fn(f64, f64) -> f64,
+    ) -> Result<Option<Value>, Diagnostic> {
+        if let (Value::Integer(left_integer), Value::Integer(right_integer)) = (self, right) {
+            return match integer(*left_integer, *right_integer) {
+                Some(result) => Ok(Some(Value::Integer(result))),
+                None => Err(Diagnostic::error(
+                    DiagnosticKind::Runtime,
+                    format!(
+                        "Integer overflow when trying to {} {} and {}",
+                        verb, left_integer, right_integer
+                    ),
+                )),
+            };
         }
 
-        return Err("Cannot subtract non number values".to_owned());
+        Ok(match (self.as_float(), right.as_float()) {
+            (Some(left_number), Some(right_number)) => {
+                Some(Value::Number(float(left_number, right_number)))
+            }
+            _ => None,
+        })
     }
 
-    pub fn div(self, right: Value) -> Result<Value, String> {
-        if let Value::Number(left_number) = self {
-            if let Value::Number(right_number) = right {
-                return Ok(Value::Number(left_number / right_number));
-            }
+    fn compare(&self, right: &Value) -> Option<Ordering> {
+        if let (Value::Integer(left_integer), Value::Integer(right_integer)) = (self, right) {
+            return Some(left_integer.cmp(right_integer));
         }
 
-        return Err("Cannot subtract non number values".to_owned());
+        self.as_float()?.partial_cmp(&right.as_float()?)
     }
 
-    pub fn equals(self, right: Value) -> Result<Value, String> {
-        if let Value::Number(left_number) = self {
-            if let Value::Number(right_number) = right {
-                return Ok(Value::Boolean(left_number == right_number));
-            }
+    pub fn equals(self, right: Value) -> Result<Value, Diagnostic> {
+        if let Some(ordering) = self.compare(&right) {
+            return Ok(Value::Boolean(ordering == Ordering::Equal));
+        }
+
+        if let (Value::Null, Value::Null) = (&self, &right) {
+            return Ok(Value::Boolean(true));
+        }
+
+        if let (Value::Tuple(left_tuple), Value::Tuple(right_tuple)) = (&self, &right) {
+            return Ok(Value::Boolean(left_tuple.equals(right_tuple)?));
+        }
+
+        if let (Value::Enum(left_variant), Value::Enum(right_variant)) = (&self, &right) {
+            return Ok(Value::Boolean(left_variant.equals(right_variant)?));
         }
 
         if let Value::Boolean(left_bool) = self {
@@ -132,46 +234,191 @@ impl Value {
             }
         }
 
-        return Ok(Value::Boolean(false));
+        Ok(Value::Boolean(false))
     }
 
-    pub fn greater(self, right: Value) -> Result<Value, String> {
-        if let Value::Number(left_number) = self {
-            if let Value::Number(right_number) = right {
-                return Ok(Value::Boolean(left_number > right_number));
-            }
+    pub fn greater(self, right: Value) -> Result<Value, Diagnostic> {
+        if self.as_float().is_some() && right.as_float().is_some() {
+            return Ok(Value::Boolean(
+                self.compare(&right) == Some(Ordering::Greater),
+            ));
         }
 
-        return Err("Cannot compare (>) non number values".to_owned());
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot compare (>) non number values".to_owned(),
+        ))
     }
 
-    pub fn lesser(self, right: Value) -> Result<Value, String> {
-        if let Value::Number(left_number) = self {
-            if let Value::Number(right_number) = right {
-                return Ok(Value::Boolean(left_number < right_number));
-            }
+    pub fn lesser(self, right: Value) -> Result<Value, Diagnostic> {
+        if self.as_float().is_some() && right.as_float().is_some() {
+            return Ok(Value::Boolean(self.compare(&right) == Some(Ordering::Less)));
         }
 
-        return Err("Cannot compare (<) non number values".to_owned());
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot compare (<) non number values".to_owned(),
+        ))
     }
 
-    pub fn and(self, right: Value) -> Result<Value, String> {
+    pub fn and(self, right: Value) -> Result<Value, Diagnostic> {
         if let Value::Boolean(left_boolean) = self {
             if let Value::Boolean(right_boolean) = right {
                 return Ok(Value::Boolean(left_boolean && right_boolean));
             }
         }
 
-        return Err("Cannot and non boolean values".to_owned());
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot and non boolean values".to_owned(),
+        ))
     }
 
-    pub fn or(self, right: Value) -> Result<Value, String> {
+    pub fn or(self, right: Value) -> Result<Value, Diagnostic> {
         if let Value::Boolean(left_boolean) = self {
             if let Value::Boolean(right_boolean) = right {
                 return Ok(Value::Boolean(left_boolean || right_boolean));
             }
         }
 
-        return Err("Cannot or non boolean values".to_owned());
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot or non boolean values".to_owned(),
+        ))
+    }
+}
+
+impl std::ops::Add for Value {
+    type Output = Result<Value, Diagnostic>;
+
+    fn add(self, right: Value) -> Result<Value, Diagnostic> {
+        if let Some(result) = self.arithmetic(&right, "add", i64::checked_add, |l, r| l + r)? {
+            return Ok(result);
+        }
+
+        Ok(Value::String(string_add(
+            self.expect_value()?,
+            right.expect_value()?,
+        )))
+    }
+}
+
+impl std::ops::Sub for Value {
+    type Output = Result<Value, Diagnostic>;
+
+    fn sub(self, right: Value) -> Result<Value, Diagnostic> {
+        if let Some(result) = self.arithmetic(&right, "subtract", i64::checked_sub, |l, r| l - r)? {
+            return Ok(result);
+        }
+
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot subtract non number values".to_owned(),
+        ))
+    }
+}
+
+impl std::ops::Mul for Value {
+    type Output = Result<Value, Diagnostic>;
+
+    fn mul(self, right: Value) -> Result<Value, Diagnostic> {
+        if let Some(result) = self.arithmetic(&right, "multiply", i64::checked_mul, |l, r| l * r)? {
+            return Ok(result);
+        }
+
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot multiply non number values".to_owned(),
+        ))
+    }
+}
+
+impl std::ops::Div for Value {
+    type Output = Result<Value, Diagnostic>;
+
+    fn div(self, right: Value) -> Result<Value, Diagnostic> {
+        if let (Value::Integer(_), Value::Integer(0)) = (&self, &right) {
+            return Err(Diagnostic::error(
+                DiagnosticKind::Runtime,
+                "Cannot divide an integer by zero".to_owned(),
+            ));
+        }
+
+        if let Some(result) = self.arithmetic(&right, "divide", i64::checked_div, |l, r| l / r)? {
+            return Ok(result);
+        }
+
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot divide non number values".to_owned(),
+        ))
+    }
+}
+
+impl std::ops::Not for Value {
+    type Output = Result<Value, Diagnostic>;
+
+    fn not(self) -> Result<Value, Diagnostic> {
+        if let Value::Boolean(boolean) = self {
+            return Ok(Value::Boolean(!boolean));
+        }
+
+        Err(Diagnostic::error(
+            DiagnosticKind::Runtime,
+            "Cannot negate non bool".to_owned(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::{list::List, range::Range};
+
+    use super::Value;
+
+    #[test]
+    fn integer_arithmetic_stays_integer() {
+        let Value::Integer(sum) = (Value::Integer(16_777_217) + Value::Integer(1)).unwrap() else {
+            panic!("Expected integer addition to produce an integer");
+        };
+        assert_eq!(sum, 16_777_218);
+
+        let Value::Integer(quotient) = (Value::Integer(7) / Value::Integer(2)).unwrap() else {
+            panic!("Expected integer division to produce an integer");
+        };
+        assert_eq!(quotient, 3);
+
+        let Value::Number(mixed) = (Value::Integer(7) / Value::Number(2.0)).unwrap() else {
+            panic!("Expected mixed division to produce a float");
+        };
+        assert_eq!(mixed, 3.5);
+
+        assert!((Value::Integer(i64::MAX) + Value::Integer(1)).is_err());
+        assert!((Value::Integer(1) / Value::Integer(0)).is_err());
+    }
+
+    #[test]
+    fn iterates_lists_ranges_and_strings() {
+        let elements = |value: Value| {
+            value
+                .elements()
+                .unwrap()
+                .map(|element| element.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
+        assert_eq!(
+            elements(Value::List(List {
+                vector: vec![Value::Integer(1), Value::Boolean(true)]
+            })),
+            "1,true"
+        );
+        assert_eq!(
+            elements(Value::Range(Range::new(0, 6, 2, true).unwrap())),
+            "0,2,4,6"
+        );
+        assert_eq!(elements(Value::String(String::from("hé"))), "h,é");
+        assert!(Value::Integer(1).elements().is_none());
     }
 }